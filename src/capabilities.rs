@@ -0,0 +1,73 @@
+use crate::jj::CommandRunner;
+
+/// External tools available to jflow, probed once at command start
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Capabilities {
+    /// jj is installed
+    pub jj: bool,
+    /// gh CLI is installed
+    pub gh: bool,
+}
+
+impl Capabilities {
+    /// Probe for jj and gh using `runner`
+    pub fn probe(runner: &dyn CommandRunner) -> Self {
+        let jj = runner.run_success("jj", &["--version"]);
+        let gh = runner.run_success("gh", &["--version"]);
+
+        Self { jj, gh }
+    }
+
+    /// Whether gh is installed and logged in (`gh auth status`). It's a network
+    /// call, so it's only made when a command is about to need gh's login.
+    pub fn gh_authenticated(&self, runner: &dyn CommandRunner) -> bool {
+        self.gh && runner.run_success("gh", &["auth", "status"])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jj::runner::mock::MockRunner;
+
+    #[test]
+    fn test_probe_gh_missing() {
+        let runner = MockRunner::new();
+        runner.mock_response("jj --version", "jj 0.24.0");
+        runner.mock_error("gh --version", "No such file or directory");
+
+        let caps = Capabilities::probe(&runner);
+        assert!(caps.jj);
+        assert!(!caps.gh);
+        assert!(!caps.gh_authenticated(&runner));
+        // No auth check without gh
+        assert!(!runner.was_called("gh", &["auth", "status"]));
+    }
+
+    #[test]
+    fn test_probe_gh_available_and_authenticated() {
+        let runner = MockRunner::new();
+        runner.mock_response("jj --version", "jj 0.24.0");
+        runner.mock_response("gh --version", "gh version 2.40.0");
+        runner.mock_response("gh auth status", "Logged in to github.com");
+
+        let caps = Capabilities::probe(&runner);
+        assert!(caps.jj);
+        assert!(caps.gh);
+        // Probing doesn't check the login; that waits until gh is needed
+        assert!(!runner.was_called("gh", &["auth", "status"]));
+        assert!(caps.gh_authenticated(&runner));
+    }
+
+    #[test]
+    fn test_probe_gh_not_authenticated() {
+        let runner = MockRunner::new();
+        runner.mock_response("jj --version", "jj 0.24.0");
+        runner.mock_response("gh --version", "gh version 2.40.0");
+        runner.mock_error("gh auth status", "You are not logged into any GitHub hosts");
+
+        let caps = Capabilities::probe(&runner);
+        assert!(caps.gh);
+        assert!(!caps.gh_authenticated(&runner));
+    }
+}
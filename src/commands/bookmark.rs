@@ -0,0 +1,164 @@
+use anyhow::Result;
+
+use crate::config::Config;
+use crate::jj::{self, CommandRunner, RealRunner};
+use crate::ui::Renderer;
+
+/// Track `name@remote` so the stack shows the bookmark's sync state
+pub fn track(config: &Config, name: &str) -> Result<()> {
+    set_tracking(config, name, "track")
+}
+
+/// Stop tracking `name@remote`
+pub fn untrack(config: &Config, name: &str) -> Result<()> {
+    set_tracking(config, name, "untrack")
+}
+
+fn set_tracking(config: &Config, name: &str, action: &str) -> Result<()> {
+    let renderer = Renderer::from_config(config);
+    let runner = RealRunner::new(&config.tools);
+    let remote_ref = format!("{}@{}", full_bookmark_name(&config.bookmarks.prefix, name), config.remote.name);
+
+    if let Err(e) = jj::run_jj(&runner, &["bookmark", action, &remote_ref]) {
+        anyhow::bail!("Could not {} '{}': {}", action, remote_ref, e.to_string().trim());
+    }
+    let verb = if action == "track" { "Tracking" } else { "Stopped tracking" };
+    renderer.success(&format!("{} {}", verb, remote_ref));
+
+    // Show updated stack
+    let revset = config.stack_revset();
+    let stack = jj::get_stack(&runner, &revset, &config.remote.name)?;
+    renderer.render_stack(&stack, &config.trunk_ref());
+
+    Ok(())
+}
+
+/// Rename existing bookmarks under the configured prefix (and track them on the
+/// remote), listing the renames unless `apply` is set
+pub fn adopt(config: &Config, apply: bool) -> Result<()> {
+    let renderer = Renderer::from_config(config);
+    if config.bookmarks.prefix.is_empty() {
+        renderer.info("No [bookmarks] prefix is configured, so every bookmark already fits");
+        return Ok(());
+    }
+
+    let renames = adopt_with(&RealRunner::new(&config.tools), &renderer, config, apply)?;
+    if renames.is_empty() {
+        renderer.info(&format!("Every bookmark already starts with '{}'", config.bookmarks.prefix));
+    } else if !apply {
+        renderer.info("Dry run - run `jf adopt --apply` to rename them");
+    }
+    Ok(())
+}
+
+/// List (and with `apply`, perform) the renames that bring bookmarks under the
+/// prefix. Returns the (old, new) names.
+fn adopt_with(
+    runner: &dyn CommandRunner,
+    renderer: &Renderer,
+    config: &Config,
+    apply: bool,
+) -> Result<Vec<(String, String)>> {
+    let presence = jj::query_remote_presence_with(runner, &config.remote.name)?;
+    let names: Vec<&str> = presence.keys().map(String::as_str).collect();
+    let mut protected = vec![config.remote.primary.as_str()];
+    protected.extend(config.remote.base_override.as_deref());
+    let renames = adoptable_bookmarks(&names, &config.bookmarks.prefix, &protected);
+
+    for (old, new) in &renames {
+        if !apply {
+            println!("  {} -> {}", old, new);
+            continue;
+        }
+        if let Err(e) = runner.run("jj", &["bookmark", "rename", old, new]) {
+            anyhow::bail!("Could not rename '{}': {}", old, e.to_string().trim());
+        }
+        // Ignore errors - the new name isn't on the remote until it's pushed
+        let _ = runner.run("jj", &["bookmark", "track", &format!("{}@{}", new, config.remote.name)]);
+        renderer.success(&format!("Renamed {} -> {}", old, new));
+        if presence.get(old.as_str()).copied().unwrap_or(false) {
+            renderer.info(&format!(
+                "'{}' is still on {}; `jf push` publishes '{}' alongside it",
+                old, config.remote.name, new
+            ));
+        }
+    }
+    Ok(renames)
+}
+
+/// Bookmarks to bring under `prefix`, as (old, new) pairs: everything without the
+/// prefix except `protected` names (the primary branch) and wip bookmarks. A rename
+/// that would collide with an existing bookmark is left out.
+fn adoptable_bookmarks(names: &[&str], prefix: &str, protected: &[&str]) -> Vec<(String, String)> {
+    names
+        .iter()
+        .filter(|name| !name.starts_with(prefix) && !name.starts_with("wip/") && !protected.contains(name))
+        .map(|name| (name.to_string(), full_bookmark_name(prefix, name)))
+        .filter(|(_, new)| !names.contains(&new.as_str()))
+        .collect()
+}
+
+/// Apply the configured bookmark prefix unless `name` already has it
+fn full_bookmark_name(prefix: &str, name: &str) -> String {
+    if name.starts_with(prefix) {
+        name.to_string()
+    } else {
+        format!("{}{}", prefix, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_bookmark_name() {
+        assert_eq!(full_bookmark_name("", "feature"), "feature");
+        assert_eq!(full_bookmark_name("jf/", "feature"), "jf/feature");
+        assert_eq!(full_bookmark_name("jf/", "jf/feature"), "jf/feature");
+    }
+
+    #[test]
+    fn test_adoptable_bookmarks_from_mixed_set() {
+        let names = ["main", "jf/already", "feature", "fix-typo", "wip/alice", "taken", "jf/taken"];
+
+        let renames = adoptable_bookmarks(&names, "jf/", &["main"]);
+        assert_eq!(
+            renames,
+            [
+                ("feature".to_string(), "jf/feature".to_string()),
+                ("fix-typo".to_string(), "jf/fix-typo".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_adopt_lists_then_applies() {
+        use crate::jj::runner::mock::MockRunner;
+
+        let runner = MockRunner::new();
+        let list = format!("jj bookmark list --all -T {}", crate::jj::query::BOOKMARK_TEMPLATE);
+        runner.mock_response(
+            &list,
+            r#"{"name":"feature","remote":null,"change_id":"abc123","synced":false,"ahead":null,"behind":null,"conflict":false}
+{"name":"feature","remote":"origin","change_id":"abc123","synced":true,"ahead":0,"behind":0,"conflict":false}
+{"name":"main","remote":null,"change_id":"def456","synced":false,"ahead":null,"behind":null,"conflict":false}"#,
+        );
+        let mut config = Config::default();
+        config.bookmarks.prefix = "jf/".to_string();
+        let renderer = Renderer::new(crate::ui::get_theme("default"), crate::ui::get_icon_set("ascii")).with_quiet(true);
+
+        let renames = adopt_with(&runner, &renderer, &config, false).unwrap();
+        assert_eq!(renames, [("feature".to_string(), "jf/feature".to_string())]);
+        assert_eq!(runner.get_calls().len(), 1);
+
+        runner.mock_response("jj bookmark rename feature jf/feature", "");
+        runner.mock_error("jj bookmark track jf/feature@origin", "No such remote bookmark");
+        adopt_with(&runner, &renderer, &config, true).unwrap();
+        assert!(runner.was_called("jj", &["bookmark", "rename", "feature", "jf/feature"]));
+
+        runner.mock_error("jj bookmark rename feature jf/feature", "Bookmark already exists");
+        let err = adopt_with(&runner, &renderer, &config, true).unwrap_err();
+        assert!(err.to_string().contains("Could not rename 'feature'"));
+    }
+}
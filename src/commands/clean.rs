@@ -0,0 +1,65 @@
+use anyhow::Result;
+
+use crate::config::Config;
+use crate::jj::{self, CommandRunner, RealRunner};
+use crate::ui::Renderer;
+
+/// Abandon empty, description-less changes in the stack
+pub fn run(config: &Config, dry_run: bool) -> Result<()> {
+    let renderer = Renderer::from_config(config);
+    clean(&RealRunner::new(&config.tools), &renderer, &config.stack_revset(), dry_run)
+}
+
+fn clean(runner: &dyn CommandRunner, renderer: &Renderer, stack_revset: &str, dry_run: bool) -> Result<()> {
+    if dry_run {
+        let empty = jj::find_empty_in(runner, stack_revset)?;
+        if empty.is_empty() {
+            renderer.info("No empty changes to abandon");
+            return Ok(());
+        }
+        println!("\nDry run - would abandon:");
+        for change_id in &empty {
+            println!("  - {}", &change_id[..8.min(change_id.len())]);
+        }
+        return Ok(());
+    }
+
+    let abandoned = jj::abandon_empty_in(runner, stack_revset)?;
+    if abandoned.is_empty() {
+        renderer.info("No empty changes to abandon");
+    } else {
+        renderer.success(&format!("Abandoned {} empty change(s)", abandoned.len()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jj::runner::mock::MockRunner;
+
+    const EMPTY_QUERY: &str = "jj log -r ((::@ ~ ::main@origin) & empty() & description(exact:\"\")) ~ (@ | bookmarks() | conflicts()) --no-graph -T change_id ++ \"\\n\"";
+
+    fn test_renderer() -> Renderer {
+        Renderer::new(crate::ui::get_theme("default"), crate::ui::get_icon_set("ascii"))
+    }
+
+    #[test]
+    fn test_dry_run_abandons_nothing() {
+        let runner = MockRunner::new();
+        runner.mock_response(EMPTY_QUERY, "aaaaaaaa\n");
+
+        clean(&runner, &test_renderer(), "::@ ~ ::main@origin", true).unwrap();
+        assert!(!runner.was_called("jj", &["abandon", "aaaaaaaa"]));
+    }
+
+    #[test]
+    fn test_clean_abandons_empty_changes() {
+        let runner = MockRunner::new();
+        runner.mock_response(EMPTY_QUERY, "aaaaaaaa\n");
+        runner.mock_response("jj abandon aaaaaaaa", "");
+
+        clean(&runner, &test_renderer(), "::@ ~ ::main@origin", false).unwrap();
+        assert!(runner.was_called("jj", &["abandon", "aaaaaaaa"]));
+    }
+}
@@ -0,0 +1,40 @@
+use anyhow::Result;
+use colored::Colorize;
+use std::path::Path;
+
+use crate::config::Config;
+
+pub fn run(config: &Config, path_only: bool) -> Result<()> {
+    let global_path = Config::global_config_path().filter(|p| p.exists());
+    let local_path = Config::local_config_path();
+
+    if path_only {
+        print_path("global", global_path.as_deref());
+        print_path("local", local_path.as_deref());
+        return Ok(());
+    }
+
+    let global_toml = global_path.and_then(|p| std::fs::read_to_string(p).ok());
+    let local_toml = local_path.and_then(|p| std::fs::read_to_string(p).ok());
+    let entries = config.sources(global_toml.as_deref(), local_toml.as_deref());
+
+    println!("# Effective jflow configuration");
+    let mut current_section = "";
+    for entry in &entries {
+        if entry.section != current_section {
+            println!("\n[{}]", entry.section);
+            current_section = &entry.section;
+        }
+        let line = format!("{} = {}", entry.key, entry.value);
+        println!("{:<32} {}", line, format!("# {}", entry.source).dimmed());
+    }
+
+    Ok(())
+}
+
+fn print_path(label: &str, path: Option<&Path>) {
+    match path {
+        Some(path) => println!("{}: {}", label, path.display()),
+        None => println!("{}: {}", label, "(not found)".dimmed()),
+    }
+}
@@ -0,0 +1,46 @@
+use anyhow::{Context, Result};
+
+use crate::config::Config;
+use crate::jj::RealRunner;
+
+/// Show the diff of `revision` (default: @), streaming jj's output as it's produced
+pub fn run(config: &Config, revision: Option<&str>, stat: bool) -> Result<()> {
+    let revision = revision.unwrap_or("@");
+
+    let args = diff_args(revision, stat, colored::control::SHOULD_COLORIZE.should_colorize());
+    // Inherit stdout/stderr so large diffs aren't buffered in memory
+    let status = RealRunner::new(&config.tools)
+        .command("jj")
+        .args(&args)
+        .status()
+        .context("Failed to execute jj command")?;
+
+    if !status.success() {
+        anyhow::bail!("Could not diff '{}' ({})", revision, status);
+    }
+
+    Ok(())
+}
+
+/// Arguments for `jj diff`; jj's colors follow our `--color` decision
+fn diff_args(revision: &str, stat: bool, color: bool) -> Vec<&str> {
+    let mut args = vec!["diff", "-r", revision, "--color", if color { "always" } else { "never" }];
+    if stat {
+        args.push("--stat");
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_args() {
+        assert_eq!(diff_args("@", false, false), vec!["diff", "-r", "@", "--color", "never"]);
+        assert_eq!(
+            diff_args("abc", true, true),
+            vec!["diff", "-r", "abc", "--color", "always", "--stat"]
+        );
+    }
+}
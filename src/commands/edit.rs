@@ -0,0 +1,22 @@
+use anyhow::Result;
+
+use crate::config::Config;
+use crate::jj::{self, RealRunner};
+use crate::ui::Renderer;
+
+/// Move the working copy to `revision` (change ID or bookmark) and show the stack
+pub fn run(config: &Config, revision: &str) -> Result<()> {
+    let renderer = Renderer::from_config(config);
+    let runner = RealRunner::new(&config.tools);
+
+    if let Err(e) = jj::run_jj(&runner, &["edit", revision]) {
+        anyhow::bail!("Could not edit '{}': {}", revision, e.to_string().trim());
+    }
+
+    // Show updated stack
+    let revset = config.stack_revset();
+    let stack = jj::get_stack(&runner, &revset, &config.remote.name)?;
+    renderer.render_stack(&stack, &config.trunk_ref());
+
+    Ok(())
+}
@@ -3,25 +3,34 @@ use std::fs;
 use std::io::{self, Write};
 use std::path::Path;
 
+use crate::capabilities::Capabilities;
 use crate::config::Config;
-use crate::jj;
+use crate::jj::{self, RealRunner};
 use crate::ui::{get_icon_set, get_theme, Renderer};
 
-pub fn run(use_defaults: bool, create_github_repo: bool, force_local: bool) -> Result<()> {
+pub fn run(
+    runner: &RealRunner,
+    caps: &Capabilities,
+    use_defaults: bool,
+    create_github_repo: bool,
+    force_local: bool,
+) -> Result<()> {
     let theme = get_theme("default");
     let icons = get_icon_set("unicode");
     let renderer = Renderer::new(theme, icons);
 
     // Check if we're in a jj repo
-    jj::check_jj_available()?;
-    if !is_jj_repo() {
+    if !caps.jj {
+        anyhow::bail!("jj command not found. Please install jujutsu: https://github.com/martinvonz/jj");
+    }
+    if !is_jj_repo(runner) {
         renderer.error("Not in a jj repository. Run 'jj git init' first.");
         return Ok(());
     }
 
     // Create GitHub repo if requested
     if create_github_repo {
-        create_github_repository(&renderer)?;
+        create_github_repository(runner, caps, &renderer)?;
     }
 
     // Check if local .jflow.toml already exists
@@ -52,8 +61,8 @@ pub fn run(use_defaults: bool, create_github_repo: bool, force_local: bool) -> R
     println!("Initializing jflow...\n");
 
     // Detect repository settings
-    let detected_primary = detect_primary_branch()?;
-    let detected_remote = detect_default_remote()?;
+    let detected_primary = detect_primary_branch(runner)?;
+    let detected_remote = detect_default_remote(runner)?;
 
     // Get configuration from user or use defaults
     let (primary, remote, push_style, bookmark_prefix) = if use_defaults {
@@ -68,8 +77,21 @@ pub fn run(use_defaults: bool, create_github_repo: bool, force_local: bool) -> R
         get_interactive_config(detected_primary, detected_remote)?
     };
 
+    // Where PRs go, from the chosen remote's URL if it's on GitHub
+    let detected_repo = detect_github_repo(runner, &remote)?;
+    let github_repo = if use_defaults {
+        detected_repo
+    } else {
+        let repo = prompt(
+            "GitHub repository for PRs, as owner/name (leave empty to let gh decide)",
+            detected_repo.as_deref().unwrap_or(""),
+        )?;
+        Some(repo).filter(|r| !r.is_empty())
+    };
+
     // Create .jflow.toml
-    let config_content = create_config_content(&primary, &remote, &push_style, &bookmark_prefix);
+    let config_content =
+        create_config_content(&primary, &remote, &push_style, &bookmark_prefix, github_repo.as_deref());
 
     fs::write(".jflow.toml", config_content).context("Failed to write .jflow.toml")?;
 
@@ -77,7 +99,7 @@ pub fn run(use_defaults: bool, create_github_repo: bool, force_local: bool) -> R
     println!();
 
     // Show summary
-    print_summary(&primary, &remote, &push_style);
+    print_summary(&primary, &remote, &push_style, github_repo.as_deref());
 
     // Show next steps
     println!("\n{} Next steps:", icons.lightbulb);
@@ -89,24 +111,24 @@ pub fn run(use_defaults: bool, create_github_repo: bool, force_local: bool) -> R
     Ok(())
 }
 
-fn is_jj_repo() -> bool {
-    jj::run_jj(&["status"]).is_ok()
+fn is_jj_repo(runner: &RealRunner) -> bool {
+    jj::run_jj(runner, &["status"]).is_ok()
 }
 
-fn detect_primary_branch() -> Result<Option<String>> {
+fn detect_primary_branch(runner: &RealRunner) -> Result<Option<String>> {
     // Try common branch names
     for branch in &["main", "master", "trunk"] {
         let remote_ref = format!("{}@origin", branch);
-        if jj::run_jj(&["log", "-r", &remote_ref, "--limit", "1"]).is_ok() {
+        if jj::run_jj(runner, &["log", "-r", &remote_ref, "--limit", "1"]).is_ok() {
             return Ok(Some(branch.to_string()));
         }
     }
     Ok(None)
 }
 
-fn detect_default_remote() -> Result<Option<String>> {
+fn detect_default_remote(runner: &RealRunner) -> Result<Option<String>> {
     // Try to get remote list
-    let output = jj::run_jj(&["git", "remote", "list"])?;
+    let output = jj::run_jj(runner, &["git", "remote", "list"])?;
 
     // Parse output - format is "name url"
     for line in output.lines() {
@@ -118,6 +140,38 @@ fn detect_default_remote() -> Result<Option<String>> {
     Ok(None)
 }
 
+/// The GitHub "owner/name" that `remote` points at, if it's a GitHub URL
+fn detect_github_repo(runner: &RealRunner, remote: &str) -> Result<Option<String>> {
+    let output = jj::run_jj(runner, &["git", "remote", "list"])?;
+
+    for line in output.lines() {
+        let mut parts = line.split_whitespace();
+        if parts.next() == Some(remote) {
+            return Ok(parts.next().and_then(parse_github_repo));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Parse "owner/name" from a GitHub remote URL in https, scp-style ssh
+/// (`git@github.com:owner/name.git`), or `ssh://` form
+fn parse_github_repo(url: &str) -> Option<String> {
+    let path = url
+        .strip_prefix("https://github.com/")
+        .or_else(|| url.strip_prefix("http://github.com/"))
+        .or_else(|| url.strip_prefix("git@github.com:"))
+        .or_else(|| url.strip_prefix("ssh://git@github.com/"))
+        .or_else(|| url.strip_prefix("ssh://github.com/"))?;
+    let path = path.trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+
+    match path.split('/').collect::<Vec<_>>()[..] {
+        [owner, name] if !owner.is_empty() && !name.is_empty() => Some(format!("{}/{}", owner, name)),
+        _ => None,
+    }
+}
+
 fn get_interactive_config(
     detected_primary: Option<String>,
     detected_remote: Option<String>,
@@ -196,11 +250,23 @@ fn create_config_content(
     remote: &str,
     push_style: &str,
     bookmark_prefix: &str,
+    github_repo: Option<&str>,
 ) -> String {
+    let repo_line = match github_repo {
+        Some(repo) => format!("repo = \"{}\"", repo),
+        None => "# repo = \"owner/name\"".to_string(),
+    };
     format!(
         r#"# jflow configuration
 # Generated by jf init
 
+[general]
+# Command a bare `jf` runs: "status", "diff", or "config"
+default_command = "status"
+
+# Reuse the last stack in `jf status` until the repo changes (speeds up large repos)
+cache_stack = false
+
 [remote]
 # Remote name
 name = "{}"
@@ -208,6 +274,9 @@ name = "{}"
 # Primary branch name
 primary = "{}"
 
+# Measure the stack from another base instead of the primary branch (or --base)
+# base_override = "integration"
+
 [github]
 # Push style: "squash" (force-push) or "append" (incremental commits)
 push_style = "{}"
@@ -218,32 +287,64 @@ merge_style = "squash"
 # Add stack context to PR descriptions
 stack_context = true
 
+# Refuse to push a stack larger than this without confirmation
+max_stack = 20
+
+# Truncate longer PR titles with "…" (the body keeps the full subject; 0 disables)
+max_title_len = 72
+
+# Refuse to push changes whose subject isn't a conventional commit (feat:, fix(scope):, ...)
+require_conventional = false
+
+# Repository PRs are opened against (passed to gh; detected from the remote URL)
+{}
+
 [bookmarks]
 # Prefix for bookmarks (e.g., "jf/" creates bookmarks like "jf/my-feature")
 prefix = "{}"
+
+[network]
+# Retries for transient failures of push/fetch/gh commands
+retries = 3
+
+# Kill a push/fetch/gh command that hangs longer than this (0 disables)
+timeout_secs = 300
+
+[land]
+# Abandon empty, description-less changes after landing
+prune_empty = true
+
+[wip]
+# Add a hash of user.email to the wip bookmark, for people sharing a user.name
+disambiguate = false
 "#,
-        remote, primary, push_style, bookmark_prefix
+        remote, primary, push_style, repo_line, bookmark_prefix
     )
 }
 
-fn print_summary(primary: &str, remote: &str, push_style: &str) {
+fn print_summary(primary: &str, remote: &str, push_style: &str, github_repo: Option<&str>) {
     println!("Configuration Summary:");
     println!("  Remote: {}", remote);
     println!("  Primary branch: {}", primary);
     println!("  Push style: {}", push_style);
+    if let Some(repo) = github_repo {
+        println!("  GitHub repo: {}", repo);
+    }
 }
 
-fn create_github_repository(renderer: &Renderer) -> Result<()> {
-    use std::process::Command;
-
+fn create_github_repository(runner: &RealRunner, caps: &Capabilities, renderer: &Renderer) -> Result<()> {
     // Check if gh is available
-    if Command::new("gh").arg("--version").output().is_err() {
+    if !caps.gh {
         renderer.error("gh CLI not found. Install it from https://cli.github.com/");
         return Ok(());
     }
+    if !caps.gh_authenticated(runner) {
+        renderer.error("gh CLI is not logged in. Run 'gh auth login' first.");
+        return Ok(());
+    }
 
     // Check if remote already exists
-    if detect_default_remote()?.is_some() {
+    if detect_default_remote(runner)?.is_some() {
         renderer.info("Remote already configured, skipping GitHub repo creation");
         return Ok(());
     }
@@ -258,7 +359,8 @@ fn create_github_repository(renderer: &Renderer) -> Result<()> {
     renderer.info(&format!("Creating GitHub repository '{}'...", repo_name));
 
     // Create repo with gh CLI (private by default, with source set to current dir)
-    let output = Command::new("gh")
+    let output = runner
+        .command("gh")
         .args(["repo", "create", repo_name, "--private", "--source", ".", "--remote", "origin"])
         .output()?;
 
@@ -267,7 +369,8 @@ fn create_github_repository(renderer: &Renderer) -> Result<()> {
 
         // Push main branch to set up tracking
         renderer.info("Pushing main branch...");
-        let push_output = Command::new("jj")
+        let push_output = runner
+            .command("jj")
             .args(["git", "push", "--named", "main=@-"])
             .output()?;
 
@@ -275,7 +378,8 @@ fn create_github_repository(renderer: &Renderer) -> Result<()> {
             renderer.success("Main branch pushed to origin");
         } else {
             // Try alternative: push current commit as main
-            let _ = Command::new("git")
+            let _ = runner
+                .command("git")
                 .args(["push", "-u", "origin", "HEAD:main"])
                 .output();
         }
@@ -286,3 +390,40 @@ fn create_github_repository(renderer: &Renderer) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_github_repo_url_formats() {
+        for url in [
+            "https://github.com/acme/widgets.git",
+            "https://github.com/acme/widgets",
+            "https://github.com/acme/widgets/",
+            "git@github.com:acme/widgets.git",
+            "git@github.com:acme/widgets",
+            "ssh://git@github.com/acme/widgets.git",
+        ] {
+            assert_eq!(parse_github_repo(url).as_deref(), Some("acme/widgets"), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_parse_github_repo_rejects_other_urls() {
+        assert_eq!(parse_github_repo("https://gitlab.com/acme/widgets.git"), None);
+        assert_eq!(parse_github_repo("git@github.com:acme.git"), None);
+        assert_eq!(parse_github_repo("https://github.com/acme/widgets/tree/main"), None);
+        assert_eq!(parse_github_repo("/srv/git/widgets.git"), None);
+    }
+
+    #[test]
+    fn test_config_content_with_repo_parses() {
+        let content = create_config_content("main", "origin", "squash", "", Some("acme/widgets"));
+        let config = Config::from_toml(&content).unwrap();
+        assert_eq!(config.github.repo.as_deref(), Some("acme/widgets"));
+
+        let content = create_config_content("main", "origin", "squash", "", None);
+        assert_eq!(Config::from_toml(&content).unwrap().github.repo, None);
+    }
+}
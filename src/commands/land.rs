@@ -1,169 +1,604 @@
-use anyhow::{Context, Result};
-use std::process::Command;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
 
 use crate::config::Config;
-use crate::jj;
-use crate::ui::{get_icon_set, get_theme, Renderer};
+use crate::jj::{self, CommandRunner, RealRunner};
+use crate::state::State;
+use crate::ui::Renderer;
 
-pub fn run(config: &Config, bookmark: Option<&str>, dry_run: bool) -> Result<()> {
-    let theme = get_theme(&config.display.theme);
-    let icons = get_icon_set(&config.display.icons);
-    let renderer = Renderer::new(theme, icons);
+/// Result of rebasing the stack and cleaning up merged bookmarks
+#[derive(Debug, PartialEq, Eq)]
+enum LandOutcome {
+    Done(LandReport),
+    /// The rebase left conflicts; nothing was deleted
+    Conflicted,
+}
+
+/// How a land ended, so `--json` consumers can tell outcomes apart
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LandStatus {
+    #[default]
+    Landed,
+    /// No merged PRs were found
+    NothingMerged,
+    /// The requested bookmark's PR isn't merged yet
+    NotMerged,
+    /// `--continue` with no land in progress
+    NothingToContinue,
+    /// A previous land stopped on conflicts and hasn't been continued
+    InProgress,
+    /// The rebase (or the stack being continued) has conflicts
+    Conflicted,
+    /// `--dry-run`: `landed` lists what would be landed; nothing was changed
+    DryRun,
+}
+
+/// What a land cleaned up, printed as a summary (or JSON with `--json`)
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct LandReport {
+    pub status: LandStatus,
+    /// Merged bookmarks that were landed
+    pub landed: Vec<String>,
+    /// Local bookmarks deleted
+    pub deleted_local: Vec<String>,
+    /// Remote branches deleted (already-deleted branches aren't counted)
+    pub deleted_remote: Vec<String>,
+    /// Empty, description-less changes abandoned
+    pub pruned: usize,
+    /// Changes left in the stack afterwards
+    pub stack_depth: usize,
+}
+
+/// Options for `jf land`
+#[derive(Debug, Default)]
+pub struct LandOptions<'a> {
+    /// Specific bookmark to land (default: auto-detect merged)
+    pub bookmark: Option<&'a str>,
+    pub dry_run: bool,
+    /// Keep empty changes (overrides `[land] prune_empty`)
+    pub no_prune: bool,
+    /// Finish a land that stopped on conflicts
+    pub continue_land: bool,
+    /// Print the report as JSON instead of a summary and the stack
+    pub json: bool,
+}
+
+pub fn run(config: &Config, options: &LandOptions) -> Result<()> {
+    let LandOptions {
+        bookmark,
+        dry_run,
+        no_prune,
+        continue_land,
+        json,
+    } = *options;
+    let renderer = Renderer::from_config(config).with_quiet(json);
+    let runner = super::network_runner(config, &renderer);
+    let gh_repo = config.github.repo.as_deref();
+    let prune = should_prune(config, no_prune);
+
+    let state_path = State::path()?;
+    let _lock = State::lock(&state_path)?;
+    let mut state = State::load_from(&state_path)?;
+
+    if continue_land {
+        if state.land_pending.is_empty() {
+            renderer.info("No land in progress");
+            return print_status(LandStatus::NothingToContinue, json);
+        }
+        if has_conflicts(&runner, &config.stack_revset())? {
+            renderer.error("The stack still has conflicts. Resolve them, then run `jf land --continue`.");
+            print_status(LandStatus::Conflicted, json)?;
+            anyhow::bail!("The stack still has conflicts");
+        }
+        let pending = std::mem::take(&mut state.land_pending);
+        retarget_dependent_prs(&runner, &renderer, gh_repo, &pending, &config.remote.primary);
+        let stack_revset = config.stack_revset();
+        let report = finish_land(&runner, &renderer, &config.remote.name, &stack_revset, &pending, prune)?;
+        state.save_to(&state_path)?;
+        return report_land(config, &renderer, report, json);
+    }
+
+    if !state.land_pending.is_empty() {
+        renderer.error("A land is in progress. Resolve conflicts, then run `jf land --continue`.");
+        print_status(LandStatus::InProgress, json)?;
+        anyhow::bail!("A land is already in progress");
+    }
 
     // Fetch latest from remote
     renderer.info(&format!("Fetching from {}...", config.remote.name));
-    jj::run_jj(&["git", "fetch", "--remote", &config.remote.name])?;
+    renderer.with_spinner("fetching", || {
+        runner.run("jj", &["git", "fetch", "--remote", &config.remote.name])
+    })?;
 
     // Find merged bookmarks
     let merged_bookmarks = if let Some(b) = bookmark {
         // Check if specific bookmark is merged
-        if is_pr_merged(b)? {
+        if is_pr_merged(&runner, gh_repo, b) {
             vec![b.to_string()]
         } else {
             renderer.info(&format!("PR for '{}' is not merged yet", b));
-            return Ok(());
+            return print_status(LandStatus::NotMerged, json);
         }
     } else {
         // Auto-detect merged PRs
-        find_merged_bookmarks(config)?
+        let local_bookmarks = jj::query_local_bookmark_names(&RealRunner::new(&config.tools))?;
+        find_merged_bookmarks(&runner, gh_repo, local_bookmarks)
     };
 
     if merged_bookmarks.is_empty() {
         renderer.info("No merged PRs found to clean up");
-        return Ok(());
+        return print_status(LandStatus::NothingMerged, json);
     }
 
     renderer.info(&format!("Found {} merged PR(s)", merged_bookmarks.len()));
 
+    if dry_run && json {
+        let report = LandReport {
+            status: LandStatus::DryRun,
+            landed: merged_bookmarks,
+            ..LandReport::default()
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
     if dry_run {
         println!("\nDry run - would clean up:");
-        for b in &merged_bookmarks {
-            println!("  - {}", b);
+        for line in dry_run_plan(&merged_bookmarks, prune) {
+            println!("  - {}", line);
         }
         return Ok(());
     }
 
-    // Delete merged bookmarks (both local and remote)
-    for b in &merged_bookmarks {
+    let target = LandTarget {
+        remote: &config.remote.name,
+        trunk_ref: &config.trunk_ref(),
+        stack_revset: &config.stack_revset(),
+        primary: &config.remote.primary,
+        gh_repo,
+    };
+    let outcome = land_bookmarks(&runner, &renderer, &target, &merged_bookmarks, prune)?;
+    match outcome {
+        LandOutcome::Done(report) => report_land(config, &renderer, report, json),
+        LandOutcome::Conflicted => {
+            // Remember what's left so `--continue` can finish once conflicts are resolved
+            state.land_pending = merged_bookmarks;
+            state.save_to(&state_path)?;
+            renderer.error("Rebasing the stack left conflicts; no bookmarks were deleted.");
+            renderer.info("Resolve the conflicts, then run `jf land --continue`");
+            print_status(LandStatus::Conflicted, json)?;
+            anyhow::bail!("Land stopped on conflicts");
+        }
+    }
+}
+
+/// Fill in the stack depth, then print the report: a summary and the stack, or JSON
+fn report_land(config: &Config, renderer: &Renderer, mut report: LandReport, json: bool) -> Result<()> {
+    let stack = jj::get_stack(&RealRunner::new(&config.tools), &config.stack_revset(), &config.remote.name)?;
+    report.stack_depth = stack.len();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!();
+    let (headline, details) = summary_lines(&report);
+    renderer.success(&headline);
+    for line in details {
+        renderer.info(&line);
+    }
+
+    println!();
+    renderer.render_stack(&stack, &config.trunk_ref());
+    Ok(())
+}
+
+/// Print an empty report carrying only `status` when `--json` is set
+fn print_status(status: LandStatus, json: bool) -> Result<()> {
+    if json {
+        let report = LandReport {
+            status,
+            ..LandReport::default()
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(())
+}
+
+/// Summary of a land: a headline, then one line per kind of cleanup
+fn summary_lines(report: &LandReport) -> (String, Vec<String>) {
+    let headline = format!("Landed {} bookmark(s)", report.landed.len());
+    let list = |names: &[String]| if names.is_empty() { "none".to_string() } else { names.join(", ") };
+    let details = vec![
+        format!("Deleted local bookmarks: {}", list(&report.deleted_local)),
+        format!("Deleted remote branches: {}", list(&report.deleted_remote)),
+        format!("Pruned {} empty change(s)", report.pruned),
+        format!("Stack depth: {}", report.stack_depth),
+    ];
+    (headline, details)
+}
+
+/// Where a land happens: the remote and trunk, the stack being rebased, and the
+/// GitHub repo whose PRs are retargeted
+struct LandTarget<'a> {
+    remote: &'a str,
+    trunk_ref: &'a str,
+    stack_revset: &'a str,
+    /// Branch dependent PRs are retargeted to
+    primary: &'a str,
+    gh_repo: Option<&'a str>,
+}
+
+/// Rebase the stack onto trunk, then retarget dependent PRs, delete `bookmarks`,
+/// and prune only if the rebase is clean, so a refused or conflicted land never
+/// leaves a half-landed stack (or PRs pointed away from branches that still exist)
+fn land_bookmarks(
+    runner: &dyn CommandRunner,
+    renderer: &Renderer,
+    target: &LandTarget,
+    bookmarks: &[String],
+    prune: bool,
+) -> Result<LandOutcome> {
+    check_not_on_working_copy(runner, target.trunk_ref, bookmarks)?;
+
+    renderer.info(&format!("Rebasing stack onto {}...", target.trunk_ref));
+    if let Err(e) = runner.run("jj", &["rebase", "-d", target.trunk_ref]) {
+        renderer.info(&format!("Note: Rebase skipped or failed: {}", e));
+    }
+
+    if has_conflicts(runner, target.stack_revset)? {
+        return Ok(LandOutcome::Conflicted);
+    }
+
+    // Before any branch is deleted: GitHub closes PRs whose base branch disappears
+    retarget_dependent_prs(runner, renderer, target.gh_repo, bookmarks, target.primary);
+    let report = finish_land(runner, renderer, target.remote, target.stack_revset, bookmarks, prune)?;
+    Ok(LandOutcome::Done(report))
+}
+
+/// Delete merged bookmarks (local and remote) and prune empty changes.
+/// The returned report leaves `stack_depth` for the caller to fill in.
+fn finish_land(
+    runner: &dyn CommandRunner,
+    renderer: &Renderer,
+    remote: &str,
+    stack_revset: &str,
+    bookmarks: &[String],
+    prune: bool,
+) -> Result<LandReport> {
+    let mut report = LandReport {
+        landed: bookmarks.to_vec(),
+        ..LandReport::default()
+    };
+
+    for b in bookmarks {
         renderer.info(&format!("Deleting bookmark '{}'...", b));
 
         // Delete remote branch on GitHub first
-        let delete_result = Command::new("git")
-            .args(["push", &config.remote.name, "--delete", b])
-            .output();
+        let delete_result = renderer.with_spinner("deleting", || {
+            runner.run("git", &["push", remote, "--delete", b])
+        });
 
         match delete_result {
-            Ok(output) if output.status.success() => {
+            Ok(_) => {
                 renderer.info(&format!("Deleted remote branch '{}'", b));
+                report.deleted_remote.push(b.clone());
             }
-            Ok(_) => {
+            Err(_) => {
                 // Branch might already be deleted on remote (GitHub auto-deletes after merge)
                 renderer.info(&format!("Remote branch '{}' already deleted or not found", b));
             }
-            Err(e) => {
-                renderer.info(&format!("Note: Could not delete remote branch: {}", e));
-            }
         }
 
         // Delete local bookmark
-        if let Err(e) = jj::run_jj(&["bookmark", "delete", b]) {
-            renderer.info(&format!("Note: Could not delete local bookmark: {}", e));
+        match runner.run("jj", &["bookmark", "delete", b]) {
+            Ok(_) => report.deleted_local.push(b.clone()),
+            Err(e) => renderer.info(&format!("Note: Could not delete local bookmark: {}", e)),
         }
     }
 
-    // Rebase remaining stack onto trunk
-    let trunk_ref = config.trunk_ref();
-    renderer.info(&format!("Rebasing stack onto {}...", trunk_ref));
-    if let Err(e) = jj::run_jj(&["rebase", "-d", &trunk_ref]) {
-        renderer.info(&format!("Note: Rebase skipped or failed: {}", e));
+    if prune {
+        report.pruned = jj::abandon_empty_in(runner, stack_revset)?.len();
     }
+    Ok(report)
+}
 
-    renderer.success("Cleanup complete!");
-
-    // Abandon any empty commits in the stack that have no description
-    // This cleans up orphaned empty commits left after landing
-    let empty_commits = jj::run_jj(&[
-        "log",
-        "-r",
-        &format!("({}) & empty() & description(exact:\"\")", config.stack_revset()),
-        "--no-graph",
-        "-T",
-        "change_id ++ \"\\n\"",
-    ])?;
-
-    for change_id in empty_commits.lines() {
-        let change_id = change_id.trim();
-        if !change_id.is_empty() && change_id != "@" {
-            // Don't abandon current working copy
-            let is_working_copy = jj::run_jj(&["log", "-r", "@", "--no-graph", "-T", "change_id"])?;
-            if change_id != is_working_copy.trim() {
-                let _ = jj::run_jj(&["abandon", change_id]);
-            }
-        }
+/// Refuse to land a bookmark on `@`: deleting it and rebasing would pull the
+/// working copy out from under the user
+fn check_not_on_working_copy(runner: &dyn CommandRunner, trunk_ref: &str, bookmarks: &[String]) -> Result<()> {
+    let output = runner.run(
+        "jj",
+        &["log", "-r", "@", "--no-graph", "-T", "local_bookmarks.map(|b| b.name()).join(\"\\n\")"],
+    )?;
+    if let Some(bookmark) = bookmarks.iter().find(|b| output.lines().any(|line| line.trim() == b.as_str())) {
+        anyhow::bail!(
+            "Can't land '{}': it's on the working copy (@). Run `jj new {}` to move off it, then land again",
+            bookmark,
+            trunk_ref
+        );
     }
+    Ok(())
+}
 
-    println!();
+/// Check whether any change in `stack_revset` has conflicts
+fn has_conflicts(runner: &dyn CommandRunner, stack_revset: &str) -> Result<bool> {
+    Ok(!jj::find_conflicts_in(runner, stack_revset)?.is_empty())
+}
 
-    // Show updated stack
-    let revset = config.stack_revset();
-    let stack = jj::get_stack(&revset, &config.remote.name)?;
-    renderer.render_stack(&stack, &config.trunk_ref());
+/// Prune empty changes unless disabled by `[land] prune_empty` or `--no-prune`
+fn should_prune(config: &Config, no_prune: bool) -> bool {
+    config.land.prune_empty && !no_prune
+}
 
-    Ok(())
+/// Describe what a land would do, one step per line
+fn dry_run_plan(merged_bookmarks: &[String], prune: bool) -> Vec<String> {
+    let mut plan: Vec<String> = merged_bookmarks.to_vec();
+    if prune {
+        plan.push("abandon empty changes without a description".to_string());
+    }
+    plan
+}
+
+/// An open PR from `gh pr list --json headRefName,baseRefName`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OpenPr {
+    head_ref_name: String,
+    base_ref_name: String,
 }
 
-fn is_pr_merged(bookmark: &str) -> Result<bool> {
-    let output = Command::new("gh")
-        .args(["pr", "view", bookmark, "--json", "state", "-q", ".state"])
-        .output()
-        .context("Failed to check PR state")?;
+/// Point open PRs stacked on a landed branch at `primary`, so they survive the
+/// landed branches being deleted. Best-effort: failures are reported, not fatal.
+/// Returns the heads of the retargeted PRs.
+fn retarget_dependent_prs(
+    runner: &dyn CommandRunner,
+    renderer: &Renderer,
+    repo: Option<&str>,
+    landed: &[String],
+    primary: &str,
+) -> Vec<String> {
+    let open: Vec<OpenPr> = match runner
+        .run(
+            "gh",
+            &super::gh_args(repo, &["pr", "list", "--state", "open", "--json", "headRefName,baseRefName", "--limit", "200"]),
+        )
+        .and_then(|output| Ok(serde_json::from_str(&output)?))
+    {
+        Ok(open) => open,
+        Err(e) => {
+            renderer.warning(&format!("Could not check for PRs stacked on landed branches: {}", e));
+            return Vec::new();
+        }
+    };
 
-    if output.status.success() {
-        let state = String::from_utf8_lossy(&output.stdout).trim().to_lowercase();
-        return Ok(state == "merged");
+    let mut retargeted = Vec::new();
+    for pr in open.iter().filter(|pr| landed.contains(&pr.base_ref_name)) {
+        let edit = super::gh_args(repo, &["pr", "edit", &pr.head_ref_name, "--base", primary]);
+        match runner.run("gh", &edit) {
+            Ok(_) => {
+                renderer.info(&format!("Retargeted PR for '{}' from {} to {}", pr.head_ref_name, pr.base_ref_name, primary));
+                retargeted.push(pr.head_ref_name.clone());
+            }
+            Err(e) => renderer.warning(&format!("Could not retarget PR for '{}': {}", pr.head_ref_name, e)),
+        }
     }
-    Ok(false)
+    retargeted
 }
 
-fn find_merged_bookmarks(_config: &Config) -> Result<Vec<String>> {
-    // Get all local bookmarks by parsing `jj bookmark list`
-    // We need to find bookmarks whose PRs are merged, regardless of where they point
-    let output = jj::run_jj(&["bookmark", "list"])?;
+/// Whether `bookmark`'s PR is merged; no PR (or no gh) counts as not merged
+fn is_pr_merged(runner: &dyn CommandRunner, repo: Option<&str>, bookmark: &str) -> bool {
+    runner
+        .run("gh", &super::gh_args(repo, &["pr", "view", bookmark, "--json", "state", "-q", ".state"]))
+        .is_ok_and(|state| state.trim().eq_ignore_ascii_case("merged"))
+}
 
-    let mut merged = Vec::new();
+/// The local `bookmarks` whose PR is merged
+fn find_merged_bookmarks(runner: &dyn CommandRunner, repo: Option<&str>, bookmarks: Vec<String>) -> Vec<String> {
+    // Check every local bookmark's PR, regardless of where the bookmark points
+    bookmarks
+        .into_iter()
+        .filter(|bookmark| is_pr_merged(runner, repo, bookmark))
+        .collect()
+}
 
-    for line in output.lines() {
-        // Parse bookmark name (first word on line, before any ':' or whitespace)
-        // Lines look like: "update-land: rkmvnysy 0f03385b Update jf land"
-        // Or: "feat-reorder-wip (deleted)"
-        let line = line.trim();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jj::runner::mock::MockRunner;
 
-        // Skip indented lines (they're remote tracking info like "@origin:")
-        if line.starts_with('@') {
-            continue;
-        }
+    const EMPTY_QUERY: &str = "jj log -r ((::@ ~ ::main@origin) & empty() & description(exact:\"\")) ~ (@ | bookmarks() | conflicts()) --no-graph -T change_id ++ \"\\n\"";
 
-        // Skip already-deleted bookmarks
-        if line.contains("(deleted)") {
-            continue;
-        }
+    const OPEN_PRS: &str = "gh pr list --state open --json headRefName,baseRefName --limit 200";
 
-        // Extract bookmark name
-        let bookmark = line
-            .split(&[':', ' ', '\t'][..])
-            .next()
-            .unwrap_or("")
-            .trim();
+    #[test]
+    fn test_land_retargets_dependent_prs() {
+        let runner = MockRunner::new();
+        runner.mock_response(
+            OPEN_PRS,
+            r#"[{"headRefName":"feature-b","baseRefName":"feature-a"},
+                {"headRefName":"feature-c","baseRefName":"feature-b"},
+                {"headRefName":"unrelated","baseRefName":"main"}]"#,
+        );
+        runner.mock_response("gh pr edit feature-b --base main", "");
 
-        if bookmark.is_empty() || bookmark.contains('@') {
-            continue;
-        }
+        let retargeted =
+            retarget_dependent_prs(&runner, &test_renderer(), None, &["feature-a".to_string()], "main");
 
-        // Check if this bookmark's PR is merged
-        if is_pr_merged(bookmark).unwrap_or(false) {
-            merged.push(bookmark.to_string());
-        }
+        assert_eq!(retargeted, ["feature-b"]);
+        assert!(runner.was_called("gh", &["pr", "edit", "feature-b", "--base", "main"]));
+        // Only PRs based on the landed branch move; feature-c still stacks on feature-b
+        let edits = runner.get_calls().into_iter().filter(|(_, args)| args[1] == "edit").count();
+        assert_eq!(edits, 1);
     }
 
-    Ok(merged)
+    #[test]
+    fn test_land_retarget_without_gh_is_not_fatal() {
+        let runner = MockRunner::new();
+        runner.mock_error(OPEN_PRS, "gh: command not found");
+
+        let retargeted =
+            retarget_dependent_prs(&runner, &test_renderer(), None, &["feature-a".to_string()], "main");
+        assert!(retargeted.is_empty());
+    }
+
+    #[test]
+    fn test_is_pr_merged_targets_configured_repo() {
+        let runner = MockRunner::new();
+        runner.mock_response("gh pr view feature --json state -q .state --repo acme/widgets", "MERGED\n");
+        runner.mock_response("gh pr view feature --json state -q .state", "OPEN\n");
+
+        assert!(is_pr_merged(&runner, Some("acme/widgets"), "feature"));
+        assert!(!is_pr_merged(&runner, None, "feature"));
+        assert!(runner.was_called("gh", &["pr", "view", "feature", "--json", "state", "-q", ".state", "--repo", "acme/widgets"]));
+    }
+
+    #[test]
+    fn test_is_pr_merged_without_pr() {
+        let runner = MockRunner::new();
+        runner.mock_error("gh pr view feature --json state -q .state", "no pull requests found");
+        assert!(!is_pr_merged(&runner, None, "feature"));
+    }
+
+    #[test]
+    fn test_dry_run_plan_with_prune() {
+        let plan = dry_run_plan(&["feature".to_string()], true);
+        assert_eq!(plan, vec!["feature", "abandon empty changes without a description"]);
+    }
+
+    #[test]
+    fn test_dry_run_plan_without_prune() {
+        let plan = dry_run_plan(&["feature".to_string()], false);
+        assert_eq!(plan, vec!["feature"]);
+    }
+
+    #[test]
+    fn test_should_prune_config_and_flag() {
+        let enabled = Config::default();
+        let disabled = Config::from_toml("[land]\nprune_empty = false").unwrap();
+
+        assert!(should_prune(&enabled, false));
+        assert!(!should_prune(&enabled, true));
+        assert!(!should_prune(&disabled, false));
+        assert!(!should_prune(&disabled, true));
+    }
+
+    const CONFLICT_QUERY: &str = "jj log -r (::@ ~ ::main@origin) & conflicts() --no-graph -T change_id ++ \"\\n\"";
+    const WORKING_COPY_BOOKMARKS: &str = "jj log -r @ --no-graph -T local_bookmarks.map(|b| b.name()).join(\"\\n\")";
+
+    fn test_renderer() -> Renderer {
+        Renderer::new(crate::ui::get_theme("default"), crate::ui::get_icon_set("ascii"))
+    }
+
+    const TARGET: LandTarget<'static> = LandTarget {
+        remote: "origin",
+        trunk_ref: "main@origin",
+        stack_revset: "::@ ~ ::main@origin",
+        primary: "main",
+        gh_repo: None,
+    };
+
+    /// Whether any `gh pr edit` ran
+    fn edited_prs(runner: &MockRunner) -> bool {
+        runner.get_calls().iter().any(|(program, args)| program == "gh" && args.get(1).is_some_and(|a| a == "edit"))
+    }
+
+    #[test]
+    fn test_land_refuses_bookmark_on_working_copy() {
+        let runner = MockRunner::new();
+        runner.mock_response(WORKING_COPY_BOOKMARKS, "other\nfeature\n");
+
+        let err = land_bookmarks(&runner, &test_renderer(), &TARGET, &["feature".to_string()], true).unwrap_err();
+
+        assert!(err.to_string().contains("'feature': it's on the working copy"));
+        assert!(err.to_string().contains("jj new main@origin"));
+        assert!(!runner.was_called("jj", &["rebase", "-d", "main@origin"]));
+        assert!(!runner.was_called("jj", &["bookmark", "delete", "feature"]));
+        // A refused land leaves dependent PRs where they were
+        assert!(!edited_prs(&runner));
+    }
+
+    #[test]
+    fn test_land_conflicting_rebase_keeps_bookmarks() {
+        let runner = MockRunner::new();
+        runner.mock_response(WORKING_COPY_BOOKMARKS, "");
+        runner.mock_response("jj rebase -d main@origin", "");
+        runner.mock_response(CONFLICT_QUERY, "qpvuntsmwlqt\n");
+
+        let outcome = land_bookmarks(&runner, &test_renderer(), &TARGET, &["feature".to_string()], true).unwrap();
+
+        assert_eq!(outcome, LandOutcome::Conflicted);
+        assert!(!edited_prs(&runner));
+        assert!(runner.was_called("jj", &["rebase", "-d", "main@origin"]));
+        assert!(!runner.was_called("git", &["push", "origin", "--delete", "feature"]));
+        assert!(!runner.was_called("jj", &["bookmark", "delete", "feature"]));
+    }
+
+    #[test]
+    fn test_land_clean_rebase_deletes_bookmarks() {
+        let runner = MockRunner::new();
+        runner.mock_response(WORKING_COPY_BOOKMARKS, "");
+        runner.mock_response("jj rebase -d main@origin", "");
+        runner.mock_response(CONFLICT_QUERY, "");
+        runner.mock_response(OPEN_PRS, r#"[{"headRefName":"feature-b","baseRefName":"feature"}]"#);
+        runner.mock_response("gh pr edit feature-b --base main", "");
+        runner.mock_response("git push origin --delete feature", "");
+        runner.mock_response("jj bookmark delete feature", "");
+
+        let outcome = land_bookmarks(&runner, &test_renderer(), &TARGET, &["feature".to_string()], false).unwrap();
+
+        assert!(matches!(outcome, LandOutcome::Done(_)));
+        // The dependent PR moves before its base branch is deleted
+        let calls = runner.get_calls();
+        let position = |program: &str, verb: &str| {
+            calls.iter().position(|(p, args)| p == program && args.iter().any(|a| a == verb))
+        };
+        assert!(position("gh", "edit").unwrap() < position("git", "--delete").unwrap());
+        assert!(runner.was_called("git", &["push", "origin", "--delete", "feature"]));
+        assert!(runner.was_called("jj", &["bookmark", "delete", "feature"]));
+    }
+
+    #[test]
+    fn test_land_report_counts() {
+        let runner = MockRunner::new();
+        runner.mock_response(WORKING_COPY_BOOKMARKS, "");
+        runner.mock_response("jj rebase -d main@origin", "");
+        runner.mock_response(CONFLICT_QUERY, "");
+        runner.mock_response("git push origin --delete feature-a", "");
+        runner.mock_error("git push origin --delete feature-b", "remote ref does not exist");
+        runner.mock_response("jj bookmark delete feature-a", "");
+        runner.mock_response("jj bookmark delete feature-b", "");
+        runner.mock_response(EMPTY_QUERY, "eeeeeeee\n");
+        runner.mock_response("jj abandon eeeeeeee", "");
+
+        let bookmarks = ["feature-a".to_string(), "feature-b".to_string()];
+        let outcome = land_bookmarks(&runner, &test_renderer(), &TARGET, &bookmarks, true).unwrap();
+
+        let LandOutcome::Done(report) = outcome else {
+            panic!("expected a clean land");
+        };
+        assert_eq!(report.landed, bookmarks);
+        assert_eq!(report.deleted_local, bookmarks);
+        // GitHub already deleted feature-b after the merge
+        assert_eq!(report.deleted_remote, ["feature-a"]);
+        assert_eq!(report.pruned, 1);
+
+        let (headline, details) = summary_lines(&report);
+        assert_eq!(headline, "Landed 2 bookmark(s)");
+        assert!(details.contains(&"Pruned 1 empty change(s)".to_string()));
+    }
+
+    #[test]
+    fn test_land_report_json_status() {
+        let report = LandReport {
+            status: LandStatus::NothingToContinue,
+            ..LandReport::default()
+        };
+        let value = serde_json::to_value(&report).unwrap();
+        assert_eq!(value["status"], "nothing-to-continue");
+        assert_eq!(value["landed"], serde_json::json!([]));
+
+        let landed = serde_json::to_value(LandReport::default()).unwrap();
+        assert_eq!(landed["status"], "landed");
+    }
 }
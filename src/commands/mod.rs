@@ -1,7 +1,84 @@
+pub mod bookmark;
+pub mod clean;
+pub mod config;
+pub mod diff;
+pub mod edit;
 pub mod init;
 pub mod land;
+pub mod new;
 pub mod pull;
 pub mod push;
+pub mod rebase;
 pub mod reorder;
+pub mod stash;
 pub mod status;
+pub mod sync;
 pub mod wip;
+
+use crate::config::Config;
+use crate::jj::{RealRunner, RetryRunner};
+use crate::ui::Renderer;
+
+/// Whether the stack is empty, meaning `@` sits on trunk itself
+pub fn is_empty_stack<T>(stack: &[T]) -> bool {
+    stack.is_empty()
+}
+
+/// The message every command shows when the stack is empty
+pub fn empty_stack_message(config: &Config) -> String {
+    let base = config.remote.base_override.as_deref().unwrap_or(&config.remote.primary);
+    format!("You're on {}, nothing to do", base)
+}
+
+/// Runner for network-bound commands, retrying transient failures and timing out
+/// hung ones per `[network]` config
+pub fn network_runner<'a>(config: &Config, renderer: &'a Renderer) -> RetryRunner<'a, RealRunner> {
+    let runner = RealRunner::new(&config.tools).with_timeout(config.network.timeout());
+    RetryRunner::new(runner, config.network.retries).on_retry(move |message| renderer.info(message))
+}
+
+/// Arguments for a gh command, with `--repo` added when `[github] repo` is set so
+/// gh doesn't have to infer the repository from the remotes
+pub fn gh_args<'a>(repo: Option<&'a str>, args: &[&'a str]) -> Vec<&'a str> {
+    let mut args = args.to_vec();
+    if let Some(repo) = repo {
+        args.extend(["--repo", repo]);
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_empty_stack() {
+        assert!(is_empty_stack::<String>(&[]));
+        assert!(!is_empty_stack(&["qpvuntsm".to_string()]));
+    }
+
+    #[test]
+    fn test_empty_stack_message_names_primary() {
+        let mut config = Config::default();
+        assert_eq!(empty_stack_message(&config), "You're on main, nothing to do");
+
+        config.remote.primary = "master".to_string();
+        assert_eq!(empty_stack_message(&config), "You're on master, nothing to do");
+    }
+
+    #[test]
+    fn test_empty_stack_message_names_base_override() {
+        let mut config = Config::default();
+        config.remote.base_override = Some("integration".to_string());
+        assert_eq!(empty_stack_message(&config), "You're on integration, nothing to do");
+    }
+
+    #[test]
+    fn test_gh_args_adds_repo_when_configured() {
+        assert_eq!(gh_args(None, &["pr", "list"]), vec!["pr", "list"]);
+        assert_eq!(
+            gh_args(Some("acme/widgets"), &["pr", "list"]),
+            vec!["pr", "list", "--repo", "acme/widgets"]
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use anyhow::Result;
+
+use crate::config::Config;
+use crate::jj::{self, RealRunner};
+use crate::ui::Renderer;
+
+/// Create a new change on top of `revision` (default: @) and show the stack
+pub fn run(config: &Config, revision: Option<&str>, message: Option<&str>) -> Result<()> {
+    let renderer = Renderer::from_config(config);
+    let runner = RealRunner::new(&config.tools);
+
+    let mut args = vec!["new", revision.unwrap_or("@")];
+    // Without a message, leave the description blank (jj default)
+    if let Some(message) = message {
+        args.extend(["-m", message]);
+    }
+
+    if let Err(e) = jj::run_jj(&runner, &args) {
+        anyhow::bail!("Could not create new change: {}", e.to_string().trim());
+    }
+
+    // Show updated stack
+    let revset = config.stack_revset();
+    let stack = jj::get_stack(&runner, &revset, &config.remote.name)?;
+    renderer.render_stack(&stack, &config.trunk_ref());
+
+    Ok(())
+}
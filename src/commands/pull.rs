@@ -1,31 +1,168 @@
 use anyhow::Result;
 use crate::config::Config;
-use crate::jj;
-use crate::ui::{get_icon_set, get_theme, Renderer};
+use crate::jj::{self, CommandRunner, RealRunner};
+use crate::ui::Renderer;
 
-pub fn run(config: &Config, remote_override: Option<&str>) -> Result<()> {
-    let theme = get_theme(&config.display.theme);
-    let icons = get_icon_set(&config.display.icons);
-    let renderer = Renderer::new(theme, icons);
+pub fn run(config: &Config, remote_override: Option<&str>, no_rebase: bool, prune: bool) -> Result<()> {
+    let renderer = Renderer::from_config(config);
+
+    let runner = super::network_runner(config, &renderer);
+    let local = RealRunner::new(&config.tools);
 
     let remote = remote_override.unwrap_or(&config.remote.name);
+    if prune {
+        fetch_and_prune(&runner, &renderer, remote, &config.remote.primary)?;
+    } else {
+        fetch(&runner, &renderer, remote)?;
+    }
 
-    // Fetch from remote
-    renderer.info(&format!("Fetching from {}...", remote));
-    jj::run_jj(&["git", "fetch", "--remote", remote])?;
+    // Fetch only: show the updated sync states without moving anything
+    if no_rebase {
+        renderer.success("Fetched (stack not rebased)");
+        println!();
+        let stack = jj::get_stack(&local, &config.stack_revset(), &config.remote.name)?;
+        renderer.render_stack(&stack, &config.trunk_ref());
+        return Ok(());
+    }
 
-    // Rebase onto trunk
-    let trunk_ref = config.trunk_ref();
-    renderer.info(&format!("Rebasing stack onto {}...", trunk_ref));
-    jj::run_jj(&["rebase", "-d", &trunk_ref])?;
+    // On trunk there's no stack to rebase
+    let revset = config.stack_revset();
+    let stack = jj::get_stack(&local, &revset, &config.remote.name)?;
+    if super::is_empty_stack(&stack) {
+        renderer.info(&super::empty_stack_message(config));
+        return Ok(());
+    }
+
+    rebase_onto_trunk(config, &renderer)?;
 
     renderer.success("Successfully pulled and rebased!");
     println!();
 
     // Show updated stack
-    let revset = config.stack_revset();
-    let stack = jj::get_stack(&revset, &config.remote.name)?;
+    let stack = jj::get_stack(&local, &revset, &config.remote.name)?;
     renderer.render_stack(&stack, &config.trunk_ref());
 
     Ok(())
 }
+
+/// Fetch from the remote and rebase the stack onto trunk
+pub fn fetch_and_rebase(
+    config: &Config,
+    runner: &dyn CommandRunner,
+    renderer: &Renderer,
+    remote: &str,
+) -> Result<()> {
+    fetch(runner, renderer, remote)?;
+    rebase_onto_trunk(config, renderer)
+}
+
+/// Fetch from the remote
+fn fetch(runner: &dyn CommandRunner, renderer: &Renderer, remote: &str) -> Result<()> {
+    renderer.info(&format!("Fetching from {}...", remote));
+    renderer.with_spinner("fetching", || runner.run("jj", &["git", "fetch", "--remote", remote]))?;
+    Ok(())
+}
+
+/// Fetch, then delete local bookmarks whose remote counterpart the fetch removed.
+/// Local-only bookmarks (never on the remote) and the primary branch are kept.
+fn fetch_and_prune(
+    runner: &dyn CommandRunner,
+    renderer: &Renderer,
+    remote: &str,
+    primary: &str,
+) -> Result<Vec<String>> {
+    let before = jj::query_remote_presence_with(runner, remote)?;
+    fetch(runner, renderer, remote)?;
+    let after = jj::query_remote_presence_with(runner, remote)?;
+
+    let stale: Vec<String> = after
+        .iter()
+        .filter(|(name, on_remote)| !**on_remote && name.as_str() != primary && before.get(*name) == Some(&true))
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    for name in &stale {
+        runner.run("jj", &["bookmark", "delete", name])?;
+        renderer.info(&format!("Pruned {} (deleted from {})", name, remote));
+    }
+    Ok(stale)
+}
+
+/// Rebase the stack onto trunk
+fn rebase_onto_trunk(config: &Config, renderer: &Renderer) -> Result<()> {
+    let trunk_ref = config.trunk_ref();
+    renderer.info(&format!("Rebasing stack onto {}...", trunk_ref));
+    jj::run_jj(&RealRunner::new(&config.tools), &["rebase", "-d", &trunk_ref])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jj::query::BOOKMARK_TEMPLATE;
+    use crate::jj::runner::mock::MockRunner;
+
+    fn test_renderer() -> Renderer {
+        Renderer::new(crate::ui::get_theme("default"), crate::ui::get_icon_set("ascii")).with_quiet(true)
+    }
+
+    fn entry(name: &str, remote: Option<&str>) -> String {
+        let remote = remote.map(|r| format!("\"{}\"", r)).unwrap_or_else(|| "null".to_string());
+        format!(
+            r#"{{"name":"{}","remote":{},"change_id":"abc123","synced":false,"ahead":null,"behind":null,"conflict":false}}"#,
+            name, remote
+        )
+    }
+
+    #[test]
+    fn test_prune_deletes_bookmark_whose_remote_vanished() {
+        let runner = MockRunner::new();
+        let before = [
+            entry("main", None),
+            entry("main", Some("origin")),
+            entry("merged", None),
+            entry("merged", Some("origin")),
+            entry("open", None),
+            entry("open", Some("origin")),
+            entry("local-only", None),
+        ]
+        .join("\n");
+        // A teammate merged "merged" and deleted the branch; main vanished too (renamed upstream)
+        let after = [
+            entry("main", None),
+            entry("merged", None),
+            entry("open", None),
+            entry("open", Some("origin")),
+            entry("local-only", None),
+        ]
+        .join("\n");
+        runner.mock_sequence(
+            &format!("jj bookmark list --all -T {}", BOOKMARK_TEMPLATE),
+            &[Ok(before.as_str()), Ok(after.as_str())],
+        );
+        runner.mock_response("jj git fetch --remote origin", "");
+        runner.mock_response("jj bookmark delete merged", "");
+
+        let pruned = fetch_and_prune(&runner, &test_renderer(), "origin", "main").unwrap();
+
+        assert_eq!(pruned, vec!["merged"]);
+        assert!(runner.was_called("jj", &["bookmark", "delete", "merged"]));
+        assert!(!runner.was_called("jj", &["bookmark", "delete", "main"]));
+        assert!(!runner.was_called("jj", &["bookmark", "delete", "open"]));
+        assert!(!runner.was_called("jj", &["bookmark", "delete", "local-only"]));
+    }
+
+    #[test]
+    fn test_prune_nothing_when_remote_unchanged() {
+        let runner = MockRunner::new();
+        let listing = [entry("feature", None), entry("feature", Some("origin"))].join("\n");
+        runner.mock_response(&format!("jj bookmark list --all -T {}", BOOKMARK_TEMPLATE), &listing);
+        runner.mock_response("jj git fetch --remote origin", "");
+
+        let pruned = fetch_and_prune(&runner, &test_renderer(), "origin", "main").unwrap();
+
+        assert!(pruned.is_empty());
+        assert!(!runner.get_calls().iter().any(|(_, args)| args[..2] == ["bookmark", "delete"]));
+    }
+}
@@ -1,61 +1,205 @@
 use anyhow::{Context, Result};
-use std::io::{self, Write};
-use std::process::Command;
-
-use crate::config::Config;
-use crate::jj;
-use crate::ui::{get_icon_set, get_theme, Renderer};
-
-pub fn run(
-    config: &Config,
-    revision: Option<&str>,
-    bookmark: Option<&str>,
-    force_squash: bool,
-    force_append: bool,
-    dry_run: bool,
-) -> Result<()> {
-    let theme = get_theme(&config.display.theme);
-    let icons = get_icon_set(&config.display.icons);
-    let renderer = Renderer::new(theme, icons);
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 
-    // Determine push style
-    let push_style = if force_squash {
-        "squash"
-    } else if force_append {
-        "append"
-    } else {
-        &config.github.push_style
-    };
+use crate::capabilities::Capabilities;
+use crate::config::{Config, HookCommand};
+use crate::jj::types::{BookmarkSyncState, ChangeWithStatus};
+use crate::jj::{self, CommandRunner, RealRunner};
+use crate::ui::Renderer;
+
+/// Options for `jf push`
+#[derive(Debug, Default)]
+pub struct PushOptions<'a> {
+    /// Revset of changes to push (default: entire stack)
+    pub revision: Option<&'a str>,
+    /// Bookmark name for changes without one
+    pub bookmark: Option<&'a str>,
+    /// Force squash-style push (override config)
+    pub force_squash: bool,
+    /// Force append-style push (override config)
+    pub force_append: bool,
+    pub dry_run: bool,
+    /// With `revision`, also push unpushed stack ancestors
+    pub with_ancestors: bool,
+    /// Overwrite other authors' commits on remote bookmarks
+    pub force: bool,
+    /// With `dry_run`, print the plan as JSON instead of a human list
+    pub json: bool,
+    /// Open created or existing PRs in the browser once pushing is done
+    pub open: bool,
+    /// Skip the `[hooks] pre_push` command and the conventional-commit check
+    pub no_verify: bool,
+    /// Largest stack to push without confirmation (overrides `[github] max_stack`)
+    pub max_stack: Option<usize>,
+    /// Ask for every missing bookmark name up front, before pushing anything
+    pub interactive: bool,
+    /// Combine every commit's description into new append-style PRs
+    /// (overrides `[github] squash_descriptions`)
+    pub squash_descriptions: bool,
+    /// Push and track bookmarks but leave PRs for a later `jf push`
+    pub no_pr: bool,
+    /// Prompt for a description for each change without one, instead of refusing
+    pub amend_descriptions: bool,
+    /// Refuse a `revision` that selects changes outside the stack (otherwise a warning)
+    pub stack_only: bool,
+    /// Push bottom-up and base each new PR on the bookmark pushed just below it
+    pub base_auto_stack: bool,
+    /// Edit each new PR's body in `$EDITOR` before creating it
+    pub edit: bool,
+}
+
+/// What a push does for a change's pull request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PushAction {
+    CreatePr,
+    UpdatePr,
+    Skip,
+}
+
+impl PushAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            PushAction::CreatePr => "create-pr",
+            PushAction::UpdatePr => "update-pr",
+            PushAction::Skip => "skip",
+        }
+    }
+}
+
+/// One change in a `jf push --dry-run` plan
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanEntry {
+    pub change_id: String,
+    pub short_id: String,
+    pub description: String,
+    /// Bookmark the change would be pushed as, if known
+    pub bookmark: Option<String>,
+    /// The change has no bookmark yet
+    pub needs_bookmark: bool,
+    pub action: PushAction,
+}
 
-    // Ensure primary branch exists on remote
-    ensure_primary_exists(config, &renderer)?;
+pub fn run(config: &Config, caps: &Capabilities, options: &PushOptions) -> Result<()> {
+    // A JSON plan keeps stdout for the JSON; warnings still reach stderr
+    let renderer = Renderer::from_config(config).with_quiet(options.dry_run && options.json);
+    let runner = super::network_runner(config, &renderer);
+    let local = RealRunner::new(&config.tools);
+
+    let PushOptions {
+        revision,
+        bookmark,
+        dry_run,
+        with_ancestors,
+        ..
+    } = *options;
+
+    let push_style = push_style(config, options);
+    let gh_repo = config.github.repo.as_deref();
+    let prs = prs_enabled(caps, options);
+
+    // Ensure primary branch exists on remote (a dry run must not create it)
+    if !dry_run {
+        ensure_primary_exists(config, &runner, &renderer)?;
+    }
 
     // Get the changes to push
-    let revset = revision.map(|r| r.to_string()).unwrap_or_else(|| config.stack_revset());
-    let changes = jj::query_changes(&revset)?;
+    let revset = match revision {
+        // Include every stack change between trunk and the revision
+        Some(r) if with_ancestors => format!("::({}) & ({})", r, config.stack_revset()),
+        Some(r) => r.to_string(),
+        None => config.stack_revset(),
+    };
+    let changes = jj::query_changes(&local, &revset)?;
+    if let Some(name) = bookmark {
+        jj::validate_bookmark_name(&format!("{}{}", config.bookmarks.prefix, name))?;
+        check_bookmark_target(&changes, name)?;
+    }
+
+    // Off-stack changes are refused (or warned about on stderr) before any plan, JSON included
+    if revision.is_some() {
+        check_on_stack(&runner, &renderer, &revset, &config.stack_revset(), options.stack_only)?;
+    }
 
+    // Machine-readable plan: nothing but JSON on stdout
+    if dry_run && options.json {
+        let plan = build_plan(&changes, bookmark, &config.bookmarks.prefix, prs, |b| get_pr_for_branch(&local, b, gh_repo))?;
+        println!("{}", serde_json::to_string_pretty(&plan)?);
+        return Ok(());
+    }
+
+    // A partial push whose base isn't on the remote yields PRs with a missing base
+    if revision.is_some() && !with_ancestors {
+        let unpushed = find_unpushed_ancestors(config, &revset)?;
+        if !unpushed.is_empty() {
+            renderer.warning("Ancestors of the selected change(s) have not been pushed:");
+            for change in &unpushed {
+                let short_id = &change.change_id[..8.min(change.change_id.len())];
+                let desc = change.description.lines().next().unwrap_or("(no description)");
+                println!("  {} {}", short_id, desc);
+            }
+            renderer.info("Use --with-ancestors to push them too");
+        }
+    }
+
+    if revision.is_none() && super::is_empty_stack(&changes) {
+        renderer.info(&super::empty_stack_message(config));
+        return Ok(());
+    }
     if changes.is_empty() {
         renderer.info("No changes to push");
         return Ok(());
     }
 
+    // A huge stack usually means a wrong primary ref reaching into history
+    let max_stack = options.max_stack.unwrap_or(config.github.max_stack);
+    check_stack_size(&renderer, changes.len(), max_stack, || {
+        // A dry run pushes nothing, so the warning is enough
+        if dry_run {
+            Ok(true)
+        } else {
+            prompt_push_anyway(changes.len())
+        }
+    })?;
+
     renderer.info(&format!("Found {} change(s) to push (style: {})", changes.len(), push_style));
 
     if dry_run {
+        // Report unsigned commits, but a dry run never blocks
+        check_signatures(&runner, &renderer, config.github.require_signed, &revset, false)?;
+        let plan = build_plan(&changes, bookmark, &config.bookmarks.prefix, prs, |b| get_pr_for_branch(&local, b, gh_repo))?;
         println!("\nDry run - would push:");
-        for change in &changes {
-            let short_id = &change.change_id[..8.min(change.change_id.len())];
-            let desc = change.description.lines().next().unwrap_or("(no description)");
-            let bookmark_info = if change.bookmarks.is_empty() {
+        for (change, entry) in changes.iter().zip(&plan) {
+            let desc = if entry.description.is_empty() {
+                "(no description)"
+            } else {
+                &entry.description
+            };
+            let bookmark_info = if entry.needs_bookmark {
                 " [needs bookmark]".to_string()
             } else {
                 format!(" [{}]", change.bookmarks.join(", "))
             };
-            println!("  {} {}{}", short_id, desc, bookmark_info);
+            println!("  {} {}{} ({})", entry.short_id, desc, bookmark_info, entry.action.as_str());
         }
         return Ok(());
     }
 
+    // Describe changes in place if asked; any left without a description are skipped
+    let changes = if options.amend_descriptions {
+        let changes = describe_empty_changes(&runner, &renderer, changes, prompt_description)?;
+        if changes.is_empty() {
+            renderer.info("No changes to push");
+            return Ok(());
+        }
+        changes
+    } else {
+        changes
+    };
+
     // Check for empty descriptions
     let empty_desc_changes: Vec<_> = changes
         .iter()
@@ -70,22 +214,154 @@ pub fn run(
         }
         println!();
         renderer.info("Add descriptions with: jj describe -r <change-id> -m \"Description\"");
+        renderer.info("Or describe them as you push with: jf push --amend-descriptions");
         anyhow::bail!("Changes must have descriptions before pushing");
     }
 
+    // Check subjects follow the conventional-commit format if the repo requires it
+    if config.github.require_conventional && !options.no_verify {
+        let unconventional: Vec<_> = changes
+            .iter()
+            .filter(|c| !is_conventional_subject(c.description.lines().next().unwrap_or("")))
+            .collect();
+
+        if !unconventional.is_empty() {
+            renderer.error("Cannot push changes whose subject isn't a conventional commit:");
+            for change in &unconventional {
+                let short_id = &change.change_id[..8.min(change.change_id.len())];
+                println!("  {} {}", short_id, change.description.lines().next().unwrap_or(""));
+            }
+            println!();
+            renderer.info("Subjects must look like: feat(scope): summary (types: feat, fix, chore, docs, refactor, test, perf)");
+            renderer.info("Skip this check with: jf push --no-verify");
+            anyhow::bail!("Changes must have conventional-commit subjects before pushing");
+        }
+    }
+
+    // Check signatures before anything is pushed
+    check_signatures(&runner, &renderer, config.github.require_signed, &revset, true)?;
+
+    // Decide every bookmark name before anything is pushed
+    let planned = if options.interactive {
+        match plan_bookmarks_interactively(&local, &renderer, &changes, &config.bookmarks.prefix)? {
+            Some(planned) => planned,
+            None => {
+                renderer.info("Aborted - nothing was pushed");
+                return Ok(());
+            }
+        }
+    } else {
+        HashMap::new()
+    };
+
+    // Run the pre-push hook once for the whole push
+    if !options.no_verify {
+        if let Some(hook) = &config.hooks.pre_push {
+            run_pre_push_hook(&renderer, hook, |program, args| local.run_streaming(program, args))?;
+        }
+    }
+
+    if options.open && !caps.gh {
+        renderer.warning("gh CLI not found - ignoring --open");
+    }
+    let context = PushContext {
+        runner: &runner,
+        local: &local,
+        renderer: &renderer,
+        config,
+        options,
+        prs,
+    };
+    let PushOutcome { to_open, up_to_date } = push_changes(&context, &changes, &planned)?;
+
+    if options.open && caps.gh {
+        // Launching a browser is local and not worth retrying or timing out
+        open_prs(&local, gh_repo, &to_open);
+    }
+
+    if up_to_date > 0 {
+        renderer.info(&format!("{} bookmark(s) were already pushed", up_to_date));
+    }
+    if options.no_pr {
+        renderer.info("Skipped pull requests (--no-pr); run `jf push` again to create them");
+    }
+    renderer.success("Done!");
+    Ok(())
+}
+
+/// Runners, output, and the settings `run` resolved, for `push_changes`
+struct PushContext<'a> {
+    /// Runs pushes and gh, retrying network failures
+    runner: &'a dyn CommandRunner,
+    /// Runs local jj commands
+    local: &'a dyn CommandRunner,
+    renderer: &'a Renderer,
+    config: &'a Config,
+    options: &'a PushOptions<'a>,
+    /// Look up and create PRs (see `prs_enabled`)
+    prs: bool,
+}
+
+/// What `push_changes` did
+struct PushOutcome {
+    /// Bookmarks whose PRs `--open` shows
+    to_open: Vec<String>,
+    /// Bookmarks that were already pushed
+    up_to_date: usize,
+}
+
+/// Push each of `changes` (newest first) and create or report its PR, bottom-up with
+/// `--base-auto-stack`. `planned` holds the bookmark names `--interactive` asked for.
+fn push_changes(
+    context: &PushContext,
+    changes: &[jj::Change],
+    planned: &HashMap<String, String>,
+) -> Result<PushOutcome> {
+    let PushContext { runner, local, renderer, config, options, prs } = *context;
+    let bookmark = options.bookmark;
+    let gh_repo = config.github.repo.as_deref();
+    let push_style = push_style(config, options);
+    let squash_descriptions =
+        push_style == "append" && (options.squash_descriptions || config.github.squash_descriptions);
+
+    // Branches whose PRs to open at the end, so browsers don't interleave with push output
+    let mut to_open = Vec::new();
+
+    // One batched lookup of open PRs, so a re-run finds the ones it already created
+    let known_prs = if prs { open_pr_urls(runner, gh_repo).ok() } else { None };
+    let mut up_to_date = 0;
+
+    // Changes come newest first; stacked bases need the ones below pushed first
+    let ordered: Vec<&jj::Change> = if options.base_auto_stack {
+        changes.iter().rev().collect()
+    } else {
+        changes.iter().collect()
+    };
+    // Bookmark pushed just below the current change, with --base-auto-stack
+    let mut below: Option<String> = None;
+
     // Process each change
-    for change in &changes {
+    for change in ordered {
         let short_id = &change.change_id[..8.min(change.change_id.len())];
         let desc = change.description.lines().next().unwrap_or("(no description)");
 
         // Check if change has a bookmark
         let change_bookmark = if !change.bookmarks.is_empty() {
             change.bookmarks[0].clone()
+        } else if options.interactive {
+            // Names were collected up front; a change without one is skipped
+            let Some(full_name) = planned.get(&change.change_id) else {
+                renderer.info(&format!("Skipping {} (no bookmark provided)", short_id));
+                continue;
+            };
+            renderer.info(&format!("Creating bookmark '{}' at {}", full_name, short_id));
+            jj::create_bookmark_with(local, full_name, &change.change_id)?;
+            full_name.clone()
         } else if let Some(provided_bookmark) = bookmark {
             // Use provided bookmark (only makes sense for single change)
             let full_name = format!("{}{}", config.bookmarks.prefix, provided_bookmark);
             renderer.info(&format!("Creating bookmark '{}' at {}", full_name, short_id));
-            jj::create_bookmark(&full_name, &change.change_id)?;
+            jj::create_bookmark_with(local, &full_name, &change.change_id)?;
             full_name
         } else {
             // Prompt for bookmark name
@@ -96,57 +372,560 @@ pub fn run(
             }
             let full_name = format!("{}{}", config.bookmarks.prefix, bookmark_name);
             renderer.info(&format!("Creating bookmark '{}' at {}", full_name, short_id));
-            jj::create_bookmark(&full_name, &change.change_id)?;
+            jj::create_bookmark_with(local, &full_name, &change.change_id)?;
             full_name
         };
 
-        // Push the bookmark
-        renderer.info(&format!("Pushing {}...", change_bookmark));
-        push_bookmark(&change_bookmark, &config.remote.name, push_style == "squash")?;
+        // Push the bookmark, unless an earlier (interrupted) run already did
+        let squash = push_style == "squash";
+        if !push_if_needed(runner, renderer, &change_bookmark, &config.remote.name, squash, options.force)? {
+            up_to_date += 1;
+        }
 
         // Check if PR exists, create if not
-        if is_gh_available() {
-            match get_pr_for_branch(&change_bookmark)? {
-                Some(pr_url) => {
+        let existing_pr = match &known_prs {
+            Some(prs) => prs.get(&change_bookmark).cloned(),
+            None if prs => get_pr_for_branch(local, &change_bookmark, gh_repo)?,
+            None => None,
+        };
+        match decide_action(Some(&change_bookmark), prs, existing_pr.as_deref()) {
+            PushAction::UpdatePr => {
+                if let Some(pr_url) = &existing_pr {
                     renderer.info(&format!("PR exists: {}", pr_url));
                 }
-                None => {
-                    renderer.info("Creating pull request...");
-                    let pr_title = desc;
-                    let pr_body = if config.github.stack_context {
-                        create_pr_body_with_stack(&change, config)?
-                    } else {
-                        change.description.clone()
+                to_open.push(change_bookmark.clone());
+            }
+            PushAction::CreatePr => {
+                renderer.info("Creating pull request...");
+                // Determine base branch (the bookmark below, or the parent's bookmark or trunk)
+                let base = if options.base_auto_stack {
+                    stack_base(below.as_deref(), || get_base_branch_for_change(local, &change.change_id, config))?
+                } else {
+                    get_base_branch_for_change(local, &change.change_id, config)?
+                };
+
+                // An append-style PR can span several commits; tell their whole story
+                let combined = if squash_descriptions {
+                    combine_descriptions(&pr_commit_descriptions(runner, &base, &change_bookmark)?)
+                } else {
+                    None
+                };
+                let (pr_title, description) = match combined {
+                    Some(combined) => combined,
+                    None => {
+                        // Trailers live past the first line, so read the whole description
+                        let full_description = jj::get_full_description(local, &change.change_id)
+                            .unwrap_or_else(|_| change.description.clone());
+                        (jj::types::pr_title(&full_description), change.description.clone())
+                    }
+                };
+                // Long subjects make poor titles; shorten the title, keep the subject in the body
+                let (pr_title, description) = match fit_pr_title(&pr_title, config.github.max_title_len) {
+                    Some(short) => {
+                        renderer.warning(&format!(
+                            "PR title is over {} characters; shortened to \"{}\"",
+                            config.github.max_title_len, short
+                        ));
+                        let description = if description.contains(pr_title.as_str()) {
+                            description
+                        } else {
+                            format!("{}\n\n{}", pr_title, description)
+                        };
+                        (short, description)
+                    }
+                    None => (pr_title, description),
+                };
+                let pr_body = if config.github.stack_context {
+                    let change = jj::Change {
+                        description,
+                        ..change.clone()
                     };
+                    create_pr_body_with_stack(&change, config)?
+                } else {
+                    description
+                };
 
-                    // Determine base branch (parent's bookmark or trunk)
-                    let base = get_base_branch_for_change(&change.change_id, config)?;
-                    create_github_pr(&change_bookmark, &base, pr_title, &pr_body)?;
-                    renderer.success("Pull request created!");
+                // Let the user refine the body first; without an editor, use it as generated
+                let body_file = if options.edit {
+                    let file = edit_pr_body(&change_bookmark, &pr_body, std::env::var("EDITOR").ok(), open_editor)?;
+                    if file.is_none() {
+                        renderer.warning("$EDITOR is not set; using the generated PR body");
+                    }
+                    file
+                } else {
+                    None
+                };
+                let body = match &body_file {
+                    Some(path) => PrBody::File(path),
+                    None => PrBody::Text(&pr_body),
+                };
+                let created = renderer.with_spinner("creating pull request", || {
+                    create_github_pr(runner, gh_repo, &change_bookmark, &base, &pr_title, body)
+                });
+                if let Some(path) = &body_file {
+                    let _ = std::fs::remove_file(path);
                 }
+                // gh's output carries the PR URL; print it once the spinner is gone
+                print!("{}", created?);
+                renderer.success("Pull request created!");
+                to_open.push(change_bookmark.clone());
             }
+            PushAction::Skip => {}
         }
+        below = Some(change_bookmark);
     }
 
-    renderer.success("Done!");
+    Ok(PushOutcome { to_open, up_to_date })
+}
+
+/// "squash" or "append": the `--squash`/`--append` override, or `[github] push_style`
+fn push_style<'a>(config: &'a Config, options: &PushOptions) -> &'a str {
+    if options.force_squash {
+        "squash"
+    } else if options.force_append {
+        "append"
+    } else {
+        &config.github.push_style
+    }
+}
+
+/// Whether push looks up and creates PRs: needs gh, and not deferred with `--no-pr`
+fn prs_enabled(caps: &Capabilities, options: &PushOptions) -> bool {
+    caps.gh && !options.no_pr
+}
+
+/// Push `bookmark` unless it's already on the remote at the local commit (as after
+/// a push that failed partway), returning whether anything was pushed
+fn push_if_needed(
+    runner: &dyn CommandRunner,
+    renderer: &Renderer,
+    bookmark: &str,
+    remote: &str,
+    squash: bool,
+    force: bool,
+) -> Result<bool> {
+    if already_pushed(runner, bookmark, remote) {
+        renderer.info(&format!("{} is already pushed", bookmark));
+        return Ok(false);
+    }
+
+    // Refuse to overwrite a collaborator's commits on a diverged branch
+    if squash {
+        guard_force_push(runner, renderer, bookmark, remote, force)?;
+    }
+
+    renderer.info(&format!("Pushing {}...", bookmark));
+    renderer.with_spinner("pushing", || push_bookmark(runner, bookmark, remote, squash))?;
+    Ok(true)
+}
+
+/// Whether `bookmark` exists on `remote` at the same commit as locally
+fn already_pushed(runner: &dyn CommandRunner, bookmark: &str, remote: &str) -> bool {
+    let revset = format!(
+        "remote_bookmarks(exact:\"{}\", exact:\"{}\") & bookmarks(exact:\"{}\")",
+        bookmark, remote, bookmark
+    );
+    runner
+        .run("jj", &["log", "-r", &revset, "--no-graph", "-T", "commit_id"])
+        .is_ok_and(|output| !output.trim().is_empty())
+}
+
+/// An open PR from `gh pr list --json headRefName,url`
+#[derive(Debug, serde::Deserialize)]
+struct OpenPr {
+    #[serde(rename = "headRefName")]
+    head_ref_name: String,
+    url: String,
+}
+
+/// URLs of open PRs keyed by head branch, from a single `gh pr list`
+fn open_pr_urls(runner: &dyn CommandRunner, repo: Option<&str>) -> Result<HashMap<String, String>> {
+    let output = runner.run(
+        "gh",
+        &super::gh_args(repo, &["pr", "list", "--state", "open", "--json", "headRefName,url", "--limit", "200"]),
+    )?;
+    let prs: Vec<OpenPr> = serde_json::from_str(&output).context("Failed to parse gh pr list output")?;
+    Ok(prs.into_iter().map(|pr| (pr.head_ref_name, pr.url)).collect())
+}
+
+/// Decide what a push does for a change's PR, given the bookmark it would be pushed
+/// as and any PR already open for it. Without a bookmark or gh there's nothing to do.
+pub fn decide_action(bookmark: Option<&str>, gh_available: bool, existing_pr: Option<&str>) -> PushAction {
+    match (bookmark, gh_available, existing_pr) {
+        (None, _, _) | (_, false, _) => PushAction::Skip,
+        (Some(_), true, Some(_)) => PushAction::UpdatePr,
+        (Some(_), true, None) => PushAction::CreatePr,
+    }
+}
+
+/// `--bookmark` names exactly one change: refuse a selection with several
+/// unbookmarked changes rather than guess which one gets the name
+fn check_bookmark_target(changes: &[jj::Change], bookmark: &str) -> Result<()> {
+    let unbookmarked: Vec<&str> = changes
+        .iter()
+        .filter(|c| c.bookmarks.is_empty())
+        .map(|c| &c.change_id[..8.min(c.change_id.len())])
+        .collect();
+    if unbookmarked.len() > 1 {
+        anyhow::bail!(
+            "--bookmark {} is ambiguous: {} selected changes have no bookmark ({}). Narrow --revision to one change",
+            bookmark,
+            unbookmarked.len(),
+            unbookmarked.join(", ")
+        );
+    }
     Ok(())
 }
 
-/// Ensure the primary branch (e.g., main) exists on the remote.
-/// If there's no main@origin, create it from the root of the stack.
-fn ensure_primary_exists(config: &Config, renderer: &Renderer) -> Result<()> {
+/// Build the dry-run plan for `changes`. `provided_bookmark` (from `--bookmark`)
+/// names changes without one; `lookup_pr` finds an existing PR for a bookmark.
+fn build_plan(
+    changes: &[jj::Change],
+    provided_bookmark: Option<&str>,
+    prefix: &str,
+    gh_available: bool,
+    lookup_pr: impl Fn(&str) -> Result<Option<String>>,
+) -> Result<Vec<PlanEntry>> {
+    let mut plan = Vec::new();
+    for change in changes {
+        let needs_bookmark = change.bookmarks.is_empty();
+        let bookmark = match change.bookmarks.first() {
+            Some(b) => Some(b.clone()),
+            None => provided_bookmark.map(|b| format!("{}{}", prefix, b)),
+        };
+        // A freshly created bookmark can't have a PR yet
+        let existing_pr = match &bookmark {
+            Some(b) if gh_available && !needs_bookmark => lookup_pr(b)?,
+            _ => None,
+        };
+
+        plan.push(PlanEntry {
+            change_id: change.change_id.clone(),
+            short_id: change.change_id[..8.min(change.change_id.len())].to_string(),
+            description: change.description.lines().next().unwrap_or("").to_string(),
+            action: decide_action(bookmark.as_deref(), gh_available, existing_pr.as_deref()),
+            bookmark,
+            needs_bookmark,
+        });
+    }
+    Ok(plan)
+}
+
+/// Run the `[hooks] pre_push` command with `run`, failing if it fails. Hooks stream
+/// their output and run once: no spinner hiding it, no retries repeating a failure.
+fn run_pre_push_hook(
+    renderer: &Renderer,
+    hook: &HookCommand,
+    run: impl FnOnce(&str, &[&str]) -> Result<()>,
+) -> Result<()> {
+    let result = match hook {
+        HookCommand::Shell(command) => {
+            renderer.info(&format!("Running pre-push hook: {}", command));
+            run("sh", &["-c", command])
+        }
+        HookCommand::Argv(argv) => {
+            let Some((program, args)) = argv.split_first() else {
+                return Ok(());
+            };
+            renderer.info(&format!("Running pre-push hook: {}", argv.join(" ")));
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            run(program, &args)
+        }
+    };
+
+    if let Err(e) = result {
+        renderer.error("Pre-push hook failed (use --no-verify to skip):");
+        eprintln!("{}", e.to_string().trim());
+        anyhow::bail!("Pre-push hook failed");
+    }
+    Ok(())
+}
+
+/// With `require_signed`, warn about changes in `revset` whose commits aren't
+/// signed, and refuse to continue if `block` is set
+fn check_signatures(
+    runner: &dyn CommandRunner,
+    renderer: &Renderer,
+    require_signed: bool,
+    revset: &str,
+    block: bool,
+) -> Result<()> {
+    if !require_signed {
+        return Ok(());
+    }
+
+    let unsigned = find_unsigned_changes(runner, revset)?;
+    if unsigned.is_empty() {
+        return Ok(());
+    }
+
+    renderer.warning("Changes with unsigned commits:");
+    for change_id in &unsigned {
+        eprintln!("  {}", &change_id[..8.min(change_id.len())]);
+    }
+    if block {
+        renderer.info("Sign them with: jj sign -r <change-id> (or disable [github] require_signed)");
+        anyhow::bail!("Commits must be signed before pushing");
+    }
+    Ok(())
+}
+
+/// Change IDs in `revset` whose commits have no cryptographic signature
+fn find_unsigned_changes(runner: &dyn CommandRunner, revset: &str) -> Result<Vec<String>> {
+    let output = runner.run(
+        "jj",
+        &[
+            "log",
+            "-r",
+            revset,
+            "--no-graph",
+            "-T",
+            r#"change_id ++ " " ++ if(signature, "signed", "unsigned") ++ "\n""#,
+        ],
+    )?;
+
+    Ok(output
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .filter(|(_, status)| status.trim() == "unsigned")
+        .map(|(change_id, _)| change_id.to_string())
+        .collect())
+}
+
+/// Fetch the remote state of `bookmark` and refuse a squash (force) push when it has
+/// diverged from the local bookmark, unless `force` is set. Ahead, synced, and new
+/// bookmarks proceed, and so do rewrites of your own changes (amending or squashing
+/// what was pushed), which diverge without discarding anything.
+pub fn guard_force_push(
+    runner: &dyn CommandRunner,
+    renderer: &Renderer,
+    bookmark: &str,
+    remote: &str,
+    force: bool,
+) -> Result<()> {
+    if force {
+        return Ok(());
+    }
+
+    // Fetch just this bookmark so the remote bookmark is current
+    let _ = renderer.with_spinner("checking remote", || {
+        runner.run("jj", &["git", "fetch", "--remote", remote, "-b", bookmark])
+    });
+
+    let state = jj::query_sync_state_with(runner, bookmark, remote).unwrap_or_default();
+    let BookmarkSyncState::Diverged { local_ahead, remote_ahead, .. } = state else {
+        return Ok(());
+    };
+    let overwritten = overwritten_commits(runner, bookmark, remote);
+    if overwritten.is_empty() {
+        return Ok(());
+    }
+
+    let mut authors: Vec<String> = overwritten.iter().map(|c| c.author()).collect();
+    authors.sort();
+    authors.dedup();
+    renderer.warning(&format!(
+        "{}@{} has diverged ({} local, {} remote commit(s)): force-pushing would overwrite {} commit(s) by {}",
+        bookmark,
+        remote,
+        local_ahead,
+        remote_ahead,
+        overwritten.len(),
+        authors.join(", ")
+    ));
+    renderer.info("Pull the remote changes first, or use --force to overwrite them");
+    anyhow::bail!("Refusing to force-push over diverged {}@{} (use --force to override)", bookmark, remote)
+}
+
+/// A commit from `jj log -T COMMIT_TEMPLATE`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LoggedCommit {
+    change_id: String,
+    commit_id: String,
+    author_email: String,
+    author_name: String,
+}
+
+impl LoggedCommit {
+    /// "Name <email>", or just the email without a name
+    fn author(&self) -> String {
+        if self.author_name.is_empty() {
+            self.author_email.clone()
+        } else {
+            format!("{} <{}>", self.author_name, self.author_email)
+        }
+    }
+}
+
+/// Change ID, commit ID, author email, and author name of each commit, one per line
+const COMMIT_TEMPLATE: &str =
+    r#"change_id ++ " " ++ commit_id ++ " " ++ author.email() ++ " " ++ author.name() ++ "\n""#;
+
+/// Commit IDs of a commit and all its predecessors, one per line
+const EVOLOG_TEMPLATE: &str = r#"commit_id ++ "\n""#;
+
+fn parse_commits(output: &str) -> Vec<LoggedCommit> {
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, ' ');
+            Some(LoggedCommit {
+                change_id: fields.next()?.to_string(),
+                commit_id: fields.next()?.to_string(),
+                author_email: fields.next().unwrap_or("").to_string(),
+                author_name: fields.next().unwrap_or("").trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Commits on `bookmark@remote` that a force-push would discard: those outside the local
+/// history that aren't an earlier version of a local change (same change ID, or a
+/// predecessor of one). Empty when the remote bookmark doesn't exist.
+fn overwritten_commits(runner: &dyn CommandRunner, bookmark: &str, remote: &str) -> Vec<LoggedCommit> {
+    let remote_ref = format!("{}@{}", bookmark, remote);
+    let log = |revset: &str| {
+        runner
+            .run("jj", &["log", "-r", revset, "--no-graph", "-T", COMMIT_TEMPLATE])
+            .map(|output| parse_commits(&output))
+    };
+
+    let Ok(remote_only) = log(&format!("{} ~ ::{}", remote_ref, bookmark)) else {
+        return Vec::new();
+    };
+    if remote_only.is_empty() {
+        return remote_only;
+    }
+
+    let local_only = log(&format!("{}..{}", remote_ref, bookmark)).unwrap_or_default();
+    // A change squashed into another lives on only as a predecessor of the result
+    let predecessors: HashSet<String> = local_only
+        .iter()
+        .filter_map(|c| {
+            runner
+                .run("jj", &["evolog", "-r", &c.commit_id, "--no-graph", "-T", EVOLOG_TEMPLATE])
+                .ok()
+        })
+        .flat_map(|output| output.lines().map(|l| l.trim().to_string()).collect::<Vec<_>>())
+        .collect();
+
+    remote_only
+        .into_iter()
+        .filter(|c| !local_only.iter().any(|l| l.change_id == c.change_id))
+        .filter(|c| !predecessors.contains(&c.commit_id))
+        .collect()
+}
+
+/// Check the changes selected by `revset` all belong to the stack. Off-stack changes
+/// are an error when `stack_only`, and a warning otherwise.
+fn check_on_stack(
+    runner: &dyn CommandRunner,
+    renderer: &Renderer,
+    revset: &str,
+    stack_revset: &str,
+    stack_only: bool,
+) -> Result<()> {
+    let off_stack = format!("({}) ~ ({})", revset, stack_revset);
+    let output = runner.run("jj", &["log", "-r", &off_stack, "--no-graph", "-T", "change_id ++ \"\\n\""])?;
+    let ids: Vec<&str> = output
+        .lines()
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| &id[..8.min(id.len())])
+        .collect();
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let message = format!("{} selected change(s) aren't in your stack: {}", ids.len(), ids.join(", "));
+    if stack_only {
+        renderer.error(&message);
+        anyhow::bail!("Refusing to push changes outside the stack (--stack-only)");
+    }
+    renderer.warning(&message);
+    renderer.info("Use --stack-only to refuse pushing changes outside the stack");
+    Ok(())
+}
+
+/// Find stack changes directly below the selection that aren't on the remote.
+/// These are the parents the selected changes' PRs would be based on.
+fn find_unpushed_ancestors(config: &Config, revset: &str) -> Result<Vec<jj::Change>> {
+    let missing = format!(
+        "(({revset})- ~ ({revset})) & ({stack}) ~ remote_bookmarks(remote=exact:\"{remote}\")",
+        revset = revset,
+        stack = config.stack_revset(),
+        remote = config.remote.name,
+    );
+    jj::query_changes(&RealRunner::new(&config.tools), &missing)
+}
+
+/// Where the primary bookmark (e.g., main) currently exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PrimaryPresence {
+    /// Both `main` and `main@origin`
+    Both,
+    /// Only `main@origin`: the local bookmark was deleted or never tracked
+    RemoteOnly,
+    /// Only `main`: it was never pushed
+    LocalOnly,
+    Neither,
+}
+
+/// Check whether the primary bookmark exists locally and on the remote
+fn primary_presence(runner: &dyn CommandRunner, primary: &str, remote: &str) -> PrimaryPresence {
+    let exists = |rev: &str| runner.run_success("jj", &["log", "-r", rev, "--limit", "1", "--no-graph", "-T", "''"]);
+    let local = exists(primary);
+    let on_remote = exists(&format!("{}@{}", primary, remote));
+
+    match (local, on_remote) {
+        (true, true) => PrimaryPresence::Both,
+        (false, true) => PrimaryPresence::RemoteOnly,
+        (true, false) => PrimaryPresence::LocalOnly,
+        (false, false) => PrimaryPresence::Neither,
+    }
+}
+
+/// Repair the primary bookmark when only one side has it: track the remote
+/// bookmark if the local one is missing, or push the local one if the remote
+/// is missing. Returns the presence found, so the caller can create it when
+/// neither side has it.
+fn repair_primary(
+    runner: &dyn CommandRunner,
+    renderer: &Renderer,
+    primary: &str,
+    remote: &str,
+) -> Result<PrimaryPresence> {
+    let presence = primary_presence(runner, primary, remote);
+    match presence {
+        PrimaryPresence::Both | PrimaryPresence::Neither => {}
+        PrimaryPresence::RemoteOnly => {
+            // Re-creating it locally would conflict with the remote bookmark
+            let primary_ref = format!("{}@{}", primary, remote);
+            renderer.info(&format!("No local {} bookmark, tracking {}...", primary, primary_ref));
+            runner.run("jj", &["bookmark", "track", &primary_ref])?;
+        }
+        PrimaryPresence::LocalOnly => {
+            renderer.info(&format!("No {} branch on {}, pushing the local one...", primary, remote));
+            runner.run("jj", &["git", "push", "--remote", remote, "--bookmark", primary, "--allow-new"])?;
+            renderer.success(&format!("Created {} branch on {}", primary, remote));
+        }
+    }
+    Ok(presence)
+}
+
+/// Ensure the primary branch (e.g., main) exists both locally and on the remote.
+/// If neither has it, create it from the root of the stack.
+fn ensure_primary_exists(config: &Config, runner: &dyn CommandRunner, renderer: &Renderer) -> Result<()> {
     let primary = &config.remote.primary;
     let remote = &config.remote.name;
-    let primary_ref = format!("{}@{}", primary, remote);
+    let local = RealRunner::new(&config.tools);
 
-    // Check if primary@remote exists
-    let result = jj::run_jj(&["log", "-r", &primary_ref, "--limit", "1", "--no-graph"]);
-    if result.is_ok() {
-        // Primary exists on remote, nothing to do
+    if repair_primary(runner, renderer, primary, remote)? != PrimaryPresence::Neither {
         return Ok(());
     }
 
-    // Primary doesn't exist on remote - find the base of the stack and create it
+    // Primary doesn't exist anywhere - find the base of the stack and create it
     renderer.info(&format!("No {} branch on remote, creating it...", primary));
 
     // Find the root commit(s) that our stack is based on
@@ -157,7 +936,7 @@ fn ensure_primary_exists(config: &Config, renderer: &Renderer) -> Result<()> {
     // We want: roots(stack)- which gives us the parent of the stack root
     let base_revset = format!("roots({})~", stack_revset);
 
-    let base_result = jj::run_jj(&[
+    let base_result = jj::run_jj(&local, &[
         "log", "-r", &base_revset,
         "--no-graph", "-T", "change_id", "--limit", "1"
     ]);
@@ -168,7 +947,7 @@ fn ensure_primary_exists(config: &Config, renderer: &Renderer) -> Result<()> {
             // No base found - stack might start from root
             // In this case, we need to find the first commit in the stack
             // and create main pointing to its parent (which would be root)
-            let root_result = jj::run_jj(&[
+            let root_result = jj::run_jj(&local, &[
                 "log", "-r", &format!("roots({})", stack_revset),
                 "--no-graph", "-T", "change_id", "--limit", "1"
             ])?;
@@ -178,7 +957,7 @@ fn ensure_primary_exists(config: &Config, renderer: &Renderer) -> Result<()> {
             }
             // Get the parent of the stack root
             let short_id = &stack_root[..8.min(stack_root.len())];
-            let parent_result = jj::run_jj(&[
+            let parent_result = jj::run_jj(&local, &[
                 "log", "-r", &format!("{}-", short_id),
                 "--no-graph", "-T", "change_id", "--limit", "1"
             ])?;
@@ -192,7 +971,7 @@ fn ensure_primary_exists(config: &Config, renderer: &Renderer) -> Result<()> {
         renderer.info("Stack starts from root - using first commit as main branch");
 
         // Get the first commit in the stack
-        let first_commit = jj::run_jj(&[
+        let first_commit = jj::run_jj(&local, &[
             "log", "-r", &format!("roots({})", stack_revset),
             "--no-graph", "-T", "change_id", "--limit", "1"
         ])?;
@@ -205,9 +984,9 @@ fn ensure_primary_exists(config: &Config, renderer: &Renderer) -> Result<()> {
         // Create main bookmark at first commit and push
         let short_id = &first_id[..8.min(first_id.len())];
         // Use set instead of create in case bookmark already exists locally
-        let _ = jj::run_jj(&["bookmark", "create", primary, "-r", short_id]);
-        let _ = jj::run_jj(&["bookmark", "set", primary, "-r", short_id]);
-        jj::run_jj(&["git", "push", "--bookmark", primary, "--allow-new"])?;
+        let _ = jj::run_jj(&local, &["bookmark", "create", primary, "-r", short_id]);
+        let _ = jj::run_jj(&local, &["bookmark", "set", primary, "-r", short_id]);
+        jj::run_jj(&local, &["git", "push", "--bookmark", primary, "--allow-new"])?;
         renderer.success(&format!("Created {} branch on {}", primary, remote));
 
         return Ok(());
@@ -216,14 +995,180 @@ fn ensure_primary_exists(config: &Config, renderer: &Renderer) -> Result<()> {
     // Create the primary bookmark at the base
     let short_base = &base_change_id[..8.min(base_change_id.len())];
     // Use set instead of create in case bookmark already exists locally
-    let _ = jj::run_jj(&["bookmark", "create", primary, "-r", short_base]);
-    let _ = jj::run_jj(&["bookmark", "set", primary, "-r", short_base]);
-    jj::run_jj(&["git", "push", "--bookmark", primary, "--allow-new"])?;
+    let _ = jj::run_jj(&local, &["bookmark", "create", primary, "-r", short_base]);
+    let _ = jj::run_jj(&local, &["bookmark", "set", primary, "-r", short_base]);
+    jj::run_jj(&local, &["git", "push", "--bookmark", primary, "--allow-new"])?;
     renderer.success(&format!("Created {} branch on {}", primary, remote));
 
     Ok(())
 }
 
+/// Show the whole stack, ask for every missing bookmark name, then confirm.
+/// Returns the new names by change ID, or None if the user declined.
+fn plan_bookmarks_interactively(
+    runner: &RealRunner,
+    renderer: &Renderer,
+    changes: &[jj::Change],
+    prefix: &str,
+) -> Result<Option<HashMap<String, String>>> {
+    println!("\nStack to push:");
+    for change in changes {
+        let short_id = &change.change_id[..8.min(change.change_id.len())];
+        let desc = change.description.lines().next().unwrap_or("(no description)");
+        let bookmark = change.bookmarks.first().map(String::as_str).unwrap_or("(needs bookmark)");
+        println!("  {} {}  {}", short_id, desc, bookmark);
+    }
+    println!();
+
+    let existing = jj::query_local_bookmark_names(runner)?;
+    let planned = collect_bookmark_names(renderer, changes, prefix, &existing, prompt_bookmark_name)?;
+    if planned.is_empty() {
+        return Ok(Some(planned));
+    }
+
+    print!("Create {} bookmark(s) and push? [Y/n]: ", planned.len());
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let confirmed = matches!(input.trim(), "" | "y" | "Y" | "yes");
+
+    Ok(confirmed.then_some(planned))
+}
+
+/// Ask `prompt` for a bookmark name for each change without one, applying `prefix`.
+/// An empty answer skips the change; a name already taken (by an existing bookmark
+/// or an earlier answer) is asked again.
+fn collect_bookmark_names(
+    renderer: &Renderer,
+    changes: &[jj::Change],
+    prefix: &str,
+    existing: &[String],
+    mut prompt: impl FnMut(&str, &str) -> Result<String>,
+) -> Result<HashMap<String, String>> {
+    let mut planned: HashMap<String, String> = HashMap::new();
+
+    for change in changes.iter().filter(|c| c.bookmarks.is_empty()) {
+        let short_id = &change.change_id[..8.min(change.change_id.len())];
+        let desc = change.description.lines().next().unwrap_or("(no description)");
+
+        loop {
+            let name = prompt(short_id, desc)?;
+            if name.is_empty() {
+                break;
+            }
+            let full_name = format!("{}{}", prefix, name.strip_prefix(prefix).unwrap_or(&name));
+            if let Err(e) = jj::validate_bookmark_name(&full_name) {
+                renderer.warning(&e.to_string());
+                continue;
+            }
+            if existing.contains(&full_name) || planned.values().any(|n| *n == full_name) {
+                renderer.warning(&format!("Bookmark '{}' is already taken", full_name));
+                continue;
+            }
+            planned.insert(change.change_id.clone(), full_name);
+            break;
+        }
+    }
+
+    Ok(planned)
+}
+
+/// Refuse to push more than `max_stack` changes unless `confirm` says otherwise
+fn check_stack_size(
+    renderer: &Renderer,
+    count: usize,
+    max_stack: usize,
+    confirm: impl FnOnce() -> Result<bool>,
+) -> Result<()> {
+    if count <= max_stack {
+        return Ok(());
+    }
+
+    renderer.warning(&format!(
+        "Stack has {} changes, more than the limit of {}",
+        count, max_stack
+    ));
+    renderer.info("Check [remote] primary (or --base) if the stack reaches into old history");
+    if confirm()? {
+        return Ok(());
+    }
+    anyhow::bail!(
+        "Refusing to push {} changes; pass --max-stack {} to allow it",
+        count,
+        count
+    )
+}
+
+/// Ask whether to push an oversized stack (never when stdin isn't a terminal)
+fn prompt_push_anyway(count: usize) -> Result<bool> {
+    if !io::stdin().is_terminal() {
+        return Ok(false);
+    }
+    print!("Push all {} changes anyway? [y/N]: ", count);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    Ok(matches!(input.trim(), "y" | "Y" | "yes"))
+}
+
+/// Give each change without a description one from `ask`, describing it in place
+/// with `jj describe`. Changes left empty are skipped, so they're dropped from the result.
+fn describe_empty_changes(
+    runner: &dyn CommandRunner,
+    renderer: &Renderer,
+    changes: Vec<jj::Change>,
+    mut ask: impl FnMut(&jj::Change) -> Result<String>,
+) -> Result<Vec<jj::Change>> {
+    let mut described = Vec::new();
+    for mut change in changes {
+        if change.description.trim().is_empty() {
+            let short_id = &change.change_id[..8.min(change.change_id.len())];
+            let description = ask(&change)?;
+            if description.trim().is_empty() {
+                renderer.info(&format!("Skipping {} (no description)", short_id));
+                continue;
+            }
+            runner.run("jj", &["describe", "-r", &change.change_id, "-m", &description])?;
+            change.description = description;
+        }
+        described.push(change);
+    }
+    Ok(described)
+}
+
+const CONVENTIONAL_TYPES: &[&str] = &["feat", "fix", "chore", "docs", "refactor", "test", "perf"];
+
+/// Whether `subject` matches `^(feat|fix|chore|docs|refactor|test|perf)(\(.+\))?!?: .+`
+fn is_conventional_subject(subject: &str) -> bool {
+    let Some(rest) = CONVENTIONAL_TYPES.iter().find_map(|t| subject.strip_prefix(t)) else {
+        return false;
+    };
+    let ends_subject = |rest: &str| {
+        let rest = rest.strip_prefix('!').unwrap_or(rest);
+        rest.strip_prefix(": ").is_some_and(|summary| !summary.is_empty())
+    };
+    match rest.strip_prefix('(') {
+        // The scope may itself contain `)`, so any non-empty split that ends the subject will do
+        Some(scoped) => scoped
+            .match_indices(')')
+            .any(|(end, _)| end > 0 && ends_subject(&scoped[end + 1..])),
+        None => ends_subject(rest),
+    }
+}
+
+fn prompt_description(change: &jj::Change) -> Result<String> {
+    let short_id = &change.change_id[..8.min(change.change_id.len())];
+    print!("Description for {} [skip]: ", short_id);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    Ok(input.trim().to_string())
+}
+
 fn prompt_bookmark_name(change_id: &str, description: &str) -> Result<String> {
     print!("Bookmark name for {} ({}) [skip]: ", change_id, description);
     io::stdout().flush()?;
@@ -234,46 +1179,74 @@ fn prompt_bookmark_name(change_id: &str, description: &str) -> Result<String> {
     Ok(input.trim().to_string())
 }
 
-fn push_bookmark(bookmark: &str, remote: &str, _force: bool) -> Result<()> {
+/// Push a bookmark to the remote.
+///
+/// - squash: `jj git push --bookmark <name>` updates the remote branch in place,
+///   rewriting it to match the amended change (reviewers see a force-push).
+/// - append: the remote branch must be an ancestor of the local bookmark, so the
+///   push is a fast-forward that adds new commits on top of what reviewers already
+///   saw. If the change was rewritten instead, the push is refused rather than
+///   silently force-pushing.
+pub fn push_bookmark(runner: &dyn CommandRunner, bookmark: &str, remote: &str, squash: bool) -> Result<()> {
     // First, ensure the bookmark is tracked on the remote
     // This is needed for new bookmarks
     let track_ref = format!("{}@{}", bookmark, remote);
-    let _ = jj::run_jj(&["bookmark", "track", &track_ref]);
+    let _ = runner.run("jj", &["bookmark", "track", &track_ref]);
     // Ignore errors - bookmark might already be tracked or not exist on remote yet
 
+    if !squash {
+        // Commits on the remote that aren't in the local history mean the push would
+        // discard them. A missing remote bookmark (new PR) makes the revset fail,
+        // which is fine - there's nothing to preserve.
+        let revset = format!("{} ~ ::{}", track_ref, bookmark);
+        if let Ok(output) = runner.run("jj", &["log", "-r", &revset, "--no-graph", "-T", "change_id"]) {
+            if !output.trim().is_empty() {
+                anyhow::bail!(
+                    "Append push for '{}' is not a fast-forward of {}. Add new changes on top instead of \
+                     amending, or push with --squash to overwrite",
+                    bookmark,
+                    track_ref
+                );
+            }
+        }
+    }
+
     // Push the bookmark
-    let args = vec!["git", "push", "--bookmark", bookmark];
-    jj::run_jj(&args)?;
+    runner.run("jj", &["git", "push", "--bookmark", bookmark])?;
     Ok(())
 }
 
-fn is_gh_available() -> bool {
-    Command::new("gh")
-        .arg("--version")
-        .output()
-        .is_ok()
+pub fn get_pr_for_branch(runner: &dyn CommandRunner, branch: &str, repo: Option<&str>) -> Result<Option<String>> {
+    // gh exits non-zero when the branch has no PR
+    let Ok(output) = runner.run("gh", &super::gh_args(repo, &["pr", "view", branch, "--json", "url", "-q", ".url"]))
+    else {
+        return Ok(None);
+    };
+    let url = output.trim();
+    Ok((!url.is_empty()).then(|| url.to_string()))
 }
 
-fn get_pr_for_branch(branch: &str) -> Result<Option<String>> {
-    let output = Command::new("gh")
-        .args(["pr", "view", branch, "--json", "url", "-q", ".url"])
-        .output()
-        .context("Failed to check for existing PR")?;
+/// Open the PR for each of `branches` in the browser (best-effort)
+fn open_prs(runner: &dyn CommandRunner, repo: Option<&str>, branches: &[String]) {
+    for branch in branches {
+        let _ = runner.run("gh", &super::gh_args(repo, &["pr", "view", branch, "--web"]));
+    }
+}
 
-    if output.status.success() {
-        let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        if !url.is_empty() {
-            return Ok(Some(url));
-        }
+/// Base for the next PR up a stack pushed bottom-up: the bookmark pushed just below
+/// it, or `bottom` (the parent's bookmark or trunk) for the first one
+fn stack_base(below: Option<&str>, bottom: impl FnOnce() -> Result<String>) -> Result<String> {
+    match below {
+        Some(bookmark) => Ok(bookmark.to_string()),
+        None => bottom(),
     }
-    Ok(None)
 }
 
-fn get_base_branch_for_change(change_id: &str, config: &Config) -> Result<String> {
+fn get_base_branch_for_change(runner: &dyn CommandRunner, change_id: &str, config: &Config) -> Result<String> {
     // Get parent of this change
     // Use short ID (first 8 chars) with `-` suffix for parent
     let short_id = &change_id[..8.min(change_id.len())];
-    let parent_output = jj::run_jj(&[
+    let parent_output = runner.run("jj", &[
         "log",
         "-r", &format!("{}-", short_id),
         "-T", "bookmarks",
@@ -296,60 +1269,981 @@ fn get_base_branch_for_change(change_id: &str, config: &Config) -> Result<String
     Ok(config.remote.primary.clone())
 }
 
-fn create_github_pr(branch: &str, base: &str, title: &str, body: &str) -> Result<()> {
-    let output = Command::new("gh")
-        .args([
-            "pr", "create",
-            "--head", branch,
-            "--base", base,
-            "--title", title,
-            "--body", body,
-        ])
-        .output()
-        .context("Failed to create PR with gh CLI")?;
+/// Body for `gh pr create`: inline, or a file edited with `--edit`
+#[derive(Debug, Clone, Copy)]
+enum PrBody<'a> {
+    Text(&'a str),
+    File(&'a Path),
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("gh pr create failed: {}", stderr);
+/// Write `body` to a temp file and open it in `editor` (via `open`), returning the file
+/// for `gh pr create --body-file`. None when there's no editor to use.
+fn edit_pr_body(
+    branch: &str,
+    body: &str,
+    editor: Option<String>,
+    open: impl FnOnce(&str, &Path) -> Result<()>,
+) -> Result<Option<PathBuf>> {
+    let Some(editor) = editor.filter(|e| !e.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let name: String = branch.chars().map(|c| if c.is_alphanumeric() { c } else { '-' }).collect();
+    let path = std::env::temp_dir().join(format!("jf-pr-{}-{}.md", std::process::id(), name));
+    std::fs::write(&path, body).context("Could not write the PR body for editing")?;
+    if let Err(e) = open(&editor, &path) {
+        let _ = std::fs::remove_file(&path);
+        return Err(e);
     }
+    Ok(Some(path))
+}
 
-    // Print gh output (contains PR URL)
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    print!("{}", stdout);
-
+/// Run `editor` on `path` and wait for it; the editor may carry arguments (e.g. "code -w")
+fn open_editor(editor: &str, path: &Path) -> Result<()> {
+    let status = std::process::Command::new("sh")
+        .args(["-c", &format!("{} \"$1\"", editor), "sh"])
+        .arg(path)
+        .status()
+        .with_context(|| format!("Could not run editor '{}'", editor))?;
+    if !status.success() {
+        anyhow::bail!("Editor '{}' exited with {}; PR not created", editor, status);
+    }
     Ok(())
 }
 
-fn create_pr_body_with_stack(change: &jj::Change, config: &Config) -> Result<String> {
+fn create_github_pr(
+    runner: &dyn CommandRunner,
+    repo: Option<&str>,
+    branch: &str,
+    base: &str,
+    title: &str,
+    body: PrBody,
+) -> Result<String> {
+    let (body_flag, body) = match body {
+        PrBody::Text(text) => ("--body", text.to_string()),
+        PrBody::File(path) => ("--body-file", path.display().to_string()),
+    };
+    let output = runner
+        .run(
+            "gh",
+            &super::gh_args(repo, &[
+                "pr", "create",
+                "--head", branch,
+                "--base", base,
+                "--title", title,
+                body_flag, &body,
+            ]),
+        )
+        .context("gh pr create failed")?;
+
+    Ok(output)
+}
+
+pub fn create_pr_body_with_stack(change: &jj::Change, config: &Config) -> Result<String> {
+    // Get stack to find related changes
+    let revset = config.stack_revset();
+    let stack = jj::get_stack(&RealRunner::new(&config.tools), &revset, &config.remote.name)?;
+    Ok(format_pr_body(change, &stack))
+}
+
+/// Full descriptions of the commits a PR from `bookmark` into `base` contains, oldest first
+fn pr_commit_descriptions(runner: &dyn CommandRunner, base: &str, bookmark: &str) -> Result<Vec<String>> {
+    let revset = format!("{}..{}", base, bookmark);
+    let output = runner.run(
+        "jj",
+        &["log", "-r", &revset, "--reversed", "--no-graph", "-T", "description ++ \"\\0\""],
+    )?;
+    Ok(output
+        .split('\0')
+        .map(str::trim)
+        .filter(|description| !description.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// PR title and body for a PR spanning several commits (oldest first): the title
+/// from the bottom commit, the body every description in order
+fn combine_descriptions(descriptions: &[String]) -> Option<(String, String)> {
+    let bottom = descriptions.first()?;
+    Some((jj::types::pr_title(bottom), descriptions.join("\n\n")))
+}
+
+/// Shorten `title` to at most `max_len` characters, ending in "…", or None if it
+/// already fits (or `max_len` is 0, which disables the limit)
+fn fit_pr_title(title: &str, max_len: usize) -> Option<String> {
+    if max_len == 0 || title.chars().count() <= max_len {
+        return None;
+    }
+    let kept: String = title.chars().take(max_len - 1).collect();
+    Some(format!("{}…", kept.trim_end()))
+}
+
+/// PR body: the change's description followed by its position in `stack`
+fn format_pr_body(change: &jj::Change, stack: &[ChangeWithStatus]) -> String {
     let mut body = change.description.clone();
 
     // Add stack context
     body.push_str("\n\n---\n\n");
     body.push_str("**Part of stack:**\n\n");
 
-    // Get stack to find related changes
-    let revset = config.stack_revset();
-    let stack = jj::get_stack(&revset, &config.remote.name)?;
-
     // Find this change's position in stack
     let mut found_current = false;
-    for item in &stack {
+    for item in stack {
         if item.change.change_id == change.change_id {
             found_current = true;
-            body.push_str(&format!(
-                "- **This PR** ({})\n",
-                change.description.lines().next().unwrap_or("This change")
-            ));
+            body.push_str(&format!("- **This PR** ({})\n", stack_title(change)));
         } else if let Some(bookmark) = &item.bookmark {
             let status = if found_current { "⏳" } else { "✓" };
             body.push_str(&format!(
                 "- {} {} (bookmark: `{}`)\n",
                 status,
-                item.change.description.lines().next().unwrap_or("Change"),
+                stack_title(&item.change),
                 bookmark
             ));
         }
     }
 
-    Ok(body)
+    body.trim_end().to_string()
+}
+
+/// First description line for the stack list, marking changes that still need one
+fn stack_title(change: &jj::Change) -> &str {
+    match change.description.lines().next().map(str::trim) {
+        Some(line) if !line.is_empty() => line,
+        _ => "(no description)",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jj::runner::mock::MockRunner;
+
+    const APPEND_CHECK: &str = "jj log -r feature@origin ~ ::feature --no-graph -T change_id";
+    const STACK: &str = "::@ ~ ::main@origin";
+
+    #[test]
+    fn test_push_bookmark_squash_pushes_in_place() {
+        let runner = MockRunner::new();
+        runner.mock_response("jj bookmark track feature@origin", "");
+        runner.mock_response("jj git push --bookmark feature", "");
+
+        push_bookmark(&runner, "feature", "origin", true).unwrap();
+
+        let calls = runner.get_calls();
+        assert_eq!(calls.len(), 2);
+        assert!(!runner.was_called("jj", &["log", "-r", "feature@origin ~ ::feature", "--no-graph", "-T", "change_id"]));
+        assert!(runner.was_called("jj", &["git", "push", "--bookmark", "feature"]));
+    }
+
+    #[test]
+    fn test_push_bookmark_append_checks_fast_forward() {
+        let runner = MockRunner::new();
+        runner.mock_response("jj bookmark track feature@origin", "");
+        runner.mock_response(APPEND_CHECK, "");
+        runner.mock_response("jj git push --bookmark feature", "");
+
+        push_bookmark(&runner, "feature", "origin", false).unwrap();
+
+        let calls = runner.get_calls();
+        assert_eq!(calls.len(), 3);
+        assert!(runner.was_called("jj", &["log", "-r", "feature@origin ~ ::feature", "--no-graph", "-T", "change_id"]));
+        assert!(runner.was_called("jj", &["git", "push", "--bookmark", "feature"]));
+    }
+
+    #[test]
+    fn test_push_bookmark_append_refuses_rewrite() {
+        let runner = MockRunner::new();
+        runner.mock_response("jj bookmark track feature@origin", "");
+        runner.mock_response(APPEND_CHECK, "qpvuntsm");
+
+        let result = push_bookmark(&runner, "feature", "origin", false);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("not a fast-forward"));
+        assert!(!runner.was_called("jj", &["git", "push", "--bookmark", "feature"]));
+    }
+
+    const REMOTE_ONLY: &str = r#"jj log -r feature@origin ~ ::feature --no-graph -T change_id ++ " " ++ commit_id ++ " " ++ author.email() ++ " " ++ author.name() ++ "\n""#;
+    const LOCAL_ONLY: &str = r#"jj log -r feature@origin..feature --no-graph -T change_id ++ " " ++ commit_id ++ " " ++ author.email() ++ " " ++ author.name() ++ "\n""#;
+
+    fn evolog_key(commit_id: &str) -> String {
+        format!("jj evolog -r {} --no-graph -T {}", commit_id, EVOLOG_TEMPLATE)
+    }
+
+    /// Mock `jj bookmark list` for feature, with feature@origin `ahead`/`behind` the local bookmark
+    fn mock_sync_state(runner: &MockRunner, ahead: usize, behind: usize) {
+        let local = r#"{"name":"feature","remote":null,"change_id":"qpvuntsm","synced":false,"ahead":null,"behind":null}"#;
+        let remote = format!(
+            r#"{{"name":"feature","remote":"origin","change_id":"zsuskuln","synced":{},"ahead":{},"behind":{}}}"#,
+            ahead == 0 && behind == 0,
+            ahead,
+            behind
+        );
+        let output = format!("{}\n{}", local, remote);
+        runner.mock_response(&format!("jj bookmark list --all -T {} feature", jj::query::BOOKMARK_TEMPLATE), &output);
+    }
+
+    /// A runner for `guard_force_push` with `remote_only` commits on a diverged feature@origin
+    /// and the local feature amended from 1111 to 2222
+    fn mock_force_push(remote_only: &str) -> MockRunner {
+        let runner = MockRunner::new();
+        runner.mock_response("jj git fetch --remote origin -b feature", "");
+        mock_sync_state(&runner, remote_only.lines().count(), 1);
+        runner.mock_response(REMOTE_ONLY, remote_only);
+        runner.mock_response(LOCAL_ONLY, "qpvuntsm 2222 alice@example.com Alice\n");
+        runner.mock_response(&evolog_key("2222"), "2222\n1111\n");
+        runner
+    }
+
+    #[test]
+    fn test_force_push_allowed_after_amend() {
+        // Amending the pushed change diverges, but the old commit is an earlier version of ours
+        let runner = mock_force_push("qpvuntsm 1111 alice@example.com Alice\n");
+
+        guard_force_push(&runner, &test_renderer(), "feature", "origin", false).unwrap();
+        assert!(overwritten_commits(&runner, "feature", "origin").is_empty());
+    }
+
+    #[test]
+    fn test_force_push_allowed_after_squash() {
+        // The squashed-away change survives only as a predecessor
+        let runner = MockRunner::new();
+        runner.mock_response(REMOTE_ONLY, "qpvuntsm 1111 a@x A\nrlvkpnrz 3333 a@x A\n");
+        runner.mock_response(LOCAL_ONLY, "qpvuntsm 2222 a@x A\n");
+        runner.mock_response(&evolog_key("2222"), "2222\n1111\n3333\n");
+
+        assert!(overwritten_commits(&runner, "feature", "origin").is_empty());
+    }
+
+    #[test]
+    fn test_force_push_refused_when_diverged() {
+        // A reviewer pushed a fixup on top of our change
+        let runner = mock_force_push("qpvuntsm 1111 alice@example.com Alice\nzsuskuln 4444 bob@example.com Bob Reviewer\n");
+        let state = jj::query_sync_state_with(&runner, "feature", "origin").unwrap();
+        assert!(matches!(state, BookmarkSyncState::Diverged { local_ahead: 1, remote_ahead: 2, .. }));
+
+        let overwritten = overwritten_commits(&runner, "feature", "origin");
+        assert_eq!(overwritten.len(), 1);
+        assert_eq!(overwritten[0].change_id, "zsuskuln");
+        assert_eq!(overwritten[0].author(), "Bob Reviewer <bob@example.com>");
+
+        let err = guard_force_push(&runner, &test_renderer(), "feature", "origin", false).unwrap_err();
+        assert!(err.to_string().contains("diverged feature@origin"));
+        // --force skips the check entirely
+        guard_force_push(&MockRunner::new(), &test_renderer(), "feature", "origin", true).unwrap();
+    }
+
+    #[test]
+    fn test_force_push_refused_over_own_commit_from_elsewhere() {
+        // Our own commit pushed from another machine is still overwritten by a force push
+        let runner = mock_force_push("zsuskuln 4444 alice@example.com Alice\n");
+
+        let err = guard_force_push(&runner, &test_renderer(), "feature", "origin", false).unwrap_err();
+        assert!(err.to_string().contains("--force"));
+    }
+
+    #[test]
+    fn test_force_push_allows_ahead_synced_and_new() {
+        for (ahead, behind) in [(0, 2), (0, 0)] {
+            let runner = MockRunner::new();
+            runner.mock_response("jj git fetch --remote origin -b feature", "");
+            mock_sync_state(&runner, ahead, behind);
+            guard_force_push(&runner, &test_renderer(), "feature", "origin", false).unwrap();
+            // Only a diverged remote needs the commit-level check
+            assert!(!runner.get_calls().iter().any(|(_, args)| args.first().is_some_and(|a| a == "log")));
+        }
+
+        // No remote bookmark yet
+        let runner = MockRunner::new();
+        runner.mock_response(
+            &format!("jj bookmark list --all -T {} feature", jj::query::BOOKMARK_TEMPLATE),
+            r#"{"name":"feature","remote":null,"change_id":"qpvuntsm","synced":false,"ahead":null,"behind":null}"#,
+        );
+        guard_force_push(&runner, &test_renderer(), "feature", "origin", false).unwrap();
+        runner.mock_error(REMOTE_ONLY, "Revision `feature@origin` doesn't exist");
+        assert!(overwritten_commits(&runner, "feature", "origin").is_empty());
+    }
+
+    #[test]
+    fn test_push_bookmark_append_new_bookmark() {
+        // No remote bookmark yet: the ancestry check fails and the push proceeds
+        let runner = MockRunner::new();
+        runner.mock_error("jj bookmark track feature@origin", "no such remote bookmark");
+        runner.mock_error(APPEND_CHECK, "Revision `feature@origin` doesn't exist");
+        runner.mock_response("jj git push --bookmark feature", "");
+
+        push_bookmark(&runner, "feature", "origin", false).unwrap();
+        assert!(runner.was_called("jj", &["git", "push", "--bookmark", "feature"]));
+    }
+
+    fn change_from(json: &str) -> jj::Change {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_decide_action() {
+        assert_eq!(decide_action(None, true, None), PushAction::Skip);
+        assert_eq!(decide_action(Some("feature"), false, None), PushAction::Skip);
+        assert_eq!(decide_action(Some("feature"), true, Some("https://pr/1")), PushAction::UpdatePr);
+        assert_eq!(decide_action(Some("feature"), true, None), PushAction::CreatePr);
+    }
+
+    #[test]
+    fn test_build_plan_json() {
+        let changes = vec![
+            change_from(r#"{"change_id": "qpvuntsmwlqt", "commit_id": "abc", "description": "Add feature\n\nDetails", "bookmarks": ["feature"]}"#),
+            change_from(r#"{"change_id": "rlvkpnrzqnoo", "commit_id": "def", "description": "Follow-up"}"#),
+        ];
+        let lookup = |b: &str| -> Result<Option<String>> {
+            assert_eq!(b, "feature");
+            Ok(Some("https://github.com/o/r/pull/1".to_string()))
+        };
+
+        let plan = build_plan(&changes, None, "jf/", true, lookup).unwrap();
+        let json = serde_json::to_value(&plan).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {
+                    "change_id": "qpvuntsmwlqt",
+                    "short_id": "qpvuntsm",
+                    "description": "Add feature",
+                    "bookmark": "feature",
+                    "needs_bookmark": false,
+                    "action": "update-pr"
+                },
+                {
+                    "change_id": "rlvkpnrzqnoo",
+                    "short_id": "rlvkpnrz",
+                    "description": "Follow-up",
+                    "bookmark": null,
+                    "needs_bookmark": true,
+                    "action": "skip"
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_build_plan_provided_bookmark_creates_pr() {
+        let changes = vec![change_from(r#"{"change_id": "rlvkpnrzqnoo", "commit_id": "def", "description": "Follow-up"}"#)];
+        let lookup = |_: &str| -> Result<Option<String>> { panic!("new bookmarks have no PR") };
+
+        let plan = build_plan(&changes, Some("follow-up"), "jf/", true, lookup).unwrap();
+        assert_eq!(plan[0].bookmark.as_deref(), Some("jf/follow-up"));
+        assert!(plan[0].needs_bookmark);
+        assert_eq!(plan[0].action, PushAction::CreatePr);
+    }
+
+    #[test]
+    fn test_open_prs_views_each_on_web() {
+        let runner = MockRunner::new();
+        runner.mock_response("gh pr view feature-a --web", "");
+        runner.mock_response("gh pr view feature-b --web", "");
+
+        open_prs(&runner, None, &["feature-a".to_string(), "feature-b".to_string()]);
+
+        assert!(runner.was_called("gh", &["pr", "view", "feature-a", "--web"]));
+        assert!(runner.was_called("gh", &["pr", "view", "feature-b", "--web"]));
+        assert_eq!(runner.get_calls().len(), 2);
+    }
+
+    fn pushed_query(bookmark: &str) -> String {
+        format!(
+            "jj log -r remote_bookmarks(exact:\"{b}\", exact:\"origin\") & bookmarks(exact:\"{b}\") --no-graph -T commit_id",
+            b = bookmark
+        )
+    }
+
+    fn git_pushes(runner: &MockRunner) -> Vec<String> {
+        runner
+            .get_calls()
+            .into_iter()
+            .filter(|(_, args)| args[..2] == ["git", "push"])
+            .map(|(_, args)| args[3].clone())
+            .collect()
+    }
+
+    /// Bookmarked changes named "jf/<name>", newest first as `jj log` lists them
+    fn bookmarked_stack(names: &[&str]) -> Vec<jj::Change> {
+        names
+            .iter()
+            .map(|name| {
+                let mut change = unbookmarked(&format!("{:z<12}", name), name);
+                change.bookmarks = vec![format!("jf/{}", name)];
+                change
+            })
+            .collect()
+    }
+
+    /// Mock a first squash-style push of each of `changes`
+    fn mock_first_push(runner: &MockRunner, changes: &[jj::Change]) {
+        for change in changes {
+            let bookmark = &change.bookmarks[0];
+            runner.mock_error(&pushed_query(bookmark), "Revision doesn't exist");
+            runner.mock_response(&format!("jj bookmark track {}@origin", bookmark), "");
+            runner.mock_response(&format!("jj git push --bookmark {}", bookmark), "");
+        }
+    }
+
+    /// Run the push loop over `changes`, without stack context in PR bodies
+    fn push_stack(runner: &MockRunner, options: &PushOptions, prs: bool, changes: &[jj::Change]) -> Result<PushOutcome> {
+        let mut config = Config::default();
+        config.github.stack_context = false;
+        let renderer = test_renderer().with_quiet(true);
+        let context = PushContext {
+            runner,
+            local: runner,
+            renderer: &renderer,
+            config: &config,
+            options,
+            prs,
+        };
+        push_changes(&context, changes, &HashMap::new())
+    }
+
+    #[test]
+    fn test_push_resumes_after_mid_stack_failure() {
+        let changes = bookmarked_stack(&["third", "second", "first"]);
+        let options = PushOptions { no_pr: true, force: true, ..Default::default() };
+
+        // First run: the network drops while pushing "second"
+        let runner = MockRunner::new();
+        mock_first_push(&runner, &changes);
+        runner.mock_error("jj git push --bookmark jf/second", "Connection reset by peer");
+
+        assert!(push_stack(&runner, &options, false, &changes).is_err());
+        assert_eq!(git_pushes(&runner), vec!["jf/third", "jf/second"]);
+
+        // Re-run: "third" is on the remote now, so only the remainder is pushed
+        let runner = MockRunner::new();
+        mock_first_push(&runner, &changes);
+        runner.mock_response(&pushed_query("jf/third"), "0123abcd");
+
+        let outcome = push_stack(&runner, &options, false, &changes).unwrap();
+        assert_eq!(git_pushes(&runner), vec!["jf/second", "jf/first"]);
+        assert_eq!(outcome.up_to_date, 1);
+    }
+
+    #[test]
+    fn test_amend_descriptions_then_push() {
+        let renderer = test_renderer().with_quiet(true);
+        let changes = vec![
+            change_from(r#"{"change_id": "qpvuntsmwlqt", "commit_id": "abc", "description": ""}"#),
+            change_from(r#"{"change_id": "rlvkpnrzqnoo", "commit_id": "def", "description": ""}"#),
+            change_from(r#"{"change_id": "kkmpptxzrspx", "commit_id": "fed", "description": "Already described"}"#),
+        ];
+        let runner = MockRunner::new();
+        runner.mock_response("jj describe -r qpvuntsmwlqt -m Add widgets", "");
+
+        // Describe the first, leave the second empty
+        let answers = |change: &jj::Change| {
+            Ok(if change.change_id == "qpvuntsmwlqt" { "Add widgets" } else { "" }.to_string())
+        };
+        let changes = describe_empty_changes(&runner, &renderer, changes, answers).unwrap();
+
+        let ids: Vec<&str> = changes.iter().map(|c| c.change_id.as_str()).collect();
+        assert_eq!(ids, vec!["qpvuntsmwlqt", "kkmpptxzrspx"]);
+        assert_eq!(changes[0].description, "Add widgets");
+        assert!(runner.was_called("jj", &["describe", "-r", "qpvuntsmwlqt", "-m", "Add widgets"]));
+        assert!(!runner.get_calls().iter().any(|(_, args)| args.contains(&"rlvkpnrzqnoo".to_string())));
+
+        // The described change goes on to be pushed
+        runner.mock_error(&pushed_query("widgets"), "Revision doesn't exist");
+        runner.mock_response("jj bookmark track widgets@origin", "");
+        runner.mock_response("jj git push --bookmark widgets", "");
+        assert!(push_if_needed(&runner, &renderer, "widgets", "origin", true, true).unwrap());
+        assert_eq!(git_pushes(&runner), vec!["widgets"]);
+    }
+
+    #[test]
+    fn test_no_pr_pushes_without_gh() {
+        let caps = Capabilities { jj: true, gh: true };
+        let options = PushOptions { no_pr: true, force: true, ..Default::default() };
+        assert!(prs_enabled(&caps, &PushOptions::default()));
+
+        let changes = bookmarked_stack(&["two", "one"]);
+        let runner = MockRunner::new();
+        mock_first_push(&runner, &changes);
+        let outcome = push_stack(&runner, &options, prs_enabled(&caps, &options), &changes).unwrap();
+
+        assert_eq!(git_pushes(&runner), vec!["jf/two", "jf/one"]);
+        assert!(!runner.get_calls().iter().any(|(program, _)| program == "gh"));
+        assert!(outcome.to_open.is_empty());
+    }
+
+    #[test]
+    fn test_open_pr_urls_batches_lookup() {
+        let runner = MockRunner::new();
+        runner.mock_response(
+            "gh pr list --state open --json headRefName,url --limit 200",
+            r#"[{"headRefName":"first","url":"https://github.com/o/r/pull/1"},
+                {"headRefName":"second","url":"https://github.com/o/r/pull/2"}]"#,
+        );
+
+        let prs = open_pr_urls(&runner, None).unwrap();
+        assert_eq!(prs.get("second").map(String::as_str), Some("https://github.com/o/r/pull/2"));
+        assert!(!prs.contains_key("third"));
+        assert_eq!(runner.get_calls().len(), 1);
+    }
+
+    #[test]
+    fn test_gh_commands_target_configured_repo() {
+        let runner = MockRunner::new();
+        runner.mock_response(
+            "gh pr list --state open --json headRefName,url --limit 200 --repo acme/widgets",
+            "[]",
+        );
+        runner.mock_response(
+            "gh pr create --head feature --base main --title Add widgets --body Body --repo acme/widgets",
+            "https://github.com/acme/widgets/pull/7\n",
+        );
+        runner.mock_response("gh pr view feature --web --repo acme/widgets", "");
+
+        open_pr_urls(&runner, Some("acme/widgets")).unwrap();
+        let output =
+            create_github_pr(&runner, Some("acme/widgets"), "feature", "main", "Add widgets", PrBody::Text("Body")).unwrap();
+        // The URL comes back for the caller to print once the spinner is gone
+        assert_eq!(output, "https://github.com/acme/widgets/pull/7\n");
+        open_prs(&runner, Some("acme/widgets"), &["feature".to_string()]);
+
+        let calls = runner.get_calls();
+        assert_eq!(calls.len(), 3);
+        assert!(calls.iter().all(|(_, args)| args.ends_with(&["--repo".to_string(), "acme/widgets".to_string()])));
+    }
+
+    #[test]
+    fn test_gh_commands_without_repo_let_gh_infer() {
+        let runner = MockRunner::new();
+        runner.mock_response("gh pr create --head feature --base main --title Add widgets --body Body", "");
+
+        create_github_pr(&runner, None, "feature", "main", "Add widgets", PrBody::Text("Body")).unwrap();
+        assert!(!runner.get_calls()[0].1.iter().any(|a| a == "--repo"));
+    }
+
+    const SIGNATURE_QUERY: &str = r#"jj log -r ::@ ~ ::main@origin --no-graph -T change_id ++ " " ++ if(signature, "signed", "unsigned") ++ "\n""#;
+
+    fn test_renderer() -> Renderer {
+        Renderer::new(crate::ui::get_theme("default"), crate::ui::get_icon_set("ascii"))
+    }
+
+    fn mock_primary(local: bool, on_remote: bool) -> MockRunner {
+        let runner = MockRunner::new();
+        let key = |rev: &str| format!("jj log -r {} --limit 1 --no-graph -T ''", rev);
+        for (rev, exists) in [("main", local), ("main@origin", on_remote)] {
+            if exists {
+                runner.mock_response(&key(rev), "");
+            } else {
+                runner.mock_error(&key(rev), "Revision doesn't exist");
+            }
+        }
+        runner.mock_response("jj bookmark track main@origin", "");
+        runner.mock_response("jj git push --remote origin --bookmark main --allow-new", "");
+        runner
+    }
+
+    fn repaired(runner: &MockRunner) -> (bool, bool) {
+        let tracked = runner.was_called("jj", &["bookmark", "track", "main@origin"]);
+        let pushed = runner.was_called("jj", &["git", "push", "--remote", "origin", "--bookmark", "main", "--allow-new"]);
+        (tracked, pushed)
+    }
+
+    #[test]
+    fn test_repair_primary_both_present() {
+        let renderer = Renderer::new(crate::ui::get_theme("default"), crate::ui::get_icon_set("ascii"));
+        let runner = mock_primary(true, true);
+        let presence = repair_primary(&runner, &renderer, "main", "origin").unwrap();
+        assert_eq!(presence, PrimaryPresence::Both);
+        assert_eq!(repaired(&runner), (false, false));
+    }
+
+    #[test]
+    fn test_repair_primary_remote_only_tracks() {
+        let renderer = Renderer::new(crate::ui::get_theme("default"), crate::ui::get_icon_set("ascii"));
+        let runner = mock_primary(false, true);
+        let presence = repair_primary(&runner, &renderer, "main", "origin").unwrap();
+        assert_eq!(presence, PrimaryPresence::RemoteOnly);
+        assert_eq!(repaired(&runner), (true, false));
+    }
+
+    #[test]
+    fn test_repair_primary_local_only_pushes() {
+        let renderer = Renderer::new(crate::ui::get_theme("default"), crate::ui::get_icon_set("ascii"));
+        let runner = mock_primary(true, false);
+        let presence = repair_primary(&runner, &renderer, "main", "origin").unwrap();
+        assert_eq!(presence, PrimaryPresence::LocalOnly);
+        assert_eq!(repaired(&runner), (false, true));
+    }
+
+    #[test]
+    fn test_repair_primary_neither_left_to_caller() {
+        let renderer = Renderer::new(crate::ui::get_theme("default"), crate::ui::get_icon_set("ascii"));
+        let runner = mock_primary(false, false);
+        let presence = repair_primary(&runner, &renderer, "main", "origin").unwrap();
+        assert_eq!(presence, PrimaryPresence::Neither);
+        assert_eq!(repaired(&runner), (false, false));
+    }
+
+    fn unbookmarked(change_id: &str, description: &str) -> jj::Change {
+        serde_json::from_value(serde_json::json!({
+            "change_id": change_id,
+            "commit_id": "c",
+            "description": description,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_bookmark_target_single_change() {
+        let mut bookmarked = unbookmarked("cccccccc1111", "Already named");
+        bookmarked.bookmarks = vec!["jf/named".to_string()];
+        let changes = vec![bookmarked, unbookmarked("aaaaaaaa1111", "New work")];
+
+        assert!(check_bookmark_target(&changes, "new-work").is_ok());
+    }
+
+    #[test]
+    fn test_bookmark_target_ambiguous_multi_change() {
+        let changes = vec![
+            unbookmarked("aaaaaaaa1111", "First"),
+            unbookmarked("bbbbbbbb2222", "Second"),
+        ];
+
+        let err = check_bookmark_target(&changes, "feature").unwrap_err().to_string();
+        assert!(err.contains("ambiguous"));
+        assert!(err.contains("aaaaaaaa, bbbbbbbb"));
+    }
+
+    #[test]
+    fn test_collect_bookmark_names_batches_prompts() {
+        let mut bookmarked = unbookmarked("cccccccc1111", "Already named");
+        bookmarked.bookmarks = vec!["jf/named".to_string()];
+        let changes = vec![
+            unbookmarked("aaaaaaaa1111", "First"),
+            bookmarked,
+            unbookmarked("bbbbbbbb1111", "Second"),
+            unbookmarked("dddddddd1111", "Third"),
+        ];
+        let existing = vec!["jf/taken".to_string()];
+
+        // "taken" clashes with an existing bookmark and the second "one" with an
+        // earlier answer, so both are asked again; an empty answer skips
+        let mut answers = vec!["one", "taken", "jf/two", "one", ""].into_iter();
+        let mut asked = Vec::new();
+        let planned = collect_bookmark_names(&test_renderer(), &changes, "jf/", &existing, |id, _| {
+            asked.push(id.to_string());
+            Ok(answers.next().unwrap().to_string())
+        })
+        .unwrap();
+
+        assert_eq!(asked, ["aaaaaaaa", "bbbbbbbb", "bbbbbbbb", "dddddddd", "dddddddd"]);
+        assert_eq!(planned.len(), 2);
+        assert_eq!(planned["aaaaaaaa1111"], "jf/one");
+        assert_eq!(planned["bbbbbbbb1111"], "jf/two");
+    }
+
+    #[test]
+    fn test_collect_bookmark_names_reprompts_invalid_name() {
+        let changes = vec![unbookmarked("aaaaaaaa1111", "First")];
+
+        let mut answers = vec!["my feature", "fix..it", "my-feature"].into_iter();
+        let planned = collect_bookmark_names(&test_renderer(), &changes, "jf/", &[], |_, _| {
+            Ok(answers.next().unwrap().to_string())
+        })
+        .unwrap();
+
+        assert_eq!(planned["aaaaaaaa1111"], "jf/my-feature");
+    }
+
+    #[test]
+    fn test_collect_bookmark_names_prompt_error_aborts() {
+        let changes = vec![unbookmarked("aaaaaaaa1111", "First")];
+        let result = collect_bookmark_names(&test_renderer(), &changes, "", &[], |_, _| anyhow::bail!("stdin closed"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_check_stack_size_within_limit() {
+        let renderer = Renderer::new(crate::ui::get_theme("default"), crate::ui::get_icon_set("ascii"));
+        let result = check_stack_size(&renderer, 20, 20, || panic!("should not ask"));
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_check_stack_size_over_limit_aborts() {
+        let renderer = Renderer::new(crate::ui::get_theme("default"), crate::ui::get_icon_set("ascii"));
+        let err = check_stack_size(&renderer, 45, 20, || Ok(false)).unwrap_err();
+        assert!(err.to_string().contains("--max-stack 45"));
+    }
+
+    #[test]
+    fn test_check_stack_size_over_limit_confirmed() {
+        let renderer = Renderer::new(crate::ui::get_theme("default"), crate::ui::get_icon_set("ascii"));
+        assert!(check_stack_size(&renderer, 45, 20, || Ok(true)).is_ok());
+    }
+
+    #[test]
+    fn test_check_signatures_off_by_default() {
+        let runner = MockRunner::new();
+        let require_signed = Config::default().github.require_signed;
+
+        check_signatures(&runner, &test_renderer(), require_signed, "::@ ~ ::main@origin", true).unwrap();
+        assert!(runner.get_calls().is_empty());
+    }
+
+    #[test]
+    fn test_check_signatures_warns_without_blocking() {
+        let runner = MockRunner::new();
+        runner.mock_response(SIGNATURE_QUERY, "qpvuntsmwlqt unsigned\nrlvkpnrzqnoo signed\n");
+
+        assert_eq!(find_unsigned_changes(&runner, "::@ ~ ::main@origin").unwrap(), vec!["qpvuntsmwlqt"]);
+        check_signatures(&runner, &test_renderer(), true, "::@ ~ ::main@origin", false).unwrap();
+    }
+
+    #[test]
+    fn test_check_signatures_blocks_unsigned() {
+        let runner = MockRunner::new();
+        runner.mock_response(SIGNATURE_QUERY, "qpvuntsmwlqt unsigned\n");
+
+        let result = check_signatures(&runner, &test_renderer(), true, "::@ ~ ::main@origin", true);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_check_signatures_all_signed() {
+        let runner = MockRunner::new();
+        runner.mock_response(SIGNATURE_QUERY, "qpvuntsmwlqt signed\n");
+
+        check_signatures(&runner, &test_renderer(), true, "::@ ~ ::main@origin", true).unwrap();
+    }
+
+    #[test]
+    fn test_failing_pre_push_hook_fails() {
+        let streaming = |program: &str, args: &[&str]| RealRunner::default().run_streaming(program, args);
+
+        let hook = HookCommand::Shell("echo checking; exit 1".to_string());
+        let err = run_pre_push_hook(&test_renderer(), &hook, streaming).unwrap_err();
+        assert_eq!(err.to_string(), "Pre-push hook failed");
+
+        run_pre_push_hook(&test_renderer(), &HookCommand::Shell("true".to_string()), streaming).unwrap();
+    }
+
+    #[test]
+    fn test_pre_push_hook_argv() {
+        let calls = std::cell::RefCell::new(Vec::new());
+        let hook = HookCommand::Argv(vec!["cargo".to_string(), "fmt".to_string(), "--check".to_string()]);
+        run_pre_push_hook(&test_renderer(), &hook, |program, args| {
+            calls.borrow_mut().push(format!("{} {}", program, args.join(" ")));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(*calls.borrow(), ["cargo fmt --check"]);
+    }
+
+    fn stack_item(change_id: &str, description: &str, bookmark: Option<&str>) -> ChangeWithStatus {
+        ChangeWithStatus {
+            change: serde_json::from_value(serde_json::json!({
+                "change_id": change_id,
+                "commit_id": "c",
+                "description": description,
+            }))
+            .unwrap(),
+            bookmark: bookmark.map(str::to_string),
+            is_working: false,
+            has_remote: true,
+            sync_state: jj::types::BookmarkSyncState::Synced,
+            is_empty: false,
+            is_immutable: false,
+            additions: 0,
+            deletions: 0,
+            pr_state: None,
+        }
+    }
+
+    #[test]
+    fn test_fit_pr_title_at_boundary() {
+        let exact = "a".repeat(72);
+        assert_eq!(fit_pr_title(&exact, 72), None);
+
+        let over = "a".repeat(73);
+        let short = fit_pr_title(&over, 72).unwrap();
+        assert_eq!(short.chars().count(), 72);
+        assert!(short.ends_with("a…"));
+
+        // Characters, not bytes, and no dangling space before the ellipsis
+        assert_eq!(fit_pr_title("héllo wörld", 7).unwrap(), "héllo…");
+        assert_eq!(fit_pr_title(&over, 0), None);
+    }
+
+    #[test]
+    fn test_combine_descriptions_from_multiple_changes() {
+        let runner = MockRunner::new();
+        runner.mock_response(
+            "jj log -r main..feature --reversed --no-graph -T description ++ \"\\0\"",
+            "Add parser\n\nHandles the basic grammar.\n\0Add error recovery\n\0\0Document the parser\n\0",
+        );
+
+        let descriptions = pr_commit_descriptions(&runner, "main", "feature").unwrap();
+        assert_eq!(descriptions.len(), 3);
+
+        let (title, body) = combine_descriptions(&descriptions).unwrap();
+        assert_eq!(title, "Add parser");
+        assert_eq!(
+            body,
+            "Add parser\n\nHandles the basic grammar.\n\nAdd error recovery\n\nDocument the parser"
+        );
+
+        assert_eq!(combine_descriptions(&[]), None);
+    }
+
+    #[test]
+    fn test_format_pr_body_marks_empty_descriptions() {
+        let stack = vec![
+            stack_item("top", "", Some("top-bookmark")),
+            stack_item("mid", "Add feature\n", Some("feature")),
+            stack_item("base", "   ", Some("base-bookmark")),
+        ];
+        let change = change_from(r#"{"change_id": "mid", "commit_id": "c", "description": "Add feature\n\n"}"#);
+
+        let body = format_pr_body(&change, &stack);
+
+        assert_eq!(
+            body,
+            "Add feature\n\n\n\n---\n\n**Part of stack:**\n\n\
+             - ✓ (no description) (bookmark: `top-bookmark`)\n\
+             - **This PR** (Add feature)\n\
+             - ⏳ (no description) (bookmark: `base-bookmark`)"
+        );
+    }
+
+    #[test]
+    fn test_conventional_subjects_accepted() {
+        for subject in [
+            "feat: add jf adopt",
+            "fix(push): retry on timeout",
+            "refactor!: drop the legacy config",
+            "perf(render)!: cache glyph widths",
+            "docs(api (v2)): describe the stack endpoint",
+            "test: cover: colons in the summary",
+        ] {
+            assert!(is_conventional_subject(subject), "{:?} should be conventional", subject);
+        }
+    }
+
+    #[test]
+    fn test_unconventional_subjects_rejected() {
+        for subject in [
+            "",
+            "Add jf adopt",
+            "feature: unknown type",
+            "Feat: wrong case",
+            "feat:missing space",
+            "feat: ",
+            "feat(): empty scope",
+            "fix(push: unclosed scope",
+            "fix!(push): bang before scope",
+            "chore !: space before bang",
+        ] {
+            assert!(!is_conventional_subject(subject), "{:?} should be rejected", subject);
+        }
+    }
+
+    #[test]
+    fn test_stack_only_rejects_off_stack_revision() {
+        let runner = MockRunner::new();
+        runner.mock_response(
+            "jj log -r (release-1.0) ~ (::@ ~ ::main@origin) --no-graph -T change_id ++ \"\\n\"",
+            "zzzmmmnnnooo\n",
+        );
+
+        let result = check_on_stack(&runner, &test_renderer(), "release-1.0", STACK, true);
+        assert!(result.unwrap_err().to_string().contains("outside the stack"));
+        // Without --stack-only it's only a warning
+        assert!(check_on_stack(&runner, &test_renderer(), "release-1.0", STACK, false).is_ok());
+    }
+
+    #[test]
+    fn test_stack_only_allows_stack_revision() {
+        let runner = MockRunner::new();
+        runner.mock_response("jj log -r (@-) ~ (::@ ~ ::main@origin) --no-graph -T change_id ++ \"\\n\"", "");
+
+        assert!(check_on_stack(&runner, &test_renderer(), "@-", STACK, true).is_ok());
+    }
+
+    #[test]
+    fn test_base_auto_stack_chains_pr_bases() {
+        let changes = bookmarked_stack(&["three", "two", "one"]);
+        let runner = MockRunner::new();
+        mock_first_push(&runner, &changes);
+        runner.mock_response("gh pr list --state open --json headRefName,url --limit 200", "[]");
+        // The bottom change's parent has no bookmark, so its PR is based on trunk
+        runner.mock_response("jj log -r onezzzzz- -T bookmarks --no-graph", "");
+        for (change, base) in changes.iter().zip(["jf/two", "jf/one", "main"]) {
+            let name = &change.description;
+            runner.mock_response(&format!("jj log -r {} --no-graph -T description", change.change_id), name);
+            runner.mock_response(
+                &format!("gh pr create --head jf/{} --base {} --title {} --body {}", name, base, name, name),
+                "",
+            );
+        }
+
+        // Force skips the divergence check, which isn't under test here
+        let options = PushOptions { base_auto_stack: true, force: true, ..Default::default() };
+        let outcome = push_stack(&runner, &options, true, &changes).unwrap();
+
+        // Bottom-up, each PR based on the bookmark pushed just below it
+        assert_eq!(git_pushes(&runner), vec!["jf/one", "jf/two", "jf/three"]);
+        let bases: Vec<(String, String)> = runner
+            .get_calls()
+            .into_iter()
+            .filter(|(_, args)| args[..2] == ["pr", "create"])
+            .map(|(_, args)| (args[3].clone(), args[5].clone()))
+            .collect();
+        assert_eq!(
+            bases,
+            [
+                ("jf/one".to_string(), "main".to_string()),
+                ("jf/two".to_string(), "jf/one".to_string()),
+                ("jf/three".to_string(), "jf/two".to_string()),
+            ]
+        );
+        assert_eq!(outcome.to_open, vec!["jf/one", "jf/two", "jf/three"]);
+    }
+
+    #[test]
+    fn test_stack_base_falls_back_for_bottom() {
+        assert_eq!(stack_base(None, || Ok("jf/parent".to_string())).unwrap(), "jf/parent");
+        assert_eq!(stack_base(Some("jf/below"), || unreachable!()).unwrap(), "jf/below");
+    }
+
+    #[test]
+    fn test_edit_uses_body_file() {
+        let runner = MockRunner::new();
+        let mut opened = None;
+        let path = edit_pr_body("jf/feature", "Generated body", Some("vi".to_string()), |editor, path| {
+            opened = Some(editor.to_string());
+            // Stand-in for the user's edit
+            std::fs::write(path, "Edited body")?;
+            Ok(())
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!(opened.as_deref(), Some("vi"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Edited body");
+
+        let file = path.display().to_string();
+        runner.mock_response(&format!("gh pr create --head jf/feature --base main --title T --body-file {}", file), "");
+        create_github_pr(&runner, None, "jf/feature", "main", "T", PrBody::File(&path)).unwrap();
+        assert!(runner.was_called(
+            "gh",
+            &["pr", "create", "--head", "jf/feature", "--base", "main", "--title", "T", "--body-file", &file]
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_edit_without_editor_keeps_generated_body() {
+        let path = edit_pr_body("jf/feature", "Generated body", None, |_, _| unreachable!()).unwrap();
+        assert!(path.is_none());
+    }
 }
@@ -0,0 +1,122 @@
+use anyhow::Result;
+
+use crate::config::Config;
+use crate::jj::{self, CommandRunner, RealRunner};
+use crate::ui::Renderer;
+
+/// Rebase the stack (or `source` and its descendants) onto `dest`, then show the
+/// stack and any conflicts the rebase left
+pub fn run(config: &Config, dest: &str, source: Option<&str>) -> Result<()> {
+    let renderer = Renderer::from_config(config);
+    let runner = RealRunner::new(&config.tools);
+
+    // Without a source the stack is what moves; on trunk there's nothing to move
+    let revset = config.stack_revset();
+    if source.is_none() {
+        let stack = jj::get_stack(&runner, &revset, &config.remote.name)?;
+        if super::is_empty_stack(&stack) {
+            renderer.info(&super::empty_stack_message(config));
+            return Ok(());
+        }
+    }
+
+    if !rebase(&runner, &renderer, &revset, dest, source)? {
+        return Ok(());
+    }
+
+    println!();
+    let stack = jj::get_stack(&runner, &revset, &config.remote.name)?;
+    renderer.render_stack(&stack, &config.trunk_ref());
+
+    Ok(())
+}
+
+/// Rebase onto `dest` after checking it resolves, then report conflicts in
+/// `stack_revset`. Returns false if nothing was rebased.
+fn rebase(
+    runner: &dyn CommandRunner,
+    renderer: &Renderer,
+    stack_revset: &str,
+    dest: &str,
+    source: Option<&str>,
+) -> Result<bool> {
+    if !resolves(runner, dest) {
+        renderer.error(&format!("Destination '{}' doesn't resolve to a change", dest));
+        return Ok(false);
+    }
+
+    let mut args = vec!["rebase", "-d", dest];
+    if let Some(source) = source {
+        args.extend(["-s", source]);
+    }
+    if let Err(e) = runner.run("jj", &args) {
+        renderer.error(&format!("Could not rebase onto '{}': {}", dest, e.to_string().trim()));
+        return Ok(false);
+    }
+    renderer.success(&format!("Rebased onto {}", dest));
+
+    let conflicted = jj::find_conflicts_in(runner, stack_revset)?;
+    if !conflicted.is_empty() {
+        let ids: Vec<&str> = conflicted.iter().map(|id| &id[..8.min(id.len())]).collect();
+        renderer.warning(&format!(
+            "{} change(s) have conflicts: {}. Resolve them with `jj resolve`.",
+            conflicted.len(),
+            ids.join(", ")
+        ));
+    }
+    Ok(true)
+}
+
+/// Whether `revset` resolves to at least one change
+fn resolves(runner: &dyn CommandRunner, revset: &str) -> bool {
+    runner
+        .run("jj", &["log", "-r", revset, "--no-graph", "--limit", "1", "-T", "change_id"])
+        .is_ok_and(|output| !output.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jj::runner::mock::MockRunner;
+
+    const STACK: &str = "::@ ~ ::main@origin";
+    const CONFLICT_QUERY: &str = "jj log -r (::@ ~ ::main@origin) & conflicts() --no-graph -T change_id ++ \"\\n\"";
+
+    fn test_renderer() -> Renderer {
+        Renderer::new(crate::ui::get_theme("default"), crate::ui::get_icon_set("ascii")).with_quiet(true)
+    }
+
+    #[test]
+    fn test_rebase_refuses_unknown_destination() {
+        let runner = MockRunner::new();
+        runner.mock_error("jj log -r nope --no-graph --limit 1 -T change_id", "Revision `nope` doesn't exist");
+
+        assert!(!rebase(&runner, &test_renderer(), STACK, "nope", None).unwrap());
+        assert!(!runner.get_calls().iter().any(|(_, args)| args[0] == "rebase"));
+    }
+
+    #[test]
+    fn test_rebase_onto_destination_with_source() {
+        let runner = MockRunner::new();
+        runner.mock_response("jj log -r alice/feature --no-graph --limit 1 -T change_id", "kkmpptxz");
+        runner.mock_response("jj rebase -d alice/feature -s qpvuntsm", "");
+        runner.mock_response(CONFLICT_QUERY, "");
+
+        assert!(rebase(&runner, &test_renderer(), STACK, "alice/feature", Some("qpvuntsm")).unwrap());
+        assert!(runner.was_called("jj", &["rebase", "-d", "alice/feature", "-s", "qpvuntsm"]));
+    }
+
+    #[test]
+    fn test_rebase_checks_conflicts_afterwards() {
+        let runner = MockRunner::new();
+        runner.mock_response("jj log -r alice/feature --no-graph --limit 1 -T change_id", "kkmpptxz");
+        runner.mock_response("jj rebase -d alice/feature", "");
+        runner.mock_response(CONFLICT_QUERY, "qpvuntsmwlqt\n");
+
+        assert!(rebase(&runner, &test_renderer(), STACK, "alice/feature", None).unwrap());
+        let calls = runner.get_calls();
+        let rebase_at = calls.iter().position(|(_, args)| args[0] == "rebase").unwrap();
+        let conflicts_at = calls.iter().position(|(_, args)| args.iter().any(|a| a.contains("conflicts()"))).unwrap();
+        assert!(rebase_at < conflicts_at);
+    }
+}
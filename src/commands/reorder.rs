@@ -1,23 +1,52 @@
 use anyhow::Result;
 
 use crate::config::Config;
-use crate::jj;
-use crate::ui::{get_icon_set, get_theme, Renderer};
+use crate::jj::{self, CommandRunner, RealRunner};
+use crate::ui::Renderer;
+
+/// Where `jf reorder <change>` moves the change, relative to another stack change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement<'a> {
+    /// Directly on top of the other change
+    After(&'a str),
+    /// Directly below the other change
+    Before(&'a str),
+}
 
 pub fn run(
     config: &Config,
     changes: Vec<String>,
     invert: bool,
     revision: Option<&str>,
+    placement: Option<Placement>,
+    dry_run: bool,
 ) -> Result<()> {
-    let theme = get_theme(&config.display.theme);
-    let icons = get_icon_set(&config.display.icons);
-    let renderer = Renderer::new(theme, icons);
+    let renderer = Renderer::from_config(config);
+    let runner = RealRunner::new(&config.tools);
 
-    if invert {
-        run_invert(config, &renderer, revision)
+    let outcome = if invert {
+        // With -r, invert from that change to @; without, the entire stack
+        let revset = match revision {
+            Some(rev) => format!("{}::@", rev),
+            None => config.stack_revset(),
+        };
+        let changes = jj::query_changes(&runner, &revset)?;
+        if revision.is_none() && super::is_empty_stack(&changes) {
+            renderer.info(&super::empty_stack_message(config));
+            return Ok(());
+        }
+        let change_ids: Vec<String> = changes.iter().map(|c| short_id(&c.change_id)).collect();
+        run_invert(&runner, &renderer, &change_ids, dry_run)?
+    } else if let Some(placement) = placement {
+        match changes.as_slice() {
+            [change] => run_relative(config, &runner, &renderer, change, placement, dry_run)?,
+            _ => {
+                renderer.error("--after and --before move a single change");
+                return Ok(());
+            }
+        }
     } else if !changes.is_empty() {
-        run_explicit(config, &renderer, changes, revision)
+        run_explicit(&runner, &renderer, changes, revision, dry_run)?
     } else {
         renderer.error("Specify changes to reorder, or use --invert");
         println!();
@@ -26,8 +55,30 @@ pub fn run(
         println!("  jf reorder -f <c1> <c2> <c3> ...      # reorder starting from c1 (inclusive)");
         println!("  jf reorder --invert                   # reverse the stack");
         println!("  jf reorder --invert -f <change>       # reverse from change to @ (inclusive)");
-        Ok(())
+        println!("  jf reorder <change> --after <other>   # move one change on top of another");
+        println!("  jf reorder <change> --before <other>  # move one change below another");
+        return Ok(());
+    };
+
+    // Show updated stack
+    if outcome == ReorderOutcome::Applied {
+        println!();
+        let stack = jj::get_stack(&runner, &config.stack_revset(), &config.remote.name)?;
+        renderer.render_stack(&stack, &config.trunk_ref());
     }
+
+    Ok(())
+}
+
+/// What a reorder did
+#[derive(Debug, PartialEq, Eq)]
+enum ReorderOutcome {
+    /// Nothing to reorder, or the request was refused
+    Nothing,
+    /// A dry run: the plan that was printed
+    Planned(Vec<String>),
+    /// The rebases ran
+    Applied,
 }
 
 /// Reorder changes in the explicit order given
@@ -35,7 +86,13 @@ pub fn run(
 /// Results in: parent(abc) -> abc -> def -> ghi
 /// With --from: jf reorder --from xyz abc def ghi
 /// Results in: parent(xyz) -> xyz -> abc -> def -> ghi (--from is inclusive)
-fn run_explicit(config: &Config, renderer: &Renderer, changes: Vec<String>, from: Option<&str>) -> Result<()> {
+fn run_explicit(
+    runner: &dyn CommandRunner,
+    renderer: &Renderer,
+    changes: Vec<String>,
+    from: Option<&str>,
+    dry_run: bool,
+) -> Result<ReorderOutcome> {
     // Build the full list of changes to reorder (--from is inclusive)
     let all_changes: Vec<String> = if let Some(from_change) = from {
         let mut v = vec![from_change.to_string()];
@@ -47,104 +104,210 @@ fn run_explicit(config: &Config, renderer: &Renderer, changes: Vec<String>, from
 
     if all_changes.is_empty() {
         renderer.error("Need at least 1 change to reorder");
-        return Ok(());
+        return Ok(ReorderOutcome::Nothing);
     }
 
     if all_changes.len() < 2 {
         renderer.error("Need at least 2 changes to reorder");
-        return Ok(());
+        return Ok(ReorderOutcome::Nothing);
     }
 
-    renderer.info(&format!("Reordering {} changes...", all_changes.len()));
-
-    // Get the base (parent of the first change)
-    let first_change = &all_changes[0];
-    let base = get_parent(first_change)?;
-
-    // Rebase each change onto the previous one
-    let mut current_base = base;
-    let mut last_change = String::new();
-    for change in &all_changes {
-        renderer.info(&format!("  Moving {} onto {}", change, short_id(&current_base)));
-        jj::run_jj(&["rebase", "-r", change, "-d", &current_base])?;
-        current_base = change.clone();
-        last_change = change.clone();
+    // Get the base (parent of the first change), then rebase each change onto the previous one
+    let base = get_parent(runner, &all_changes[0])?;
+    let outcome = reorder_onto(runner, renderer, &base, &all_changes, "Reordering", dry_run)?;
+    if outcome == ReorderOutcome::Applied {
+        renderer.success("Reorder complete!");
     }
+    Ok(outcome)
+}
 
-    // Move @ to the last reordered change so the stack displays correctly
-    if !last_change.is_empty() {
-        jj::run_jj(&["edit", &last_change])?;
+/// Invert `change_ids` (newest first, as jj lists them): the newest ends up on the
+/// base and the oldest on top
+fn run_invert(
+    runner: &dyn CommandRunner,
+    renderer: &Renderer,
+    change_ids: &[String],
+    dry_run: bool,
+) -> Result<ReorderOutcome> {
+    if change_ids.len() < 2 {
+        renderer.info("Stack has fewer than 2 changes, nothing to invert");
+        return Ok(ReorderOutcome::Nothing);
     }
 
-    renderer.success("Reorder complete!");
-    println!();
-
-    // Show updated stack
-    let revset = config.stack_revset();
-    let stack = jj::get_stack(&revset, &config.remote.name)?;
-    renderer.render_stack(&stack, &config.trunk_ref());
+    // Get the base (parent of the oldest change in the range)
+    let oldest_change = &change_ids[change_ids.len() - 1];
+    let base = get_parent(runner, oldest_change)?;
 
-    Ok(())
+    // Rebase in reverse order: newest becomes first (on base), oldest becomes last
+    let outcome = reorder_onto(runner, renderer, &base, change_ids, "Inverting", dry_run)?;
+    if outcome == ReorderOutcome::Applied {
+        renderer.success("Stack inverted!");
+    }
+    Ok(outcome)
 }
 
-/// Invert the stack (reverse order)
-/// With -r, inverts from that change to @
-/// Without -r, inverts the entire stack
-fn run_invert(config: &Config, renderer: &Renderer, revision: Option<&str>) -> Result<()> {
-    // Get the stack to invert
-    let revset = if let Some(rev) = revision {
-        format!("{}::@", rev)
+/// Move one change directly after (above) or before (below) another in the stack,
+/// rebasing only the part of the stack whose order changes
+fn run_relative(
+    config: &Config,
+    runner: &RealRunner,
+    renderer: &Renderer,
+    change: &str,
+    placement: Placement,
+    dry_run: bool,
+) -> Result<ReorderOutcome> {
+    let changes = jj::query_changes(runner, &config.stack_revset())?;
+    if super::is_empty_stack(&changes) {
+        renderer.info(&super::empty_stack_message(config));
+        return Ok(ReorderOutcome::Nothing);
+    }
+
+    // Changes come newest first; orders run oldest first
+    let order: Vec<String> = changes.iter().rev().map(|c| c.change_id.clone()).collect();
+    let new_order = match move_relative(&order, change, placement) {
+        Ok(new_order) => new_order,
+        Err(e) => {
+            renderer.error(&e.to_string());
+            return Ok(ReorderOutcome::Nothing);
+        }
+    };
+
+    // Changes below the first difference keep their place
+    let Some(first) = order.iter().zip(&new_order).position(|(old, new)| old != new) else {
+        renderer.info("Change is already there, nothing to reorder");
+        return Ok(ReorderOutcome::Nothing);
+    };
+    let base = if first == 0 {
+        get_parent(runner, &order[0])?
     } else {
-        config.stack_revset()
+        order[first - 1].clone()
     };
 
-    let changes = jj::query_changes(&revset)?;
+    let moved: Vec<String> = new_order[first..].iter().map(|id| short_id(id)).collect();
+    let outcome = reorder_onto(runner, renderer, &base, &moved, "Reordering", dry_run)?;
+    if outcome == ReorderOutcome::Applied {
+        renderer.success("Reorder complete!");
+    }
+    Ok(outcome)
+}
 
-    if changes.len() < 2 {
-        renderer.info("Stack has fewer than 2 changes, nothing to invert");
-        return Ok(());
+/// Stack `order` on `base`, each change onto the previous one. A dry run only prints
+/// the plan; otherwise `action` ("Reordering") announces the rebases.
+fn reorder_onto(
+    runner: &dyn CommandRunner,
+    renderer: &Renderer,
+    base: &str,
+    order: &[String],
+    action: &str,
+    dry_run: bool,
+) -> Result<ReorderOutcome> {
+    let moves = plan_moves(base, order);
+    if dry_run {
+        let plan = format_plan(base, &moves);
+        print_plan(&plan);
+        return Ok(ReorderOutcome::Planned(plan));
     }
 
-    renderer.info(&format!("Inverting {} changes...", changes.len()));
+    renderer.info(&format!("{} {} changes...", action, moves.len()));
+    apply_moves(runner, renderer, &moves)?;
+    Ok(ReorderOutcome::Applied)
+}
 
-    // Changes come in reverse order (newest first), so we need to reverse them
-    // to get oldest first, then that becomes our target order (which will invert the stack)
-    let change_ids: Vec<String> = changes.iter().map(|c| c.change_id.clone()).collect();
+/// The stack `order` (oldest first) with `change` moved next to another change as
+/// `placement` says. Both are given as change ID prefixes and must be distinct stack changes.
+fn move_relative(order: &[String], change: &str, placement: Placement) -> Result<Vec<String>> {
+    let other = match placement {
+        Placement::After(other) | Placement::Before(other) => other,
+    };
+    let from = find_in_stack(order, change)?;
+    let target = find_in_stack(order, other)?;
+    if from == target {
+        anyhow::bail!("Can't move {} relative to itself", change);
+    }
 
-    // Get the base (parent of the oldest change in the range)
-    let oldest_change = &change_ids[change_ids.len() - 1];
-    let base = get_parent(&short_id(oldest_change))?;
+    let mut new_order = order.to_vec();
+    let moved = new_order.remove(from);
+    let target = new_order
+        .iter()
+        .position(|id| *id == order[target])
+        .expect("target is still in the stack");
+    let at = match placement {
+        Placement::After(_) => target + 1,
+        Placement::Before(_) => target,
+    };
+    new_order.insert(at, moved);
+    Ok(new_order)
+}
 
-    // Rebase in reverse order: newest becomes first (on base), oldest becomes last
-    let mut current_base = base;
-    let mut last_change = String::new();
-    for change_id in &change_ids {
-        let short = short_id(change_id);
-        renderer.info(&format!("  Moving {} onto {}", short, short_id(&current_base)));
-        jj::run_jj(&["rebase", "-r", &short, "-d", &current_base])?;
-        current_base = short.clone();
-        last_change = short;
+/// Position of the one stack change whose ID starts with `prefix`
+fn find_in_stack(order: &[String], prefix: &str) -> Result<usize> {
+    let matches: Vec<usize> = order
+        .iter()
+        .enumerate()
+        .filter(|(_, id)| !prefix.is_empty() && id.starts_with(prefix))
+        .map(|(i, _)| i)
+        .collect();
+    match matches.as_slice() {
+        [i] => Ok(*i),
+        [] => anyhow::bail!("'{}' isn't a change in your stack", prefix),
+        _ => anyhow::bail!("'{}' matches {} changes in your stack; use a longer ID", prefix, matches.len()),
     }
+}
 
-    // Move @ to the new tip so the stack displays correctly
-    if !last_change.is_empty() {
-        jj::run_jj(&["edit", &last_change])?;
-    }
+/// A single step of a reorder: rebase `change` onto `destination`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Move {
+    change: String,
+    destination: String,
+}
 
-    renderer.success("Stack inverted!");
-    println!();
+/// Plan the rebases that stack `order` on `base`, each change onto the previous one
+fn plan_moves(base: &str, order: &[String]) -> Vec<Move> {
+    let mut destination = base.to_string();
+    order
+        .iter()
+        .map(|change| Move {
+            change: change.clone(),
+            destination: std::mem::replace(&mut destination, change.clone()),
+        })
+        .collect()
+}
 
-    // Show updated stack
-    let stack_revset = config.stack_revset();
-    let stack = jj::get_stack(&stack_revset, &config.remote.name)?;
-    renderer.render_stack(&stack, &config.trunk_ref());
+/// Describe a reorder plan: the base, each rebase, and the resulting order
+fn format_plan(base: &str, moves: &[Move]) -> Vec<String> {
+    let mut lines = vec![format!("Base: {}", short_id(base))];
+    for step in moves {
+        lines.push(format!("  jj rebase -r {} -d {}", step.change, short_id(&step.destination)));
+    }
+    let order: Vec<String> = std::iter::once(short_id(base))
+        .chain(moves.iter().map(|step| step.change.clone()))
+        .collect();
+    lines.push(format!("Resulting order: {}", order.join(" -> ")));
+    lines
+}
+
+fn print_plan(plan: &[String]) {
+    println!("\nDry run - would reorder:");
+    for line in plan {
+        println!("  {}", line);
+    }
+}
 
+/// Run the planned rebases, then move @ to the new tip so the stack displays correctly
+fn apply_moves(runner: &dyn CommandRunner, renderer: &Renderer, moves: &[Move]) -> Result<()> {
+    for step in moves {
+        renderer.info(&format!("  Moving {} onto {}", step.change, short_id(&step.destination)));
+        runner.run("jj", &["rebase", "-r", &step.change, "-d", &step.destination])?;
+    }
+    if let Some(last) = moves.last() {
+        runner.run("jj", &["edit", &last.change])?;
+    }
     Ok(())
 }
 
 /// Get the parent of a change
-fn get_parent(change: &str) -> Result<String> {
-    let output = jj::run_jj(&["log", "-r", &format!("{}-", change), "-T", "change_id", "--no-graph", "--limit", "1"])?;
+fn get_parent(runner: &dyn CommandRunner, change: &str) -> Result<String> {
+    let output = runner.run("jj", &["log", "-r", &format!("{}-", change), "-T", "change_id", "--no-graph", "--limit", "1"])?;
     Ok(output.trim().to_string())
 }
 
@@ -152,3 +315,143 @@ fn get_parent(change: &str) -> Result<String> {
 fn short_id(id: &str) -> String {
     id[..8.min(id.len())].to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jj::runner::mock::MockRunner;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_plan_moves_chains_onto_base() {
+        let moves = plan_moves("basebasebase", &ids(&["ccc", "aaa", "bbb"]));
+        let steps: Vec<(&str, &str)> = moves.iter().map(|m| (m.change.as_str(), m.destination.as_str())).collect();
+        assert_eq!(steps, [("ccc", "basebasebase"), ("aaa", "ccc"), ("bbb", "aaa")]);
+    }
+
+    fn test_renderer() -> Renderer {
+        Renderer::new(crate::ui::get_theme("default"), crate::ui::get_icon_set("ascii"))
+    }
+
+    fn rebased(runner: &MockRunner) -> bool {
+        runner.get_calls().iter().any(|(_, args)| args.first().is_some_and(|a| a == "rebase" || a == "edit"))
+    }
+
+    #[test]
+    fn test_explicit_dry_run_runs_no_rebases() {
+        let runner = MockRunner::new();
+        runner.mock_response("jj log -r ccc- -T change_id --no-graph --limit 1", "basebasebase\n");
+
+        let outcome = run_explicit(&runner, &test_renderer(), ids(&["aaa", "bbb"]), Some("ccc"), true).unwrap();
+
+        assert!(!rebased(&runner));
+        assert_eq!(
+            outcome,
+            ReorderOutcome::Planned(ids(&[
+                "Base: basebase",
+                "  jj rebase -r ccc -d basebase",
+                "  jj rebase -r aaa -d ccc",
+                "  jj rebase -r bbb -d aaa",
+                "Resulting order: basebase -> ccc -> aaa -> bbb",
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_invert_dry_run_runs_no_rebases() {
+        let runner = MockRunner::new();
+        runner.mock_response("jj log -r aaa- -T change_id --no-graph --limit 1", "basebasebase\n");
+
+        // Newest first, as jj lists them
+        let outcome = run_invert(&runner, &test_renderer(), &ids(&["ccc", "bbb", "aaa"]), true).unwrap();
+
+        assert!(!rebased(&runner));
+        assert_eq!(
+            outcome,
+            ReorderOutcome::Planned(ids(&[
+                "Base: basebase",
+                "  jj rebase -r ccc -d basebase",
+                "  jj rebase -r bbb -d ccc",
+                "  jj rebase -r aaa -d bbb",
+                "Resulting order: basebase -> ccc -> bbb -> aaa",
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_invert_applies_without_dry_run() {
+        let runner = MockRunner::new();
+        runner.mock_response("jj log -r aaa- -T change_id --no-graph --limit 1", "basebasebase\n");
+        runner.mock_response("jj rebase -r bbb -d basebasebase", "");
+        runner.mock_response("jj rebase -r aaa -d bbb", "");
+        runner.mock_response("jj edit aaa", "");
+
+        let outcome = run_invert(&runner, &test_renderer(), &ids(&["bbb", "aaa"]), false).unwrap();
+
+        assert_eq!(outcome, ReorderOutcome::Applied);
+        assert!(runner.was_called("jj", &["rebase", "-r", "bbb", "-d", "basebasebase"]));
+    }
+
+    #[test]
+    fn test_apply_moves_rebases_in_order_then_edits_tip() {
+        let runner = MockRunner::new();
+        runner.mock_response("jj rebase -r ccc -d basebasebase", "");
+        runner.mock_response("jj rebase -r aaa -d ccc", "");
+        runner.mock_response("jj edit aaa", "");
+        apply_moves(&runner, &test_renderer(), &plan_moves("basebasebase", &ids(&["ccc", "aaa"]))).unwrap();
+
+        let calls: Vec<String> = runner.get_calls().iter().map(|(_, args)| args.join(" ")).collect();
+        assert_eq!(calls, ["rebase -r ccc -d basebasebase", "rebase -r aaa -d ccc", "edit aaa"]);
+    }
+
+    const STACK: [&str; 4] = ["aaaaaaaaaaaa", "bbbbbbbbbbbb", "cccccccccccc", "dddddddddddd"];
+
+    fn order_after(change: &str, placement: Placement) -> Vec<String> {
+        move_relative(&ids(&STACK), change, placement).unwrap()
+    }
+
+    #[test]
+    fn test_move_after() {
+        assert_eq!(order_after("aaa", Placement::After("ccc")), ids(&["bbbbbbbbbbbb", "cccccccccccc", "aaaaaaaaaaaa", "dddddddddddd"]));
+        // To the top of the stack
+        assert_eq!(order_after("bbb", Placement::After("ddd")), ids(&["aaaaaaaaaaaa", "cccccccccccc", "dddddddddddd", "bbbbbbbbbbbb"]));
+        // Downward
+        assert_eq!(order_after("ddd", Placement::After("aaa")), ids(&["aaaaaaaaaaaa", "dddddddddddd", "bbbbbbbbbbbb", "cccccccccccc"]));
+    }
+
+    #[test]
+    fn test_move_before() {
+        assert_eq!(order_after("ddd", Placement::Before("bbb")), ids(&["aaaaaaaaaaaa", "dddddddddddd", "bbbbbbbbbbbb", "cccccccccccc"]));
+        // To the bottom of the stack
+        assert_eq!(order_after("ccc", Placement::Before("aaa")), ids(&["cccccccccccc", "aaaaaaaaaaaa", "bbbbbbbbbbbb", "dddddddddddd"]));
+        // Upward
+        assert_eq!(order_after("aaa", Placement::Before("ddd")), ids(&["bbbbbbbbbbbb", "cccccccccccc", "aaaaaaaaaaaa", "dddddddddddd"]));
+    }
+
+    #[test]
+    fn test_move_already_in_place_keeps_order() {
+        assert_eq!(order_after("ccc", Placement::After("bbb")), ids(&STACK));
+        assert_eq!(order_after("bbb", Placement::Before("ccc")), ids(&STACK));
+    }
+
+    #[test]
+    fn test_move_rejects_unknown_and_same_change() {
+        let order = ids(&STACK);
+        let err = move_relative(&order, "zzz", Placement::After("aaa")).unwrap_err();
+        assert!(err.to_string().contains("isn't a change in your stack"));
+        let err = move_relative(&order, "aaa", Placement::Before("zzz")).unwrap_err();
+        assert!(err.to_string().contains("'zzz'"));
+        let err = move_relative(&order, "aaa", Placement::After("aaaa")).unwrap_err();
+        assert!(err.to_string().contains("itself"));
+    }
+
+    #[test]
+    fn test_move_rejects_ambiguous_prefix() {
+        let order = ids(&["abc111111111", "abc222222222"]);
+        let err = move_relative(&order, "abc", Placement::After("abc2")).unwrap_err();
+        assert!(err.to_string().contains("matches 2 changes"));
+    }
+}
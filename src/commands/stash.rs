@@ -0,0 +1,83 @@
+use anyhow::Result;
+
+use crate::config::Config;
+use crate::jj::{self, RealRunner};
+use crate::state::State;
+use crate::ui::Renderer;
+
+/// Shelve the working change: remember `@` and start a new change on trunk
+pub fn run(config: &Config) -> Result<()> {
+    let renderer = Renderer::from_config(config);
+    let runner = RealRunner::new(&config.tools);
+    let path = State::path()?;
+    let _lock = State::lock(&path)?;
+    let mut state = State::load_from(&path)?;
+
+    if let Some(stashed) = &state.stashed_change {
+        anyhow::bail!(
+            "{} is already stashed. Run `jf restore` first.",
+            &stashed[..8.min(stashed.len())]
+        );
+    }
+
+    let change_id = jj::run_jj(&runner, &["log", "-r", "@", "--no-graph", "-T", "change_id"])?
+        .trim()
+        .to_string();
+
+    let trunk = config.trunk_ref();
+    if let Err(e) = jj::run_jj(&runner, &["new", &trunk]) {
+        anyhow::bail!("Could not move to {}: {}", trunk, e.to_string().trim());
+    }
+
+    renderer.success(&format!(
+        "Stashed {} - now on a new change on {}",
+        &change_id[..8.min(change_id.len())],
+        trunk
+    ));
+    state.stashed_change = Some(change_id);
+    state.save_to(&path)?;
+    println!("  Use `jf restore` to return to it");
+
+    Ok(())
+}
+
+/// Return to the change shelved by `jf stash`
+pub fn restore(config: &Config) -> Result<()> {
+    let renderer = Renderer::from_config(config);
+    let runner = RealRunner::new(&config.tools);
+    let path = State::path()?;
+    let _lock = State::lock(&path)?;
+    let mut state = State::load_from(&path)?;
+
+    let Some(change_id) = state.stashed_change.take() else {
+        renderer.info("Nothing stashed");
+        return Ok(());
+    };
+    let short_id = &change_id[..8.min(change_id.len())];
+
+    // An abandoned change no longer resolves; drop the stale record
+    if !change_exists(&runner, &change_id) {
+        state.save_to(&path)?;
+        anyhow::bail!("Stashed change {} no longer exists (abandoned?)", short_id);
+    }
+
+    if let Err(e) = jj::run_jj(&runner, &["edit", &change_id]) {
+        anyhow::bail!("Could not restore {}: {}", short_id, e.to_string().trim());
+    }
+    state.save_to(&path)?;
+    renderer.success(&format!("Restored {}", short_id));
+
+    // Show updated stack
+    let revset = config.stack_revset();
+    let stack = jj::get_stack(&runner, &revset, &config.remote.name)?;
+    renderer.render_stack(&stack, &config.trunk_ref());
+
+    Ok(())
+}
+
+/// Check whether `change_id` still resolves to a visible change
+fn change_exists(runner: &RealRunner, change_id: &str) -> bool {
+    jj::run_jj(runner, &["log", "-r", change_id, "--no-graph", "-T", "change_id"])
+        .map(|output| !output.trim().is_empty())
+        .unwrap_or(false)
+}
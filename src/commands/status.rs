@@ -1,23 +1,536 @@
 use anyhow::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::capabilities::Capabilities;
 use crate::config::Config;
-use crate::jj;
-use crate::ui::{get_icon_set, get_theme, Renderer};
+use crate::jj::types::{ChangeWithStatus, PrState, TrackedBookmark};
+use crate::jj::{self, CommandRunner, RealRunner};
+use crate::ui::{LineFormat, Renderer};
+
+/// Options for `jf status`
+#[derive(Debug, Default)]
+pub struct StatusOptions<'a> {
+    /// Only show changes authored by the current user
+    pub mine: bool,
+    /// Compare against this remote instead of the configured one
+    pub remote: Option<&'a str>,
+    pub compact: bool,
+    pub plain: bool,
+    /// Show +added/-removed line counts
+    pub stat: bool,
+    /// Show full descriptions
+    pub full: bool,
+    /// Show only each bookmarked change's PR and CI rollup
+    pub ci: bool,
+    /// Group changes under the bookmark (PR) that covers them
+    pub group: bool,
+    /// Color each change by its author, with a legend
+    pub author_colors: bool,
+    /// Also list tracked remote bookmarks outside the stack
+    pub all: bool,
+    /// Render each change from this template instead of the box
+    pub format: Option<&'a LineFormat>,
+}
+
+/// Longest `status` waits on gh for PR markers: they're decoration, not worth a stall
+const PR_STATE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn run(config: &Config, caps: &Capabilities, options: &StatusOptions) -> Result<()> {
+    let StatusOptions {
+        mine,
+        remote,
+        compact,
+        plain,
+        stat,
+        full,
+        ci,
+        group,
+        author_colors,
+        all,
+        format,
+    } = *options;
 
-pub fn run(config: &Config) -> Result<()> {
     // Check jj is available
-    jj::check_jj_available()?;
+    let runner = RealRunner::new(&config.tools);
+    jj::check_jj_available(&runner)?;
+
+    // Compare against another remote: the stack, trunk, and bookmark sync all follow it
+    let mut config = config.clone();
+    if let Some(remote) = remote {
+        config.remote.name = remote.to_string();
+    }
+    let config = &config;
+
+    // Resolve the primary branch once; the warning, the stack, and the box all use it
+    let primary_ref = config.primary_ref();
+    let base_renderer = Renderer::from_config(config).with_plain(plain);
+
+    // Without a primary branch the "stack" is every commit ever; say so up front
+    if let Some(warning) = root_fallback_warning(config, &primary_ref) {
+        base_renderer.warning(&warning);
+    }
+
+    if ci {
+        return run_ci(config, &base_renderer, &config.stack_revset_over(&primary_ref));
+    }
 
-    // Get theme and icons
-    let theme = get_theme(&config.display.theme);
-    let icons = get_icon_set(&config.display.icons);
-    let renderer = Renderer::new(theme, icons);
+    // Set up renderer (shows other authors relative to the current user)
+    let user_email = jj::get_user_email(&runner);
+    let renderer = base_renderer
+        .with_current_user(user_email.clone())
+        .with_stats(stat)
+        .with_full_descriptions(full)
+        .with_grouped(group)
+        .with_author_colors(author_colors);
 
     // Query the stack
-    let revset = config.stack_revset();
-    let stack = jj::get_stack(&revset, &config.remote.name)?;
+    let revset = config.stack_revset_over(&primary_ref);
+    let jj::QueryResult { changes: mut stack, skipped } = if config.general.cache_stack {
+        jj::get_stack_result_cached(&runner, &revset, &config.remote.name)?
+    } else {
+        jj::get_stack_result(&runner, &revset, &config.remote.name)?
+    };
+    if stat {
+        jj::add_diff_stats(&runner, &mut stack, &revset)?;
+    }
+    if full {
+        jj::add_full_descriptions(&runner, &mut stack, &revset)?;
+    }
+
+    // Only keep changes authored by the current user
+    if mine {
+        if let Some(email) = &user_email {
+            stack.retain(|item| item.change.is_authored_by(email));
+        } else {
+            renderer.info("user.email is not set in jj config; showing all changes");
+        }
+    }
+
+    // PR markers, and merged PRs make the box footer suggest `jf land`; both take the network
+    if caps.gh && !(plain || compact || format.is_some() || config.offline) {
+        let gh = runner.clone().with_timeout(Some(pr_state_timeout(config.network.timeout())));
+        add_pr_states(&gh, config.github.repo.as_deref(), &mut stack);
+    }
+    // Showing the stack is the point of status, so --quiet doesn't hide it
+    let renderer = renderer.with_merged_bookmarks(merged_bookmarks(&stack)).with_quiet(false);
 
     // Render
-    renderer.render_stack(&stack, &config.trunk_ref());
+    if let Some(format) = format {
+        renderer.render_stack_formatted(&stack, format);
+    } else if plain {
+        renderer.render_stack_plain(&stack);
+    } else if compact {
+        renderer.render_stack_compact(&stack);
+    } else {
+        renderer.render_stack(&stack, &primary_ref);
+    }
+    let tracked = tracked_outside_stack(&runner, config, &stack, all, jj::jj_version(&runner))?;
+    renderer.render_tracked_bookmarks(&tracked);
+    if skipped > 0 {
+        renderer.warning(&format!("{} change(s) could not be parsed and are not shown", skipped));
+    }
+
+    Ok(())
+}
+
+/// With `all`, the bookmarks tracking the remote that aren't on a stack change and
+/// aren't the primary branch: what else exists besides your own work
+fn tracked_outside_stack(
+    runner: &dyn CommandRunner,
+    config: &Config,
+    stack: &[ChangeWithStatus],
+    all: bool,
+    version: Option<jj::query::JjVersion>,
+) -> Result<Vec<TrackedBookmark>> {
+    if !all {
+        return Ok(Vec::new());
+    }
+    Ok(jj::query_tracked_bookmarks_with(runner, &config.remote.name, version)?
+        .into_iter()
+        .filter(|b| b.name != config.remote.primary)
+        .filter(|b| {
+            // Bookmark IDs are short; a change can carry several bookmarks
+            !stack.iter().any(|item| {
+                item.bookmark.as_deref() == Some(b.name.as_str())
+                    || (!b.change_id.is_empty() && item.change.change_id.starts_with(&b.change_id))
+            })
+        })
+        .collect())
+}
+
+/// Warning shown when the stack falls back to `root()` for lack of a primary branch
+fn root_fallback_warning(config: &Config, primary_ref: &str) -> Option<String> {
+    config.primary_missing(primary_ref).then(|| {
+        format!(
+            "No primary branch found ({}@{} or {}); showing full history - run `jf init` or push your {} branch",
+            config.remote.primary, config.remote.name, config.remote.primary, config.remote.primary
+        )
+    })
+}
+
+/// CI rollup of a PR's checks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CiState {
+    Passing,
+    Failing,
+    Pending,
+    /// The PR has no checks configured
+    NoChecks,
+}
+
+impl CiState {
+    fn label(&self) -> &'static str {
+        match self {
+            CiState::Passing => "pass",
+            CiState::Failing => "fail",
+            CiState::Pending => "pending",
+            CiState::NoChecks => "no checks",
+        }
+    }
+}
+
+/// An open PR from `gh pr list --json number,headRefName,statusCheckRollup`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PrChecks {
+    number: u64,
+    head_ref_name: String,
+    #[serde(default)]
+    status_check_rollup: Vec<Check>,
+}
+
+/// One entry of `statusCheckRollup`: a check run (`status` + `conclusion`) or a
+/// commit status (`state`)
+#[derive(Debug, Default, Deserialize)]
+struct Check {
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    conclusion: Option<String>,
+    #[serde(default)]
+    state: Option<String>,
+}
+
+impl Check {
+    fn state(&self) -> CiState {
+        if let Some(state) = &self.state {
+            return match state.as_str() {
+                "SUCCESS" => CiState::Passing,
+                "FAILURE" | "ERROR" => CiState::Failing,
+                _ => CiState::Pending,
+            };
+        }
+        if self.status.as_deref() != Some("COMPLETED") {
+            return CiState::Pending;
+        }
+        match self.conclusion.as_deref() {
+            Some("SUCCESS" | "NEUTRAL" | "SKIPPED") => CiState::Passing,
+            _ => CiState::Failing,
+        }
+    }
+}
+
+/// Roll a PR's checks up into one state: any failure fails, then any pending is pending
+fn rollup(checks: &[Check]) -> CiState {
+    let states: Vec<CiState> = checks.iter().map(Check::state).collect();
+    if states.is_empty() {
+        CiState::NoChecks
+    } else if states.contains(&CiState::Failing) {
+        CiState::Failing
+    } else if states.contains(&CiState::Pending) {
+        CiState::Pending
+    } else {
+        CiState::Passing
+    }
+}
+
+/// Final line of `status --ci`, e.g. "3/4 PRs passing, 1 failing"
+fn summarize(states: &[CiState]) -> String {
+    let count = |state: CiState| states.iter().filter(|s| **s == state).count();
+    let mut summary = format!("{}/{} PRs passing", count(CiState::Passing), states.len());
+    for (state, label) in [
+        (CiState::Failing, "failing"),
+        (CiState::Pending, "pending"),
+        (CiState::NoChecks, "without checks"),
+    ] {
+        if count(state) > 0 {
+            summary.push_str(&format!(", {} {}", count(state), label));
+        }
+    }
+    summary
+}
+
+/// Fetch open PRs with their checks, keyed by head branch
+fn fetch_pr_checks(runner: &dyn CommandRunner, repo: Option<&str>) -> Result<HashMap<String, PrChecks>> {
+    let output = runner.run(
+        "gh",
+        &super::gh_args(
+            repo,
+            &["pr", "list", "--state", "open", "--json", "number,headRefName,statusCheckRollup", "--limit", "200"],
+        ),
+    )?;
+    let prs: Vec<PrChecks> = serde_json::from_str(&output)?;
+    Ok(prs.into_iter().map(|pr| (pr.head_ref_name.clone(), pr)).collect())
+}
+
+/// A PR from `gh pr list --state all --json headRefName,state`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BookmarkPr {
+    head_ref_name: String,
+    state: PrState,
+}
 
+/// Set each pushed bookmark's PR state from one `gh pr list` (best-effort: no gh or
+/// no network leaves every `pr_state` unset, so no marker is shown)
+fn add_pr_states(runner: &dyn CommandRunner, repo: Option<&str>, stack: &mut [ChangeWithStatus]) {
+    if !stack.iter().any(|c| c.has_remote && c.bookmark.is_some()) {
+        return;
+    }
+    let Ok(output) = runner.run(
+        "gh",
+        &super::gh_args(repo, &["pr", "list", "--state", "all", "--json", "headRefName,state", "--limit", "200"]),
+    ) else {
+        return;
+    };
+    let prs: Vec<BookmarkPr> = serde_json::from_str(&output).unwrap_or_default();
+
+    // gh lists newest first; a reopened branch's latest PR is the one that counts
+    let mut states = HashMap::new();
+    for pr in prs {
+        states.entry(pr.head_ref_name).or_insert(pr.state);
+    }
+    for item in stack.iter_mut().filter(|c| c.has_remote) {
+        item.pr_state = item.bookmark.as_ref().and_then(|b| states.get(b).copied());
+    }
+}
+
+/// Timeout for the PR lookup: the network timeout, but never over `PR_STATE_TIMEOUT`
+fn pr_state_timeout(network: Option<Duration>) -> Duration {
+    network.map_or(PR_STATE_TIMEOUT, |t| t.min(PR_STATE_TIMEOUT))
+}
+
+/// Bookmarks in the stack whose PRs are merged
+fn merged_bookmarks(stack: &[ChangeWithStatus]) -> Vec<String> {
+    stack
+        .iter()
+        .filter(|c| c.pr_state == Some(PrState::Merged))
+        .filter_map(|c| c.bookmark.clone())
+        .collect()
+}
+
+/// Terse CI view: each bookmarked change's PR number and CI rollup, then a total
+fn run_ci(config: &Config, renderer: &Renderer, revset: &str) -> Result<()> {
+    // PRs and their checks live on GitHub; offline there's nothing to show
+    if config.offline {
+        renderer.info("Offline - skipping PR and CI checks");
+        return Ok(());
+    }
+    let runner = super::network_runner(config, renderer);
+    let stack = jj::get_stack(&RealRunner::new(&config.tools), revset, &config.remote.name)?;
+    let prs = renderer.with_spinner("checking PRs", || fetch_pr_checks(&runner, config.github.repo.as_deref()))?;
+
+    let mut states = Vec::new();
+    for item in &stack {
+        let Some(pr) = item.bookmark.as_ref().and_then(|b| prs.get(b)) else {
+            continue;
+        };
+        let state = rollup(&pr.status_check_rollup);
+        let short_id = &item.change.change_id[..8.min(item.change.change_id.len())];
+        println!("{}  #{:<5} {}  {}", short_id, pr.number, pr.head_ref_name, state.label());
+        states.push(state);
+    }
+
+    if states.is_empty() {
+        renderer.info("No open PRs for this stack");
+    } else {
+        println!("{}", summarize(&states));
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(json: &str) -> Check {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn stack_item(bookmark: &str, has_remote: bool) -> ChangeWithStatus {
+        serde_json::from_value(serde_json::json!({
+            "change": {"change_id": bookmark, "commit_id": "def456"},
+            "bookmark": bookmark,
+            "is_working": false,
+            "has_remote": has_remote,
+            "sync_state": "Synced",
+            "is_empty": false,
+            "is_immutable": false,
+            "additions": 0,
+            "deletions": 0,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_run_ci_offline_skips_network() {
+        let config = Config {
+            offline: true,
+            quiet: true,
+            ..Config::default()
+        };
+        // Nothing to run: no jj, no gh
+        run_ci(&config, &Renderer::from_config(&config), "::@ ~ ::main").unwrap();
+    }
+
+    const PR_LIST: &str = "gh pr list --state all --json headRefName,state --limit 200";
+
+    #[test]
+    fn test_add_pr_states_marks_pushed_bookmarks() {
+        use crate::jj::runner::mock::MockRunner;
+
+        let runner = MockRunner::new();
+        runner.mock_response(
+            PR_LIST,
+            r#"[{"headRefName":"merged","state":"MERGED"},{"headRefName":"open","state":"OPEN"},
+                {"headRefName":"retried","state":"OPEN"},{"headRefName":"retried","state":"CLOSED"},
+                {"headRefName":"local","state":"OPEN"}]"#,
+        );
+        let mut stack = [
+            stack_item("merged", true),
+            stack_item("open", true),
+            stack_item("retried", true),
+            stack_item("no-pr", true),
+            stack_item("local", false),
+        ];
+        add_pr_states(&runner, None, &mut stack);
+
+        let states: Vec<Option<PrState>> = stack.iter().map(|c| c.pr_state).collect();
+        assert_eq!(
+            states,
+            vec![Some(PrState::Merged), Some(PrState::Open), Some(PrState::Open), None, None]
+        );
+        assert_eq!(merged_bookmarks(&stack), vec!["merged"]);
+    }
+
+    #[test]
+    fn test_add_pr_states_without_gh() {
+        use crate::jj::runner::mock::MockRunner;
+
+        // Nothing pushed: no gh call at all
+        let quiet = MockRunner::new();
+        add_pr_states(&quiet, None, &mut [stack_item("local", false)]);
+        assert!(quiet.get_calls().is_empty());
+
+        // gh missing or failing: no markers
+        let failing = MockRunner::new();
+        failing.mock_error(PR_LIST, "gh: command not found");
+        let mut stack = [stack_item("open", true)];
+        add_pr_states(&failing, None, &mut stack);
+        assert_eq!(stack[0].pr_state, None);
+    }
+
+    #[test]
+    fn test_pr_state_timeout_is_short() {
+        assert_eq!(pr_state_timeout(Some(Duration::from_secs(60))), PR_STATE_TIMEOUT);
+        assert_eq!(pr_state_timeout(Some(Duration::from_secs(2))), Duration::from_secs(2));
+        // Even with network timeouts off, status doesn't wait indefinitely
+        assert_eq!(pr_state_timeout(None), PR_STATE_TIMEOUT);
+    }
+
+    #[test]
+    fn test_root_fallback_warning() {
+        let config = Config::default();
+
+        let warning = root_fallback_warning(&config, "root()").unwrap();
+        assert!(warning.starts_with("No primary branch found"));
+        assert!(warning.contains("showing full history"));
+        assert!(warning.contains("jf init"));
+
+        assert_eq!(root_fallback_warning(&config, "main@origin"), None);
+    }
+
+    #[test]
+    fn test_check_states() {
+        assert_eq!(check(r#"{"status":"COMPLETED","conclusion":"SUCCESS"}"#).state(), CiState::Passing);
+        assert_eq!(check(r#"{"status":"COMPLETED","conclusion":"SKIPPED"}"#).state(), CiState::Passing);
+        assert_eq!(check(r#"{"status":"COMPLETED","conclusion":"FAILURE"}"#).state(), CiState::Failing);
+        assert_eq!(check(r#"{"status":"COMPLETED","conclusion":"TIMED_OUT"}"#).state(), CiState::Failing);
+        assert_eq!(check(r#"{"status":"IN_PROGRESS","conclusion":""}"#).state(), CiState::Pending);
+        assert_eq!(check(r#"{"state":"SUCCESS"}"#).state(), CiState::Passing);
+        assert_eq!(check(r#"{"state":"ERROR"}"#).state(), CiState::Failing);
+        assert_eq!(check(r#"{"state":"PENDING"}"#).state(), CiState::Pending);
+    }
+
+    #[test]
+    fn test_rollup() {
+        let pass = || check(r#"{"status":"COMPLETED","conclusion":"SUCCESS"}"#);
+        let fail = || check(r#"{"state":"FAILURE"}"#);
+        let pending = || check(r#"{"status":"QUEUED"}"#);
+
+        assert_eq!(rollup(&[]), CiState::NoChecks);
+        assert_eq!(rollup(&[pass(), pass()]), CiState::Passing);
+        assert_eq!(rollup(&[pass(), pending()]), CiState::Pending);
+        assert_eq!(rollup(&[pending(), fail(), pass()]), CiState::Failing);
+    }
+
+    #[test]
+    fn test_summarize() {
+        use CiState::*;
+        assert_eq!(summarize(&[Passing, Passing, Failing, Passing]), "3/4 PRs passing, 1 failing");
+        assert_eq!(summarize(&[Passing, Passing]), "2/2 PRs passing");
+        assert_eq!(
+            summarize(&[Failing, Pending, NoChecks, Passing]),
+            "1/4 PRs passing, 1 failing, 1 pending, 1 without checks"
+        );
+    }
+
+    #[test]
+    fn test_parse_pr_list() {
+        use crate::jj::runner::mock::MockRunner;
+
+        let runner = MockRunner::new();
+        runner.mock_response(
+            "gh pr list --state open --json number,headRefName,statusCheckRollup --limit 200",
+            r#"[{"number":12,"headRefName":"feature","statusCheckRollup":[{"status":"COMPLETED","conclusion":"SUCCESS"}]},
+                {"number":13,"headRefName":"fix","statusCheckRollup":[]}]"#,
+        );
+
+        let prs = fetch_pr_checks(&runner, None).unwrap();
+        assert_eq!(prs["feature"].number, 12);
+        assert_eq!(rollup(&prs["feature"].status_check_rollup), CiState::Passing);
+        assert_eq!(rollup(&prs["fix"].status_check_rollup), CiState::NoChecks);
+    }
+
+    #[test]
+    fn test_tracked_bookmark_outside_stack_only_with_all() {
+        use crate::jj::runner::mock::MockRunner;
+
+        let runner = MockRunner::new();
+        let output = [
+            r#"{"name":"feature","remote":null,"change_id":"feature","synced":false,"ahead":null,"behind":null}"#,
+            r#"{"name":"feature","remote":"origin","change_id":"feature","synced":true,"ahead":0,"behind":0}"#,
+            r#"{"name":"main","remote":null,"change_id":"aaa111","synced":false,"ahead":null,"behind":null}"#,
+            r#"{"name":"main","remote":"origin","change_id":"aaa111","synced":true,"ahead":0,"behind":0}"#,
+            r#"{"name":"bob/fix","remote":null,"change_id":"bbb222","synced":false,"ahead":null,"behind":null}"#,
+            r#"{"name":"bob/fix","remote":"origin","change_id":"ccc333","synced":false,"ahead":0,"behind":2}"#,
+            r#"{"name":"scratch","remote":null,"change_id":"ddd444","synced":false,"ahead":null,"behind":null}"#,
+        ]
+        .join("\n");
+        runner.mock_response(&format!("jj bookmark list --all -T {}", crate::jj::query::BOOKMARK_TEMPLATE), &output);
+        let config = Config::default();
+        let stack = vec![stack_item("feature", true)];
+
+        // Off by default, without even listing bookmarks
+        assert!(tracked_outside_stack(&runner, &config, &stack, false, None).unwrap().is_empty());
+        assert!(runner.get_calls().is_empty());
+
+        // The stack's own bookmark, the primary, and untracked bookmarks are left out
+        let tracked = tracked_outside_stack(&runner, &config, &stack, true, None).unwrap();
+        let names: Vec<&str> = tracked.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["bob/fix"]);
+        assert!(matches!(tracked[0].sync_state, jj::types::BookmarkSyncState::Ahead { count: 2 }));
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::Result;
+
+use crate::capabilities::Capabilities;
+use crate::config::Config;
+use crate::jj::{self, CommandRunner, RealRunner};
+use crate::ui::Renderer;
+
+use super::{pull, push};
+
+/// Pull, rebase, push already-bookmarked changes, and refresh PR stack context
+pub fn run(config: &Config, caps: &Capabilities, dry_run: bool) -> Result<()> {
+    let renderer = Renderer::from_config(config);
+    let runner = super::network_runner(config, &renderer);
+    let local = RealRunner::new(&config.tools);
+
+    let remote = &config.remote.name;
+    let squash = config.github.push_style == "squash";
+    let gh_repo = config.github.repo.as_deref();
+
+    if dry_run {
+        let changes = jj::query_changes(&local, &config.stack_revset())?;
+
+        println!("\nDry run - would:");
+        println!("  - Fetch from {}", remote);
+        println!("  - Rebase stack onto {}", config.trunk_ref());
+        for change in &changes {
+            let short_id = &change.change_id[..8.min(change.change_id.len())];
+            match change.bookmarks.first() {
+                Some(bookmark) => {
+                    println!("  - Push {} ({}) [style: {}]", bookmark, short_id, config.github.push_style);
+                    if config.github.stack_context {
+                        println!("  - Update stack context for {}", bookmark);
+                    }
+                }
+                None => println!("  - Skip {} (no bookmark)", short_id),
+            }
+        }
+        return Ok(());
+    }
+
+    // Fetch and rebase, exactly as `jf pull`
+    pull::fetch_and_rebase(config, &runner, &renderer, remote)?;
+
+    // Re-query after the rebase so we push the rewritten commits
+    let changes = jj::query_changes(&local, &config.stack_revset())?;
+    if super::is_empty_stack(&changes) {
+        renderer.info(&super::empty_stack_message(config));
+        return Ok(());
+    }
+
+    for change in &changes {
+        let short_id = &change.change_id[..8.min(change.change_id.len())];
+
+        let Some(bookmark) = change.bookmarks.first() else {
+            renderer.info(&format!(
+                "Skipping {} (no bookmark - use `jf push` to create one)",
+                short_id
+            ));
+            continue;
+        };
+
+        if change.description.trim().is_empty() {
+            renderer.info(&format!("Skipping {} (no description)", short_id));
+            continue;
+        }
+
+        if squash {
+            push::guard_force_push(&runner, &renderer, bookmark, remote, false)?;
+        }
+
+        renderer.info(&format!("Pushing {}...", bookmark));
+        renderer.with_spinner("pushing", || push::push_bookmark(&runner, bookmark, remote, squash))?;
+
+        // Refresh stack context in the PR description
+        if config.github.stack_context && caps.gh && push::get_pr_for_branch(&local, bookmark, gh_repo)?.is_some() {
+            let body = push::create_pr_body_with_stack(change, config)?;
+            renderer.with_spinner("updating pull request", || {
+                runner.run("gh", &super::gh_args(gh_repo, &["pr", "edit", bookmark, "--body", &body]))
+            })?;
+            renderer.info(&format!("Updated stack context for {}", bookmark));
+        }
+    }
+
+    renderer.success("Synced!");
+    println!();
+
+    // Show updated stack
+    let stack = jj::get_stack(&local, &config.stack_revset(), remote)?;
+    renderer.render_stack(&stack, &config.trunk_ref());
+
+    Ok(())
+}
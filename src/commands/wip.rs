@@ -1,15 +1,61 @@
 use anyhow::Result;
 
 use crate::config::Config;
-use crate::jj;
-use crate::ui::{get_icon_set, get_theme, Renderer};
+use crate::jj::{self, CommandRunner, RealRunner};
+use crate::state::State;
+use crate::ui::Renderer;
+
+/// Outcome of checking the remote wip tip against the one we last saw
+#[derive(Debug, PartialEq, Eq)]
+enum Lease {
+    /// The remote is where we last saw it (or we never saw it), safe to overwrite
+    Held,
+    /// Someone pushed since we last saw the remote
+    Moved { expected: String, actual: String },
+}
+
+/// Commit the remote wip bookmark points at, if it exists
+fn remote_tip(runner: &dyn CommandRunner, remote_ref: &str) -> Option<String> {
+    runner
+        .run("jj", &["log", "-r", remote_ref, "--limit", "1", "--no-graph", "-T", "commit_id"])
+        .ok()
+        .map(|output| output.trim().to_string())
+        .filter(|commit| !commit.is_empty())
+}
+
+/// Check that the remote wip tip hasn't moved since we last pushed or pulled it
+fn check_lease(runner: &dyn CommandRunner, remote_ref: &str, expected: Option<&str>) -> Lease {
+    match (expected, remote_tip(runner, remote_ref)) {
+        (Some(expected), Some(actual)) if expected != actual => Lease::Moved {
+            expected: expected.to_string(),
+            actual,
+        },
+        _ => Lease::Held,
+    }
+}
+
+/// Remember where the remote wip bookmark is now, for the next `--force` push
+fn record_remote_tip(runner: &dyn CommandRunner, bookmark: &str, remote_ref: &str) -> Result<()> {
+    let path = State::path()?;
+    let _lock = State::lock(&path)?;
+    let mut state = State::load_from(&path)?;
+    match remote_tip(runner, remote_ref) {
+        Some(tip) => state.wip_remote_tips.insert(bookmark.to_string(), tip),
+        None => state.wip_remote_tips.remove(bookmark),
+    };
+    state.save_to(&path)
+}
 
 /// Get the wip bookmark name for the current user
-fn wip_bookmark_name() -> Result<String> {
+fn wip_bookmark_name(config: &Config, runner: &RealRunner) -> Result<String> {
     // Get username from jj config (user.name)
-    let output = jj::run_jj(&["config", "get", "user.name"])?;
-    let username = output.trim();
+    let output = jj::run_jj(runner, &["config", "get", "user.name"])?;
+    let email = if config.wip.disambiguate { jj::get_user_email(runner) } else { None };
+    Ok(format_wip_bookmark(output.trim(), email.as_deref()))
+}
 
+/// Build `wip/<name-slug>`, with `-<email hash>` appended when an email is given
+fn format_wip_bookmark(username: &str, email: Option<&str>) -> String {
     // Slugify: lowercase, replace spaces/special chars with dashes
     let slug: String = username
         .to_lowercase()
@@ -21,22 +67,34 @@ fn wip_bookmark_name() -> Result<String> {
         .collect::<Vec<_>>()
         .join("-");
 
-    Ok(format!("wip/{}", slug))
+    match email {
+        Some(email) => format!("wip/{}-{}", slug, email_hash(email)),
+        None => format!("wip/{}", slug),
+    }
+}
+
+/// Short hash of an email (FNV-1a), stable across machines and Rust versions
+fn email_hash(email: &str) -> String {
+    let hash = email
+        .trim()
+        .to_lowercase()
+        .bytes()
+        .fold(0x811c9dc5u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x01000193));
+    format!("{:08x}", hash)[..6].to_string()
 }
 
 pub fn run(
     config: &Config,
     subcommand: Option<&str>,
     force: bool,
+    stash: bool,
 ) -> Result<()> {
-    let theme = get_theme(&config.display.theme);
-    let icons = get_icon_set(&config.display.icons);
-    let renderer = Renderer::new(theme, icons);
+    let renderer = Renderer::from_config(config);
 
     match subcommand {
         None => run_status(config, &renderer),
         Some("push") => run_push(config, &renderer, force),
-        Some("pull") => run_pull(config, &renderer),
+        Some("pull") => run_pull(config, &renderer, stash),
         Some("clean") => run_clean(config, &renderer, force),
         Some(cmd) => {
             renderer.error(&format!("Unknown subcommand: {}", cmd));
@@ -45,6 +103,7 @@ pub fn run(
             println!("  jf wip              # show wip status");
             println!("  jf wip push         # push stack to wip branch");
             println!("  jf wip pull         # pull wip branch and rebase");
+            println!("  jf wip pull --stash # bookmark local changes aside, then pull");
             println!("  jf wip clean        # delete wip branch");
             Ok(())
         }
@@ -53,12 +112,13 @@ pub fn run(
 
 /// Show status of wip bookmark
 fn run_status(config: &Config, renderer: &Renderer) -> Result<()> {
-    let bookmark = wip_bookmark_name()?;
+    let runner = RealRunner::new(&config.tools);
+    let bookmark = wip_bookmark_name(config, &runner)?;
     let remote = &config.remote.name;
 
     // Check if wip bookmark exists on remote
     let remote_ref = format!("{}@{}", bookmark, remote);
-    if !revision_exists(&remote_ref) {
+    if !revision_exists(&runner, &remote_ref) {
         renderer.info(&format!("No wip branch found ({})", bookmark));
         println!("  Use `jf wip push` to push your stack");
         return Ok(());
@@ -67,7 +127,7 @@ fn run_status(config: &Config, renderer: &Renderer) -> Result<()> {
     // Get changes in the wip bookmark
     let main_ref = config.trunk_ref();
     let revset = format!("{}::({}) ~ ::{})", main_ref, remote_ref, main_ref);
-    let changes = jj::query_changes(&revset)?;
+    let changes = jj::query_changes(&runner, &revset)?;
 
     renderer.info(&format!("{} on {}:", bookmark, remote));
     if changes.is_empty() {
@@ -89,12 +149,13 @@ fn run_status(config: &Config, renderer: &Renderer) -> Result<()> {
 
 /// Push stack to wip bookmark
 fn run_push(config: &Config, renderer: &Renderer, force: bool) -> Result<()> {
-    let bookmark = wip_bookmark_name()?;
+    let runner = RealRunner::new(&config.tools);
+    let bookmark = wip_bookmark_name(config, &runner)?;
     let remote = &config.remote.name;
 
     // Check if we have any changes to push
     let revset = config.stack_revset();
-    let changes = jj::query_changes(&revset)?;
+    let changes = jj::query_changes(&runner, &revset)?;
 
     if changes.is_empty() {
         renderer.info("No changes in stack to push");
@@ -103,11 +164,42 @@ fn run_push(config: &Config, renderer: &Renderer, force: bool) -> Result<()> {
 
     // Fetch first to get accurate remote state
     renderer.info("Checking remote...");
-    jj::run_jj(&["git", "fetch", "--remote", remote])?;
+    jj::run_jj(&runner, &["git", "fetch", "--remote", remote])?;
 
     // Check if wip bookmark already exists on remote
     let remote_ref = format!("{}@{}", bookmark, remote);
-    let exists_on_remote = revision_exists(&remote_ref);
+    let exists_on_remote = revision_exists(&runner, &remote_ref);
+
+    // --force only overwrites the snapshot we last saw, like git's --force-with-lease
+    if exists_on_remote && force {
+        let state = State::load_from(&State::path()?)?;
+        let expected = state.wip_remote_tips.get(&bookmark).map(String::as_str);
+        if let Lease::Moved { expected, actual } = check_lease(&runner, &remote_ref, expected) {
+            renderer.error(&format!("{} on {} moved since you last pushed or pulled it", bookmark, remote));
+            println!("  expected {}, found {}", short_commit(&expected), short_commit(&actual));
+
+            let main_ref = config.trunk_ref();
+            let new_revset = format!("({}::{}) ~ ::{}", main_ref, remote_ref, expected);
+            // The old tip may have been rewritten away locally; the commit ids above still tell the story
+            let new_changes = jj::query_changes(&runner, &new_revset).unwrap_or_default();
+            if !new_changes.is_empty() {
+                println!();
+                for change in &new_changes {
+                    let short_id = &change.change_id[..8.min(change.change_id.len())];
+                    let desc = if change.description.is_empty() {
+                        "(no description)".to_string()
+                    } else {
+                        change.description.clone()
+                    };
+                    println!("  ○ {}  {}", short_id, desc);
+                }
+            }
+
+            println!();
+            println!("  Run `jf wip pull` to see the newer snapshot before overwriting it.");
+            anyhow::bail!("Refusing to overwrite {} on {}", bookmark, remote);
+        }
+    }
 
     if exists_on_remote && !force {
         renderer.error(&format!("{} already exists on {}", bookmark, remote));
@@ -115,7 +207,7 @@ fn run_push(config: &Config, renderer: &Renderer, force: bool) -> Result<()> {
         // Show what's there
         let main_ref = config.trunk_ref();
         let existing_revset = format!("{}::({}) ~ ::({})", main_ref, remote_ref, main_ref);
-        let existing_changes = jj::query_changes(&existing_revset)?;
+        let existing_changes = jj::query_changes(&runner, &existing_revset)?;
 
         if !existing_changes.is_empty() {
             println!();
@@ -132,7 +224,7 @@ fn run_push(config: &Config, renderer: &Renderer, force: bool) -> Result<()> {
 
         println!();
         println!("  Use `--force` to overwrite, or `jf wip pull` to fetch it first.");
-        return Ok(());
+        anyhow::bail!("Refusing to overwrite {} on {} without --force", bookmark, remote);
     }
 
     renderer.info(&format!(
@@ -141,65 +233,57 @@ fn run_push(config: &Config, renderer: &Renderer, force: bool) -> Result<()> {
         bookmark
     ));
 
-    let local_exists = bookmark_exists(&bookmark);
+    let local_exists = bookmark_exists(&runner, &bookmark);
 
     // If bookmark exists on remote but not locally, track it first
     if exists_on_remote && !local_exists {
-        jj::run_jj(&["bookmark", "track", &format!("{}@{}", bookmark, remote)])?;
+        jj::run_jj(&runner, &["bookmark", "track", &format!("{}@{}", bookmark, remote)])?;
     }
 
     // Push based on current state
     if exists_on_remote {
         // Remote exists and is tracked - set and push
-        jj::run_jj(&["bookmark", "set", &bookmark, "-r", "@"])?;
-        jj::run_jj(&["git", "push", "--bookmark", &bookmark])?;
+        jj::run_jj(&runner, &["bookmark", "set", &bookmark, "-r", "@"])?;
+        jj::run_jj(&runner, &["git", "push", "--bookmark", &bookmark])?;
     } else if local_exists {
         // Local exists but not on remote - delete local, use --named to create fresh
-        jj::run_jj(&["bookmark", "delete", &bookmark])?;
-        jj::run_jj(&["git", "push", "--named", &format!("{}=@", bookmark)])?;
+        jj::run_jj(&runner, &["bookmark", "delete", &bookmark])?;
+        jj::run_jj(&runner, &["git", "push", "--named", &format!("{}=@", bookmark)])?;
     } else {
         // Neither exists - use --named to create and push
-        jj::run_jj(&["git", "push", "--named", &format!("{}=@", bookmark)])?;
+        jj::run_jj(&runner, &["git", "push", "--named", &format!("{}=@", bookmark)])?;
     }
 
+    record_remote_tip(&runner, &bookmark, &remote_ref)?;
+
     renderer.success("Done!");
 
     Ok(())
 }
 
 /// Pull wip bookmark and rebase onto main
-fn run_pull(config: &Config, renderer: &Renderer) -> Result<()> {
-    let bookmark = wip_bookmark_name()?;
+fn run_pull(config: &Config, renderer: &Renderer, stash: bool) -> Result<()> {
+    let runner = RealRunner::new(&config.tools);
+    let bookmark = wip_bookmark_name(config, &runner)?;
     let remote = &config.remote.name;
 
     // Check for local changes first
     let revset = config.stack_revset();
-    let local_changes = jj::query_changes(&revset)?;
+    let local_changes = jj::query_changes(&runner, &revset)?;
 
-    if !local_changes.is_empty() {
-        renderer.error("You have local changes:");
-        println!();
-        for change in &local_changes {
-            let short_id = &change.change_id[..8.min(change.change_id.len())];
-            let desc = if change.description.is_empty() {
-                "(no description)".to_string()
-            } else {
-                change.description.clone()
-            };
-            println!("  ○ {}  {}", short_id, desc);
-        }
-        println!();
-        println!("  Clean up your local stack first, then try again.");
+    let stash_name = format!("wip-stash/{}", crate::ui::time::now());
+    let stash = stash.then_some(stash_name.as_str());
+    if !clear_local_changes(&runner, renderer, &local_changes, &revset, stash)? {
         return Ok(());
     }
 
     // Fetch from remote
     renderer.info("Fetching from origin...");
-    jj::run_jj(&["git", "fetch"])?;
+    jj::run_jj(&runner, &["git", "fetch"])?;
 
     // Check if wip bookmark exists on remote
     let remote_ref = format!("{}@{}", bookmark, remote);
-    if !revision_exists(&remote_ref) {
+    if !revision_exists(&runner, &remote_ref) {
         renderer.error(&format!("No wip branch found ({})", bookmark));
         return Ok(());
     }
@@ -207,7 +291,7 @@ fn run_pull(config: &Config, renderer: &Renderer) -> Result<()> {
     // Get changes from wip
     let main_ref = config.trunk_ref();
     let wip_revset = format!("{}::({}) ~ ::({})", main_ref, remote_ref, main_ref);
-    let wip_changes = jj::query_changes(&wip_revset)?;
+    let wip_changes = jj::query_changes(&runner, &wip_revset)?;
 
     if wip_changes.is_empty() {
         renderer.info("No changes in wip branch");
@@ -222,31 +306,89 @@ fn run_pull(config: &Config, renderer: &Renderer) -> Result<()> {
     renderer.info(&format!("Rebasing onto {}...", main_ref));
 
     // Rebase the entire wip branch onto main
-    jj::run_jj(&["rebase", "-s", &remote_ref, "-d", &main_ref])?;
+    jj::run_jj(&runner, &["rebase", "-s", &remote_ref, "-d", &main_ref])?;
 
     // Move @ to the tip (which is now rebased)
     // After rebase, the bookmark still points to the rebased tip
-    jj::run_jj(&["edit", &bookmark])?;
+    jj::run_jj(&runner, &["edit", &bookmark])?;
+
+    // We've now seen this snapshot, so a later --force push may replace it
+    record_remote_tip(&runner, &bookmark, &remote_ref)?;
 
     renderer.success("Done!");
 
     // Show the stack
     println!();
-    let stack = jj::get_stack(&config.stack_revset(), &config.remote.name)?;
+    let stack = jj::get_stack(&runner, &config.stack_revset(), &config.remote.name)?;
     renderer.render_stack(&stack, &config.trunk_ref());
 
     Ok(())
 }
 
+/// Make way for a wip pull. Without `stash`, local changes block the pull; with it,
+/// each head of the local stack is bookmarked under `stash` so the changes stay easy
+/// to find after `@` moves to the wip tip. Returns whether the pull may go ahead.
+fn clear_local_changes(
+    runner: &dyn CommandRunner,
+    renderer: &Renderer,
+    local_changes: &[jj::Change],
+    revset: &str,
+    stash: Option<&str>,
+) -> Result<bool> {
+    if local_changes.is_empty() {
+        return Ok(true);
+    }
+
+    let Some(stash) = stash else {
+        renderer.error("You have local changes:");
+        println!();
+        for change in local_changes {
+            let short_id = &change.change_id[..8.min(change.change_id.len())];
+            let desc = if change.description.is_empty() {
+                "(no description)".to_string()
+            } else {
+                change.description.clone()
+            };
+            println!("  ○ {}  {}", short_id, desc);
+        }
+        println!();
+        println!("  Clean up your local stack first, then try again.");
+        println!("  Or set them aside under a bookmark with: jf wip pull --stash");
+        return Ok(false);
+    };
+
+    let heads = format!("heads({})", revset);
+    let output = runner.run("jj", &["log", "-r", &heads, "--no-graph", "-T", "change_id ++ \"\\n\""])?;
+    let heads: Vec<&str> = output.lines().map(str::trim).filter(|id| !id.is_empty()).collect();
+
+    let mut stashed = Vec::new();
+    for (i, head) in heads.iter().enumerate() {
+        let name = if i == 0 { stash.to_string() } else { format!("{}-{}", stash, i + 1) };
+        jj::create_bookmark_with(runner, &name, head)?;
+        stashed.push(name);
+    }
+
+    renderer.success(&format!(
+        "Stashed {} local change(s) under {}",
+        local_changes.len(),
+        stashed.join(", ")
+    ));
+    if let Some(first) = stashed.first() {
+        println!("  Reconcile them with the wip changes later, e.g. `jj rebase -b {} -d @`", first);
+    }
+    Ok(true)
+}
+
 /// Clean up wip bookmark
 fn run_clean(config: &Config, renderer: &Renderer, force: bool) -> Result<()> {
-    let bookmark = wip_bookmark_name()?;
+    let runner = RealRunner::new(&config.tools);
+    let bookmark = wip_bookmark_name(config, &runner)?;
     let remote = &config.remote.name;
 
     // Check if bookmark exists
     let remote_ref = format!("{}@{}", bookmark, remote);
-    let local_exists = bookmark_exists(&bookmark);
-    let remote_exists = revision_exists(&remote_ref);
+    let local_exists = bookmark_exists(&runner, &bookmark);
+    let remote_exists = revision_exists(&runner, &remote_ref);
 
     if !local_exists && !remote_exists {
         renderer.info(&format!("No wip branch found ({})", bookmark));
@@ -257,7 +399,7 @@ fn run_clean(config: &Config, renderer: &Renderer, force: bool) -> Result<()> {
     let main_ref = config.trunk_ref();
     let wip_ref = if remote_exists { &remote_ref } else { &bookmark };
     let revset = format!("{}::({}) ~ ::({})", main_ref, wip_ref, main_ref);
-    let changes = jj::query_changes(&revset)?;
+    let changes = jj::query_changes(&runner, &revset)?;
 
     renderer.info(&format!("{} contains {} changes:", bookmark, changes.len()));
 
@@ -272,7 +414,7 @@ fn run_clean(config: &Config, renderer: &Renderer, force: bool) -> Result<()> {
         };
 
         // Check if this change has a non-wip bookmark (indicating a PR)
-        let has_pr = has_non_wip_bookmark(&change.change_id);
+        let has_pr = has_non_wip_bookmark(&runner, &change.change_id);
 
         if has_pr {
             println!("  ○ {}  {} ✓", short_id, desc);
@@ -291,12 +433,19 @@ fn run_clean(config: &Config, renderer: &Renderer, force: bool) -> Result<()> {
 
     // Delete local bookmark
     if local_exists {
-        jj::run_jj(&["bookmark", "delete", &bookmark])?;
+        jj::run_jj(&runner, &["bookmark", "delete", &bookmark])?;
     }
 
     // Delete remote bookmark
     if remote_exists {
-        jj::run_jj(&["git", "push", "--bookmark", &bookmark, "--delete"])?;
+        jj::run_jj(&runner, &["git", "push", "--bookmark", &bookmark, "--delete"])?;
+    }
+
+    let state_path = State::path()?;
+    let _lock = State::lock(&state_path)?;
+    let mut state = State::load_from(&state_path)?;
+    if state.wip_remote_tips.remove(&bookmark).is_some() {
+        state.save_to(&state_path)?;
     }
 
     renderer.success(&format!("Deleted bookmark {} (local and remote)", bookmark));
@@ -304,11 +453,14 @@ fn run_clean(config: &Config, renderer: &Renderer, force: bool) -> Result<()> {
     Ok(())
 }
 
-/// Check if a revision exists
-fn revision_exists(rev: &str) -> bool {
-    use std::process::Command;
+fn short_commit(commit: &str) -> &str {
+    &commit[..12.min(commit.len())]
+}
 
-    Command::new("jj")
+/// Check if a revision exists
+fn revision_exists(runner: &RealRunner, rev: &str) -> bool {
+    runner
+        .command("jj")
         .args(["log", "-r", rev, "--limit", "1", "--no-graph", "-T", "''"])
         .output()
         .map(|o| o.status.success())
@@ -316,10 +468,9 @@ fn revision_exists(rev: &str) -> bool {
 }
 
 /// Check if a bookmark exists locally
-fn bookmark_exists(bookmark: &str) -> bool {
-    use std::process::Command;
-
-    let output = Command::new("jj")
+fn bookmark_exists(runner: &RealRunner, bookmark: &str) -> bool {
+    let output = runner
+        .command("jj")
         .args(["bookmark", "list", "--all"])
         .output()
         .ok();
@@ -333,10 +484,9 @@ fn bookmark_exists(bookmark: &str) -> bool {
 }
 
 /// Check if a change has any bookmark other than wip/*
-fn has_non_wip_bookmark(change_id: &str) -> bool {
-    use std::process::Command;
-
-    let output = Command::new("jj")
+fn has_non_wip_bookmark(runner: &RealRunner, change_id: &str) -> bool {
+    let output = runner
+        .command("jj")
         .args(["log", "-r", change_id, "--no-graph", "-T", "bookmarks"])
         .output()
         .ok();
@@ -353,3 +503,118 @@ fn has_non_wip_bookmark(change_id: &str) -> bool {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jj::runner::mock::MockRunner;
+
+    const TIP_QUERY: &str = "jj log -r wip/alice@origin --limit 1 --no-graph -T commit_id";
+    const STACK: &str = "::@ ~ ::main@origin";
+    const HEADS_QUERY: &str = "jj log -r heads(::@ ~ ::main@origin) --no-graph -T change_id ++ \"\\n\"";
+
+    #[test]
+    fn test_wip_bookmark_slug() {
+        assert_eq!(format_wip_bookmark("Test User", None), "wip/test-user");
+        assert_eq!(format_wip_bookmark("  O'Brien, Pat ", None), "wip/o-brien-pat");
+    }
+
+    #[test]
+    fn test_wip_bookmark_disambiguated_by_email() {
+        let alice = format_wip_bookmark("Test User", Some("alice@example.com"));
+        let bob = format_wip_bookmark("Test User", Some("bob@example.com"));
+
+        assert_ne!(alice, bob);
+        assert!(alice.starts_with("wip/test-user-"));
+        assert_eq!(alice.len(), "wip/test-user-".len() + 6);
+        // Stable, and indifferent to email case
+        assert_eq!(alice, format_wip_bookmark("Test User", Some("Alice@Example.com")));
+        assert!(jj::validate_bookmark_name(&alice).is_ok());
+    }
+
+    #[test]
+    fn test_force_rejected_when_remote_moved() {
+        let runner = MockRunner::new();
+        runner.mock_response(TIP_QUERY, "ffff0000ffff0000\n");
+
+        let lease = check_lease(&runner, "wip/alice@origin", Some("aaaa1111aaaa1111"));
+        assert_eq!(
+            lease,
+            Lease::Moved {
+                expected: "aaaa1111aaaa1111".to_string(),
+                actual: "ffff0000ffff0000".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_force_allowed_when_remote_unchanged() {
+        let runner = MockRunner::new();
+        runner.mock_response(TIP_QUERY, "aaaa1111aaaa1111\n");
+
+        assert_eq!(check_lease(&runner, "wip/alice@origin", Some("aaaa1111aaaa1111")), Lease::Held);
+    }
+
+    #[test]
+    fn test_force_allowed_without_recorded_tip() {
+        let runner = MockRunner::new();
+        runner.mock_response(TIP_QUERY, "ffff0000ffff0000\n");
+
+        assert_eq!(check_lease(&runner, "wip/alice@origin", None), Lease::Held);
+    }
+
+    fn change(id: &str) -> jj::Change {
+        jj::Change {
+            change_id: id.to_string(),
+            commit_id: "abc123".to_string(),
+            description: "Local work".to_string(),
+            author: Default::default(),
+            bookmarks: vec![],
+            timestamp: String::new(),
+            is_empty: false,
+            committer: Default::default(),
+            has_conflict: false,
+            parents: vec![],
+        }
+    }
+
+    fn test_renderer() -> Renderer {
+        Renderer::new(crate::ui::get_theme("default"), crate::ui::get_icon_set("ascii")).with_quiet(true)
+    }
+
+    #[test]
+    fn test_pull_refuses_local_changes_without_stash() {
+        let runner = MockRunner::new();
+        let renderer = test_renderer();
+
+        let proceed = clear_local_changes(&runner, &renderer, &[change("qpvuntsmwlqt")], STACK, None).unwrap();
+        assert!(!proceed);
+        assert!(runner.get_calls().is_empty());
+    }
+
+    #[test]
+    fn test_pull_stashes_local_changes_under_bookmark() {
+        let runner = MockRunner::new();
+        let renderer = test_renderer();
+        runner.mock_response(HEADS_QUERY, "qpvuntsmwlqt\n");
+        runner.mock_response(
+            "jj log -r bookmarks(exact:\"wip-stash/1700000000\") --no-graph -T change_id ++ \"\\n\"",
+            "",
+        );
+        runner.mock_response("jj bookmark create wip-stash/1700000000 -r qpvuntsmwlqt", "");
+
+        let local = [change("qpvuntsmwlqt"), change("kkmpptxzrspx")];
+        let proceed = clear_local_changes(&runner, &renderer, &local, STACK, Some("wip-stash/1700000000")).unwrap();
+        assert!(proceed);
+        assert!(runner.was_called("jj", &["bookmark", "create", "wip-stash/1700000000", "-r", "qpvuntsmwlqt"]));
+    }
+
+    #[test]
+    fn test_pull_proceeds_without_local_changes() {
+        let runner = MockRunner::new();
+        let renderer = test_renderer();
+
+        assert!(clear_local_changes(&runner, &renderer, &[], STACK, None).unwrap());
+        assert!(runner.get_calls().is_empty());
+    }
+}
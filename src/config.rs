@@ -2,8 +2,17 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+use crate::jj::{CommandRunner, RealRunner};
+use crate::ui::{ICON_SET_NAMES, THEME_NAMES};
+
+/// Primary ref used when no primary branch exists
+const ROOT_REF: &str = "root()";
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
+    #[serde(default)]
+    pub general: GeneralConfig,
+
     #[serde(default)]
     pub remote: RemoteConfig,
 
@@ -15,6 +24,74 @@ pub struct Config {
 
     #[serde(default)]
     pub bookmarks: BookmarkConfig,
+
+    #[serde(default)]
+    pub network: NetworkConfig,
+
+    #[serde(default)]
+    pub land: LandConfig,
+
+    #[serde(default)]
+    pub wip: WipConfig,
+
+    #[serde(default)]
+    pub hooks: HooksConfig,
+
+    #[serde(default)]
+    pub tools: ToolsConfig,
+
+    /// Skip network access (`--offline` / `JF_OFFLINE`); set at runtime, never from a file
+    #[serde(skip)]
+    pub offline: bool,
+
+    /// Only print errors and requested data (`--quiet`); set at runtime, never from a file
+    #[serde(skip)]
+    pub quiet: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GeneralConfig {
+    /// Command `jf` runs with no arguments
+    #[serde(default)]
+    pub default_command: DefaultCommand,
+
+    /// Reuse the last `jf status` stack while jj's operation log is unchanged
+    #[serde(default)]
+    pub cache_stack: bool,
+}
+
+/// Commands allowed as `[general] default_command`: only ones that don't change the
+/// repo or the remote, so a bare `jf` is always safe. Anything else fails to parse.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DefaultCommand {
+    #[default]
+    Status,
+    Diff,
+    Config,
+}
+
+impl DefaultCommand {
+    /// The subcommand name, as typed on the command line
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DefaultCommand::Status => "status",
+            DefaultCommand::Diff => "diff",
+            DefaultCommand::Config => "config",
+        }
+    }
+}
+
+/// Which primary ref `[remote] primary_resolution` prefers when both exist.
+/// Anything else fails to parse.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PrimaryResolution {
+    /// `main@origin`, then `main`
+    #[default]
+    RemoteFirst,
+    /// `main`, then `main@origin`
+    LocalFirst,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -27,6 +104,16 @@ pub struct RemoteConfig {
     /// Note: "trunk" is accepted as an alias for backward compatibility
     #[serde(default = "default_primary", alias = "trunk")]
     pub primary: String,
+
+    /// Which primary ref wins when both exist: "remote-first" (main@origin) or
+    /// "local-first" (main)
+    #[serde(default)]
+    pub primary_resolution: PrimaryResolution,
+
+    /// Base revision the stack is measured from, instead of the primary branch
+    /// (e.g., an integration branch). Overridden by `--base`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_override: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -42,6 +129,34 @@ pub struct GitHubConfig {
     /// Add stack context to PR descriptions
     #[serde(default = "default_true")]
     pub stack_context: bool,
+
+    /// Refuse to push changes whose commits aren't cryptographically signed
+    #[serde(default)]
+    pub require_signed: bool,
+
+    /// Refuse to push a stack with more changes than this (catches a misconfigured primary)
+    #[serde(default = "default_max_stack")]
+    pub max_stack: usize,
+
+    /// Truncate PR titles longer than this many characters (0 disables); the body
+    /// keeps the full subject
+    #[serde(default = "default_max_title_len")]
+    pub max_title_len: usize,
+
+    /// With append-style pushes, title new PRs from the bottom commit and fill the
+    /// body with every commit's description
+    #[serde(default)]
+    pub squash_descriptions: bool,
+
+    /// Refuse to push changes whose subject isn't a conventional commit
+    /// (`type(scope)!: subject`)
+    #[serde(default)]
+    pub require_conventional: bool,
+
+    /// GitHub repository PRs go to, as "owner/name" (detected by `jf init`);
+    /// passed to gh as `--repo` so it doesn't have to guess from the remotes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -57,6 +172,19 @@ pub struct DisplayConfig {
     /// Icons: unicode, ascii
     #[serde(default = "default_icons")]
     pub icons: String,
+
+    /// Width of the status box (default: `COLUMNS` or terminal width - 2, capped at 80)
+    #[serde(default)]
+    pub box_width: Option<usize>,
+
+    /// Label sync states with distinct shapes and words, not just color
+    #[serde(default)]
+    pub colorblind: bool,
+
+    /// Draw a diverged bookmark's commits one glyph each up to this many per side;
+    /// beyond it, show a "+500 / -300" summary instead
+    #[serde(default = "default_max_fork_glyphs")]
+    pub max_fork_glyphs: usize,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -66,6 +194,113 @@ pub struct BookmarkConfig {
     pub prefix: String,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NetworkConfig {
+    /// Retries for transient failures of network commands (push, fetch, gh pr)
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+
+    /// Kill a network command that runs longer than this many seconds (0 disables)
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl NetworkConfig {
+    /// Timeout for network commands, if enabled
+    pub fn timeout(&self) -> Option<std::time::Duration> {
+        (self.timeout_secs > 0).then(|| std::time::Duration::from_secs(self.timeout_secs))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LandConfig {
+    /// Abandon empty, description-less changes in the stack after landing
+    #[serde(default = "default_true")]
+    pub prune_empty: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct WipConfig {
+    /// Append a short hash of user.email to the wip bookmark, so two people with the
+    /// same user.name don't share one
+    #[serde(default)]
+    pub disambiguate: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct HooksConfig {
+    /// Command run once before `jf push` pushes anything; a failure aborts the push
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_push: Option<HookCommand>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ToolsConfig {
+    /// jj binary to run (`JF_JJ` overrides)
+    #[serde(default = "default_jj_path")]
+    pub jj_path: String,
+
+    /// gh binary to run
+    #[serde(default = "default_gh_path")]
+    pub gh_path: String,
+
+    /// git binary to run
+    #[serde(default = "default_git_path")]
+    pub git_path: String,
+
+    /// Extra global arguments for every jj invocation (e.g., `["--config", "user.name=CI"]`)
+    #[serde(default)]
+    pub jj_args: Vec<String>,
+}
+
+impl ToolsConfig {
+    /// The binary to run for `program`; programs jf doesn't configure pass through
+    pub fn resolve<'a>(&'a self, program: &'a str) -> &'a str {
+        match program {
+            "jj" => &self.jj_path,
+            "gh" => &self.gh_path,
+            "git" => &self.git_path,
+            _ => program,
+        }
+    }
+}
+
+/// A hook command: a shell string (`"cargo test"`) or an argv list (`["cargo", "test"]`)
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum HookCommand {
+    Shell(String),
+    Argv(Vec<String>),
+}
+
+/// Where an effective config value came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    Global,
+    Local,
+}
+
+impl std::fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::Global => write!(f, "global"),
+            ConfigSource::Local => write!(f, "local"),
+        }
+    }
+}
+
+/// A single effective config value with its provenance
+#[derive(Debug, Clone)]
+pub struct ConfigEntry {
+    pub section: String,
+    pub key: String,
+    /// Value formatted as TOML
+    pub value: String,
+    pub source: ConfigSource,
+}
+
 // Default values
 fn default_remote() -> String {
     "origin".to_string()
@@ -83,6 +318,14 @@ fn default_merge_style() -> String {
     "squash".to_string()
 }
 
+fn default_max_stack() -> usize {
+    20
+}
+
+fn default_max_title_len() -> usize {
+    72
+}
+
 fn default_theme() -> String {
     "catppuccin".to_string()
 }
@@ -91,6 +334,30 @@ fn default_icons() -> String {
     "unicode".to_string()
 }
 
+fn default_max_fork_glyphs() -> usize {
+    10
+}
+
+fn default_jj_path() -> String {
+    "jj".to_string()
+}
+
+fn default_gh_path() -> String {
+    "gh".to_string()
+}
+
+fn default_git_path() -> String {
+    "git".to_string()
+}
+
+fn default_retries() -> u32 {
+    3
+}
+
+fn default_timeout_secs() -> u64 {
+    300
+}
+
 fn default_true() -> bool {
     true
 }
@@ -100,6 +367,8 @@ impl Default for RemoteConfig {
         Self {
             name: default_remote(),
             primary: default_primary(),
+            primary_resolution: PrimaryResolution::default(),
+            base_override: None,
         }
     }
 }
@@ -110,6 +379,12 @@ impl Default for GitHubConfig {
             push_style: default_push_style(),
             merge_style: default_merge_style(),
             stack_context: true,
+            require_signed: false,
+            max_stack: default_max_stack(),
+            max_title_len: default_max_title_len(),
+            squash_descriptions: false,
+            require_conventional: false,
+            repo: None,
         }
     }
 }
@@ -120,6 +395,9 @@ impl Default for DisplayConfig {
             theme: default_theme(),
             show_commit_ids: false,
             icons: default_icons(),
+            box_width: None,
+            colorblind: false,
+            max_fork_glyphs: default_max_fork_glyphs(),
         }
     }
 }
@@ -132,19 +410,53 @@ impl Default for BookmarkConfig {
     }
 }
 
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            retries: default_retries(),
+            timeout_secs: default_timeout_secs(),
+        }
+    }
+}
+
+impl Default for ToolsConfig {
+    fn default() -> Self {
+        Self {
+            jj_path: default_jj_path(),
+            gh_path: default_gh_path(),
+            git_path: default_git_path(),
+            jj_args: Vec::new(),
+        }
+    }
+}
+
+impl Default for LandConfig {
+    fn default() -> Self {
+        Self { prune_empty: true }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
+            general: GeneralConfig::default(),
             remote: RemoteConfig::default(),
             github: GitHubConfig::default(),
             display: DisplayConfig::default(),
             bookmarks: BookmarkConfig::default(),
+            network: NetworkConfig::default(),
+            land: LandConfig::default(),
+            wip: WipConfig::default(),
+            hooks: HooksConfig::default(),
+            tools: ToolsConfig::default(),
+            offline: false,
+            quiet: false,
         }
     }
 }
 
 impl Config {
-    /// Load config with hierarchy: local .jflow.toml > global ~/.jflow.toml > defaults
+    /// Load config with hierarchy: `JF_CONFIG` or local .jflow.toml > global ~/.jflow.toml > defaults
     /// Local config values override global config values.
     pub fn load() -> Result<Self> {
         // Start with defaults
@@ -170,6 +482,11 @@ impl Config {
             config = Self::merge(config, local_config);
         }
 
+        // An explicit jj binary wins over config files
+        if let Some(jj_path) = std::env::var_os("JF_JJ").filter(|p| !p.is_empty()) {
+            config.tools.jj_path = jj_path.to_string_lossy().into_owned();
+        }
+
         Ok(config)
     }
 
@@ -184,8 +501,19 @@ impl Config {
         dirs::home_dir().map(|home| home.join(".jflow.toml"))
     }
 
-    /// Find .jflow.toml in current directory or parent directories
+    /// Get the path to the local config file, if one exists
+    pub fn local_config_path() -> Option<PathBuf> {
+        Self::find_local_config_file().ok()
+    }
+
+    /// Find the local config file: `JF_CONFIG` if set, otherwise .jflow.toml in
+    /// the current directory or parent directories
     fn find_local_config_file() -> Result<PathBuf> {
+        // An explicit path replaces the directory walk (and must exist)
+        if let Some(path) = std::env::var_os("JF_CONFIG").filter(|p| !p.is_empty()) {
+            return Ok(PathBuf::from(path));
+        }
+
         let mut current_dir = std::env::current_dir()?;
 
         loop {
@@ -203,6 +531,14 @@ impl Config {
     /// Merge two configs, with `overlay` values taking precedence over `base`
     fn merge(base: Config, overlay: Config) -> Config {
         Config {
+            general: GeneralConfig {
+                default_command: if overlay.general.default_command != DefaultCommand::default() {
+                    overlay.general.default_command
+                } else {
+                    base.general.default_command
+                },
+                cache_stack: overlay.general.cache_stack,
+            },
             remote: RemoteConfig {
                 name: if overlay.remote.name != default_remote() {
                     overlay.remote.name
@@ -214,6 +550,12 @@ impl Config {
                 } else {
                     base.remote.primary
                 },
+                primary_resolution: if overlay.remote.primary_resolution != PrimaryResolution::default() {
+                    overlay.remote.primary_resolution
+                } else {
+                    base.remote.primary_resolution
+                },
+                base_override: overlay.remote.base_override.or(base.remote.base_override),
             },
             github: GitHubConfig {
                 push_style: if overlay.github.push_style != default_push_style() {
@@ -229,6 +571,20 @@ impl Config {
                 // For booleans, we can't easily detect "not set" vs "set to default"
                 // So overlay always wins for these
                 stack_context: overlay.github.stack_context,
+                require_signed: overlay.github.require_signed,
+                max_stack: if overlay.github.max_stack != default_max_stack() {
+                    overlay.github.max_stack
+                } else {
+                    base.github.max_stack
+                },
+                max_title_len: if overlay.github.max_title_len != default_max_title_len() {
+                    overlay.github.max_title_len
+                } else {
+                    base.github.max_title_len
+                },
+                squash_descriptions: overlay.github.squash_descriptions,
+                require_conventional: overlay.github.require_conventional,
+                repo: overlay.github.repo.or(base.github.repo),
             },
             display: DisplayConfig {
                 theme: if overlay.display.theme != default_theme() {
@@ -242,6 +598,13 @@ impl Config {
                 } else {
                     base.display.icons
                 },
+                box_width: overlay.display.box_width.or(base.display.box_width),
+                colorblind: overlay.display.colorblind,
+                max_fork_glyphs: if overlay.display.max_fork_glyphs != default_max_fork_glyphs() {
+                    overlay.display.max_fork_glyphs
+                } else {
+                    base.display.max_fork_glyphs
+                },
             },
             bookmarks: BookmarkConfig {
                 prefix: if !overlay.bookmarks.prefix.is_empty() {
@@ -250,14 +613,85 @@ impl Config {
                     base.bookmarks.prefix
                 },
             },
+            network: NetworkConfig {
+                retries: if overlay.network.retries != default_retries() {
+                    overlay.network.retries
+                } else {
+                    base.network.retries
+                },
+                timeout_secs: if overlay.network.timeout_secs != default_timeout_secs() {
+                    overlay.network.timeout_secs
+                } else {
+                    base.network.timeout_secs
+                },
+            },
+            land: LandConfig {
+                prune_empty: overlay.land.prune_empty,
+            },
+            wip: WipConfig {
+                disambiguate: overlay.wip.disambiguate,
+            },
+            hooks: HooksConfig {
+                pre_push: overlay.hooks.pre_push.or(base.hooks.pre_push),
+            },
+            tools: ToolsConfig {
+                jj_path: if overlay.tools.jj_path != default_jj_path() {
+                    overlay.tools.jj_path
+                } else {
+                    base.tools.jj_path
+                },
+                gh_path: if overlay.tools.gh_path != default_gh_path() {
+                    overlay.tools.gh_path
+                } else {
+                    base.tools.gh_path
+                },
+                git_path: if overlay.tools.git_path != default_git_path() {
+                    overlay.tools.git_path
+                } else {
+                    base.tools.git_path
+                },
+                jj_args: if !overlay.tools.jj_args.is_empty() {
+                    overlay.tools.jj_args
+                } else {
+                    base.tools.jj_args
+                },
+            },
+            offline: overlay.offline || base.offline,
+            quiet: overlay.quiet || base.quiet,
         }
     }
 
     /// Get the revset for querying the default stack (all local changes not on primary)
     /// Falls back gracefully if remote tracking doesn't exist
     pub fn stack_revset(&self) -> String {
-        let primary_ref = self.resolve_primary_ref();
-        format!("::@ ~ ::{}", primary_ref)
+        match &self.remote.base_override {
+            Some(base) => self.stack_revset_from(base),
+            None => self.stack_revset_from(&self.resolve_primary_ref()),
+        }
+    }
+
+    /// Get the stack revset from an already-resolved `primary_ref`; `base_override` still wins
+    pub fn stack_revset_over(&self, primary_ref: &str) -> String {
+        self.stack_revset_from(self.remote.base_override.as_deref().unwrap_or(primary_ref))
+    }
+
+    /// Get the stack revset measured from `base` instead of the primary branch
+    pub fn stack_revset_from(&self, base: &str) -> String {
+        format!("::@ ~ ::{}", base)
+    }
+
+    /// Check that `[remote] base_override` (or `--base`) resolves to a revision
+    pub fn validate_base(&self) -> Result<()> {
+        self.validate_base_with(&RealRunner::new(&self.tools))
+    }
+
+    fn validate_base_with(&self, runner: &dyn CommandRunner) -> Result<()> {
+        if let Some(base) = &self.remote.base_override {
+            if !Self::revision_exists(runner, base) {
+                anyhow::bail!("Stack base '{}' doesn't resolve to a revision", base);
+            }
+        }
+        Ok(())
     }
 
     /// Get primary branch reference (e.g., "main@origin")
@@ -272,32 +706,132 @@ impl Config {
     }
 
     /// Resolve the best available primary branch reference
-    /// Priority: primary@remote > primary (local) > root()
+    /// Priority: primary@remote > primary (local) > root(), or local before remote
+    /// with `primary_resolution = "local-first"`
     fn resolve_primary_ref(&self) -> String {
-        // Try remote tracking first (e.g., main@origin)
+        self.resolve_primary_ref_with(&RealRunner::new(&self.tools))
+    }
+
+    /// Resolve the primary branch reference, running jj through `runner`.
+    /// Offline, the remote ref is never probed.
+    fn resolve_primary_ref_with(&self, runner: &dyn CommandRunner) -> String {
+        let local_ref = self.remote.primary.clone();
         let remote_ref = format!("{}@{}", self.remote.primary, self.remote.name);
-        if Self::revision_exists(&remote_ref) {
-            return remote_ref;
-        }
 
-        // Try local primary (e.g., main)
-        if Self::revision_exists(&self.remote.primary) {
-            return self.remote.primary.clone();
+        // Remote tracking (e.g., main@origin) and local (e.g., main), in configured order
+        let candidates = match self.remote.primary_resolution {
+            PrimaryResolution::LocalFirst => [(local_ref, false), (remote_ref, true)],
+            PrimaryResolution::RemoteFirst => [(remote_ref, true), (local_ref, false)],
+        };
+
+        for (candidate, is_remote) in candidates {
+            // Offline, the remote ref is never probed
+            if is_remote && self.offline {
+                continue;
+            }
+            if Self::revision_exists(runner, &candidate) {
+                return candidate;
+            }
         }
 
         // Fall back to root
-        "root()".to_string()
+        ROOT_REF.to_string()
+    }
+
+    /// Whether the stack is measured from `root()` because neither the remote nor the
+    /// local primary branch exists, making the "stack" the entire history.
+    /// `primary_ref` is the already-resolved primary branch reference.
+    pub fn primary_missing(&self, primary_ref: &str) -> bool {
+        self.remote.base_override.is_none() && primary_ref == ROOT_REF
     }
 
     /// Check if a revision exists in the jj repo
-    fn revision_exists(rev: &str) -> bool {
-        use std::process::Command;
+    fn revision_exists(runner: &dyn CommandRunner, rev: &str) -> bool {
+        runner.run_success("jj", &["log", "-r", rev, "--limit", "1", "--no-graph", "-T", "''"])
+    }
+
+    /// Apply `--theme`/`--icons` for this invocation. An unknown name leaves the
+    /// configured value in place; the returned warnings say so.
+    pub fn override_display(&mut self, theme: Option<&str>, icons: Option<&str>) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if let Some(theme) = theme {
+            if THEME_NAMES.contains(&theme) {
+                self.display.theme = theme.to_string();
+            } else {
+                warnings.push(format!(
+                    "Unknown theme '{}' (expected one of: {}); using '{}'",
+                    theme,
+                    THEME_NAMES.join(", "),
+                    self.display.theme
+                ));
+            }
+        }
+        if let Some(icons) = icons {
+            if ICON_SET_NAMES.contains(&icons) {
+                self.display.icons = icons.to_string();
+            } else {
+                warnings.push(format!(
+                    "Unknown icon set '{}' (expected one of: {}); using '{}'",
+                    icons,
+                    ICON_SET_NAMES.join(", "),
+                    self.display.icons
+                ));
+            }
+        }
+        warnings
+    }
+
+    /// Fail with a clear message in offline mode, for commands that need the network
+    pub fn require_online(&self, command: &str) -> Result<()> {
+        if self.offline {
+            anyhow::bail!("`jf {}` needs the network and can't run offline (--offline / JF_OFFLINE)", command);
+        }
+        Ok(())
+    }
 
-        Command::new("jj")
-            .args(["log", "-r", rev, "--limit", "1", "--no-graph", "-T", "''"])
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false)
+    /// List every effective value with the file it came from.
+    ///
+    /// A value is attributed to a file only if that file sets the key *and* the
+    /// effective value matches it, so merge quirks (e.g., a local file resetting a
+    /// global boolean to its default) show up as "default".
+    pub fn sources(&self, global_toml: Option<&str>, local_toml: Option<&str>) -> Vec<ConfigEntry> {
+        let effective = match toml::Value::try_from(self) {
+            Ok(toml::Value::Table(table)) => table,
+            _ => return Vec::new(),
+        };
+        let global = global_toml.and_then(|c| c.parse::<toml::Table>().ok());
+        let local = local_toml.and_then(|c| c.parse::<toml::Table>().ok());
+
+        // Look up an explicitly set key in a raw config table
+        let lookup = |table: &Option<toml::Table>, section: &str, key: &str| -> Option<toml::Value> {
+            let section = table.as_ref()?.get(section)?.as_table()?;
+            section
+                .get(key)
+                // "trunk" is an alias for "primary"
+                .or_else(|| if key == "primary" { section.get("trunk") } else { None })
+                .cloned()
+        };
+
+        let mut entries = Vec::new();
+        for (section, values) in &effective {
+            let Some(values) = values.as_table() else { continue };
+            for (key, value) in values {
+                let source = if lookup(&local, section, key).as_ref() == Some(value) {
+                    ConfigSource::Local
+                } else if lookup(&global, section, key).as_ref() == Some(value) {
+                    ConfigSource::Global
+                } else {
+                    ConfigSource::Default
+                };
+                entries.push(ConfigEntry {
+                    section: section.clone(),
+                    key: key.clone(),
+                    value: value.to_string(),
+                    source,
+                });
+            }
+        }
+        entries
     }
 
     /// Parse config from a TOML string (for testing)
@@ -318,10 +852,19 @@ mod tests {
         assert_eq!(config.github.push_style, "squash");
         assert_eq!(config.github.merge_style, "squash");
         assert!(config.github.stack_context);
+        assert!(!config.github.require_signed);
+        assert!(!config.github.require_conventional);
+        assert_eq!(config.github.max_stack, 20);
+        assert_eq!(config.github.max_title_len, 72);
         assert_eq!(config.display.theme, "catppuccin");
         assert_eq!(config.display.icons, "unicode");
         assert!(!config.display.show_commit_ids);
+        assert_eq!(config.display.box_width, None);
+        assert_eq!(config.display.max_fork_glyphs, 10);
         assert_eq!(config.bookmarks.prefix, "");
+        assert_eq!(config.network.retries, 3);
+        assert!(config.land.prune_empty);
+        assert_eq!(config.hooks.pre_push, None);
     }
 
     #[test]
@@ -359,14 +902,28 @@ primary = "develop"
 push_style = "append"
 merge_style = "rebase"
 stack_context = false
+require_signed = true
+require_conventional = true
+max_stack = 50
 
 [display]
 theme = "nord"
 icons = "ascii"
 show_commit_ids = true
+box_width = 100
 
 [bookmarks]
 prefix = "jf/"
+
+[network]
+retries = 5
+timeout_secs = 60
+
+[land]
+prune_empty = false
+
+[hooks]
+pre_push = "cargo test"
 "#;
         let config = Config::from_toml(toml).unwrap();
         assert_eq!(config.remote.name, "upstream");
@@ -374,10 +931,151 @@ prefix = "jf/"
         assert_eq!(config.github.push_style, "append");
         assert_eq!(config.github.merge_style, "rebase");
         assert!(!config.github.stack_context);
+        assert!(config.github.require_signed);
+        assert!(config.github.require_conventional);
+        assert_eq!(config.github.max_stack, 50);
         assert_eq!(config.display.theme, "nord");
         assert_eq!(config.display.icons, "ascii");
         assert!(config.display.show_commit_ids);
+        assert_eq!(config.display.box_width, Some(100));
         assert_eq!(config.bookmarks.prefix, "jf/");
+        assert_eq!(config.network.retries, 5);
+        assert_eq!(config.network.timeout(), Some(std::time::Duration::from_secs(60)));
+        assert!(!config.land.prune_empty);
+        assert_eq!(config.hooks.pre_push, Some(HookCommand::Shell("cargo test".to_string())));
+    }
+
+    #[test]
+    fn test_parse_pre_push_argv() {
+        let config = Config::from_toml("[hooks]\npre_push = [\"cargo\", \"clippy\", \"--\", \"-D\", \"warnings\"]").unwrap();
+        assert_eq!(
+            config.hooks.pre_push,
+            Some(HookCommand::Argv(
+                ["cargo", "clippy", "--", "-D", "warnings"].iter().map(|s| s.to_string()).collect()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_merge_pre_push_hook() {
+        let base = Config::from_toml("[hooks]\npre_push = \"cargo test\"").unwrap();
+        let merged = Config::merge(base.clone(), Config::default());
+        assert_eq!(merged.hooks.pre_push, Some(HookCommand::Shell("cargo test".to_string())));
+
+        let overlay = Config::from_toml("[hooks]\npre_push = \"make check\"").unwrap();
+        let merged = Config::merge(base, overlay);
+        assert_eq!(merged.hooks.pre_push, Some(HookCommand::Shell("make check".to_string())));
+    }
+
+    #[test]
+    fn test_override_display_beats_config() {
+        let mut config = Config::from_toml("[display]\ntheme = \"nord\"\nicons = \"nerdfont\"").unwrap();
+        let warnings = config.override_display(Some("dracula"), Some("ascii"));
+        assert!(warnings.is_empty());
+        assert_eq!(config.display.theme, "dracula");
+        assert_eq!(config.display.icons, "ascii");
+    }
+
+    #[test]
+    fn test_override_display_unknown_name_warns_and_keeps_config() {
+        let mut config = Config::from_toml("[display]\ntheme = \"nord\"").unwrap();
+        let warnings = config.override_display(Some("neon"), Some("emoji"));
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("Unknown theme 'neon'"));
+        assert!(warnings[0].ends_with("using 'nord'"));
+        assert!(warnings[1].contains("Unknown icon set 'emoji'"));
+        assert_eq!(config.display.theme, "nord");
+        assert_eq!(config.display.icons, "unicode");
+
+        // No flags, no change
+        assert!(config.override_display(None, None).is_empty());
+        assert_eq!(config.display.theme, "nord");
+    }
+
+    #[test]
+    fn test_wip_disambiguate() {
+        assert!(!Config::default().wip.disambiguate);
+        let config = Config::from_toml("[wip]\ndisambiguate = true").unwrap();
+        assert!(config.wip.disambiguate);
+        assert!(Config::merge(Config::default(), config).wip.disambiguate);
+    }
+
+    #[test]
+    fn test_merge_network_retries() {
+        let base = Config::from_toml("[network]\nretries = 5").unwrap();
+        let overlay = Config::from_toml("[network]\nretries = 0").unwrap();
+        assert_eq!(Config::merge(base.clone(), overlay).network.retries, 0);
+        assert_eq!(Config::merge(base, Config::default()).network.retries, 5);
+    }
+
+    #[test]
+    fn test_merge_github_repo() {
+        let global = Config::from_toml("[github]\nrepo = \"acme/widgets\"").unwrap();
+        let local = Config::from_toml("[github]\nrepo = \"me/widgets\"").unwrap();
+        assert_eq!(Config::merge(global.clone(), local).github.repo.as_deref(), Some("me/widgets"));
+        assert_eq!(Config::merge(global, Config::default()).github.repo.as_deref(), Some("acme/widgets"));
+    }
+
+    #[test]
+    fn test_network_timeout() {
+        assert_eq!(Config::default().network.timeout(), Some(std::time::Duration::from_secs(300)));
+
+        let disabled = Config::from_toml("[network]\ntimeout_secs = 0").unwrap();
+        assert_eq!(disabled.network.timeout(), None);
+    }
+
+    #[test]
+    fn test_sources_local_override() {
+        let global = "[remote]\nname = \"upstream\"\nprimary = \"develop\"";
+        let local = "[remote]\nprimary = \"master\"";
+        let config = Config::merge(
+            Config::merge(Config::default(), Config::from_toml(global).unwrap()),
+            Config::from_toml(local).unwrap(),
+        );
+
+        let entries = config.sources(Some(global), Some(local));
+        let source = |section: &str, key: &str| {
+            entries
+                .iter()
+                .find(|e| e.section == section && e.key == key)
+                .map(|e| e.source)
+        };
+
+        assert_eq!(source("remote", "primary"), Some(ConfigSource::Local));
+        assert_eq!(source("remote", "name"), Some(ConfigSource::Global));
+        assert_eq!(source("github", "push_style"), Some(ConfigSource::Default));
+    }
+
+    #[test]
+    fn test_sources_trunk_alias_attributed() {
+        let local = "[remote]\ntrunk = \"master\"";
+        let config = Config::from_toml(local).unwrap();
+        let entries = config.sources(None, Some(local));
+        let entry = entries
+            .iter()
+            .find(|e| e.section == "remote" && e.key == "primary")
+            .unwrap();
+        assert_eq!(entry.source, ConfigSource::Local);
+        assert_eq!(entry.value, "\"master\"");
+    }
+
+    #[test]
+    fn test_sources_boolean_reset_by_local_is_default() {
+        // Global enables commit ids, but a local file without the key resets it
+        let global = "[display]\nshow_commit_ids = true";
+        let local = "[remote]\nprimary = \"main\"";
+        let config = Config::merge(
+            Config::merge(Config::default(), Config::from_toml(global).unwrap()),
+            Config::from_toml(local).unwrap(),
+        );
+
+        let entries = config.sources(Some(global), Some(local));
+        let entry = entries
+            .iter()
+            .find(|e| e.section == "display" && e.key == "show_commit_ids")
+            .unwrap();
+        assert_eq!(entry.value, "false");
+        assert_eq!(entry.source, ConfigSource::Default);
     }
 
     #[test]
@@ -457,6 +1155,197 @@ name = "origin"
         assert_eq!(config.remote.primary, "main");
     }
 
+    #[test]
+    fn test_load_from_jf_config_env() {
+        use std::fs;
+        use tempfile::tempdir;
+
+        let _guard = DIR_MUTEX.lock().unwrap();
+
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("ci.toml");
+        fs::write(&config_path, "[remote]\nname = \"upstream\"\nprimary = \"develop\"").unwrap();
+
+        std::env::set_var("JF_CONFIG", &config_path);
+        let path = Config::local_config_path();
+        let result = Config::load();
+        std::env::remove_var("JF_CONFIG");
+
+        assert_eq!(path, Some(config_path));
+        let config = result.unwrap();
+        assert_eq!(config.remote.name, "upstream");
+        assert_eq!(config.remote.primary, "develop");
+    }
+
+    #[test]
+    fn test_tools_resolve() {
+        let tools = ToolsConfig::default();
+        assert_eq!(tools.resolve("jj"), "jj");
+        assert_eq!(tools.resolve("gh"), "gh");
+
+        let config = Config::from_toml("[tools]\njj_path = \"/opt/jj/bin/jj\"\ngit_path = \"/usr/local/bin/git\"").unwrap();
+        assert_eq!(config.tools.resolve("jj"), "/opt/jj/bin/jj");
+        assert_eq!(config.tools.resolve("gh"), "gh");
+        assert_eq!(config.tools.resolve("git"), "/usr/local/bin/git");
+        assert_eq!(config.tools.resolve("sh"), "sh");
+    }
+
+    #[test]
+    fn test_default_command() {
+        assert_eq!(Config::default().general.default_command, DefaultCommand::Status);
+
+        let config = Config::from_toml("[general]\ndefault_command = \"diff\"").unwrap();
+        assert_eq!(config.general.default_command, DefaultCommand::Diff);
+    }
+
+    #[test]
+    fn test_default_command_rejects_destructive_commands() {
+        for command in ["sync", "push", "land", "nonsense"] {
+            let toml = format!("[general]\ndefault_command = \"{}\"", command);
+            assert!(Config::from_toml(&toml).is_err(), "{} should be rejected", command);
+        }
+    }
+
+    #[test]
+    fn test_tools_jj_args() {
+        assert!(Config::default().tools.jj_args.is_empty());
+
+        let config = Config::from_toml("[tools]\njj_args = [\"--config\", \"user.name=CI\"]").unwrap();
+        assert_eq!(config.tools.jj_args, ["--config", "user.name=CI"]);
+    }
+
+    #[test]
+    fn test_jf_jj_env_overrides_jj_path() {
+        use std::fs;
+        use tempfile::tempdir;
+
+        let _guard = DIR_MUTEX.lock().unwrap();
+
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("ci.toml");
+        fs::write(&config_path, "[tools]\njj_path = \"/opt/jj/bin/jj\"").unwrap();
+
+        std::env::set_var("JF_CONFIG", &config_path);
+        let from_file = Config::load();
+        std::env::set_var("JF_JJ", "/tmp/jj-dev");
+        let from_env = Config::load();
+        std::env::remove_var("JF_JJ");
+        std::env::remove_var("JF_CONFIG");
+
+        assert_eq!(from_file.unwrap().tools.jj_path, "/opt/jj/bin/jj");
+        assert_eq!(from_env.unwrap().tools.jj_path, "/tmp/jj-dev");
+    }
+
+    #[test]
+    fn test_load_from_missing_jf_config_errors() {
+        use tempfile::tempdir;
+
+        let _guard = DIR_MUTEX.lock().unwrap();
+
+        let dir = tempdir().unwrap();
+        std::env::set_var("JF_CONFIG", dir.path().join("missing.toml"));
+        let result = Config::load();
+        std::env::remove_var("JF_CONFIG");
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_offline_resolves_local_primary_without_remote_probe() {
+        use crate::jj::runner::mock::MockRunner;
+
+        let mock = || {
+            let runner = MockRunner::new();
+            runner.mock_response("jj log -r main@origin --limit 1 --no-graph -T ''", "");
+            runner.mock_response("jj log -r main --limit 1 --no-graph -T ''", "");
+            runner
+        };
+
+        assert_eq!(Config::default().resolve_primary_ref_with(&mock()), "main@origin");
+
+        let runner = mock();
+        let offline = Config {
+            offline: true,
+            ..Config::default()
+        };
+        assert_eq!(offline.resolve_primary_ref_with(&runner), "main");
+        assert!(!runner.was_called("jj", &["log", "-r", "main@origin", "--limit", "1", "--no-graph", "-T", "''"]));
+    }
+
+    /// Runner where only the given revisions exist
+    fn runner_with_revisions(existing: &[&str]) -> crate::jj::runner::mock::MockRunner {
+        let runner = crate::jj::runner::mock::MockRunner::new();
+        for rev in ["main", "main@origin"] {
+            let key = format!("jj log -r {} --limit 1 --no-graph -T ''", rev);
+            if existing.contains(&rev) {
+                runner.mock_response(&key, "");
+            } else {
+                runner.mock_error(&key, "Revision doesn't exist");
+            }
+        }
+        runner
+    }
+
+    #[test]
+    fn test_primary_resolution_remote_first() {
+        let config = Config::default();
+        assert_eq!(config.remote.primary_resolution, PrimaryResolution::RemoteFirst);
+
+        let resolve = |existing: &[&str]| config.resolve_primary_ref_with(&runner_with_revisions(existing));
+        assert_eq!(resolve(&["main", "main@origin"]), "main@origin");
+        assert_eq!(resolve(&["main@origin"]), "main@origin");
+        assert_eq!(resolve(&["main"]), "main");
+        assert_eq!(resolve(&[]), "root()");
+    }
+
+    #[test]
+    fn test_primary_missing() {
+        let config = Config::default();
+        let missing = |existing: &[&str]| {
+            config.primary_missing(&config.resolve_primary_ref_with(&runner_with_revisions(existing)))
+        };
+        assert!(missing(&[]));
+        assert!(!missing(&["main"]));
+        assert!(!missing(&["main@origin"]));
+
+        // An explicit base is never the root fallback
+        let mut config = Config::default();
+        config.remote.base_override = Some("integration".to_string());
+        assert!(!config.primary_missing("root()"));
+
+        assert_eq!(config.stack_revset_over("root()"), "::@ ~ ::integration");
+        assert_eq!(Config::default().stack_revset_over("main"), "::@ ~ ::main");
+    }
+
+    #[test]
+    fn test_primary_resolution_local_first() {
+        let config = Config::from_toml("[remote]\nprimary_resolution = \"local-first\"").unwrap();
+
+        let resolve = |existing: &[&str]| config.resolve_primary_ref_with(&runner_with_revisions(existing));
+        assert_eq!(resolve(&["main", "main@origin"]), "main");
+        assert_eq!(resolve(&["main@origin"]), "main@origin");
+        assert_eq!(resolve(&["main"]), "main");
+        assert_eq!(resolve(&[]), "root()");
+    }
+
+    #[test]
+    fn test_primary_resolution_rejects_unknown_values() {
+        assert!(Config::from_toml("[remote]\nprimary_resolution = \"local_first\"").is_err());
+        assert!(Config::from_toml("[remote]\nprimary_resolution = \"local\"").is_err());
+    }
+
+    #[test]
+    fn test_require_online() {
+        assert!(Config::default().require_online("push").is_ok());
+
+        let offline = Config {
+            offline: true,
+            ..Config::default()
+        };
+        let err = offline.require_online("push").unwrap_err();
+        assert!(err.to_string().contains("jf push"));
+    }
+
     #[test]
     fn test_load_or_default_when_missing() {
         use tempfile::tempdir;
@@ -660,6 +1549,47 @@ prefix = "jf\\test"
         assert_eq!(config.bookmarks.prefix, "jf\\test");
     }
 
+    #[test]
+    fn test_stack_revset_from_base() {
+        let config = Config::default();
+        assert_eq!(config.stack_revset_from("main@origin"), "::@ ~ ::main@origin");
+    }
+
+    #[test]
+    fn test_stack_revset_with_base_override() {
+        let mut config = Config::default();
+        config.remote.base_override = Some("integration@origin".to_string());
+        assert_eq!(config.stack_revset(), "::@ ~ ::integration@origin");
+    }
+
+    #[test]
+    fn test_base_override_from_toml() {
+        let config = Config::from_toml("[remote]\nbase_override = \"integration\"").unwrap();
+        assert_eq!(config.remote.base_override.as_deref(), Some("integration"));
+
+        let merged = Config::merge(config, Config::default());
+        assert_eq!(merged.remote.base_override.as_deref(), Some("integration"));
+    }
+
+    #[test]
+    fn test_validate_base() {
+        use crate::jj::runner::mock::MockRunner;
+
+        let runner = MockRunner::new();
+        runner.mock_response("jj log -r integration --limit 1 --no-graph -T ''", "");
+        runner.mock_error("jj log -r missing --limit 1 --no-graph -T ''", "Revision `missing` doesn't exist");
+
+        assert!(Config::default().validate_base_with(&runner).is_ok());
+
+        let mut config = Config::default();
+        config.remote.base_override = Some("integration".to_string());
+        assert!(config.validate_base_with(&runner).is_ok());
+
+        config.remote.base_override = Some("missing".to_string());
+        let err = config.validate_base_with(&runner).unwrap_err();
+        assert!(err.to_string().contains("missing"));
+    }
+
     #[test]
     fn test_stack_revset_format() {
         let config = Config::default();
@@ -0,0 +1,180 @@
+//! Cache of the last stack query, keyed by jj's current operation. Any change to the
+//! repo (a commit, a fetch, an edited file snapshotted) creates a new operation, so a
+//! matching operation id means the stack can't have changed.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::query::get_stack_result;
+use super::runner::{CommandRunner, RealRunner};
+use super::types::{ChangeWithStatus, QueryResult};
+use crate::state;
+
+/// File under `.jflow/` holding the cached stack
+const CACHE_FILE: &str = "stack-cache.json";
+
+/// The last stack query and what it was computed for
+#[derive(Debug, Deserialize, Serialize)]
+struct StackCache {
+    op_id: String,
+    revset: String,
+    remote: String,
+    changes: Vec<ChangeWithStatus>,
+    skipped: usize,
+}
+
+/// `get_stack_result`, reusing the last result while jj's operation is unchanged
+pub fn get_stack_result_cached(
+    runner: &RealRunner,
+    revset: &str,
+    remote_name: &str,
+) -> Result<QueryResult<ChangeWithStatus>> {
+    let Some(path) = cache_path() else {
+        return get_stack_result(runner, revset, remote_name);
+    };
+    cached_stack_with(runner, &path, revset, remote_name, |_| get_stack_result(runner, revset, remote_name))
+}
+
+fn cache_path() -> Option<PathBuf> {
+    let root = state::find_repo_root(&std::env::current_dir().ok()?)?;
+    Some(state::state_dir(&root).ok()?.join(CACHE_FILE))
+}
+
+/// Id of jj's current operation (None if jj can't tell us)
+fn current_op_id(runner: &dyn CommandRunner) -> Option<String> {
+    runner
+        .run("jj", &["op", "log", "--no-graph", "--limit", "1", "-T", "id"])
+        .ok()
+        .map(|output| output.trim().to_string())
+        .filter(|id| !id.is_empty())
+}
+
+/// Return the cached stack at `path` if it matches the current operation, revset,
+/// and remote; otherwise `compute` it and cache the result
+fn cached_stack_with(
+    runner: &dyn CommandRunner,
+    path: &Path,
+    revset: &str,
+    remote_name: &str,
+    compute: impl FnOnce(&dyn CommandRunner) -> Result<QueryResult<ChangeWithStatus>>,
+) -> Result<QueryResult<ChangeWithStatus>> {
+    let Some(op_id) = current_op_id(runner) else {
+        return compute(runner);
+    };
+
+    let cached = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<StackCache>(&contents).ok());
+    if let Some(cache) = cached {
+        if cache.op_id == op_id && cache.revset == revset && cache.remote == remote_name {
+            return Ok(QueryResult {
+                changes: cache.changes,
+                skipped: cache.skipped,
+            });
+        }
+    }
+
+    let result = compute(runner)?;
+    let cache = StackCache {
+        op_id,
+        revset: revset.to_string(),
+        remote: remote_name.to_string(),
+        changes: result.changes.clone(),
+        skipped: result.skipped,
+    };
+    // Best effort: a cache that can't be written only makes the next run slower
+    if let Ok(contents) = serde_json::to_string(&cache) {
+        let _ = std::fs::write(path, contents);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jj::runner::mock::MockRunner;
+    use crate::jj::types::{BookmarkSyncState, Change};
+    use tempfile::tempdir;
+
+    const OP_LOG: &str = "jj op log --no-graph --limit 1 -T id";
+    const STACK_LOG: &str = "jj log -r ::@ ~ ::main@origin";
+
+    /// Stand-in for `get_stack_result`: one `jj log` through the runner
+    fn query(runner: &dyn CommandRunner) -> Result<QueryResult<ChangeWithStatus>> {
+        let change_id = runner.run("jj", &["log", "-r", "::@ ~ ::main@origin"])?;
+        Ok(QueryResult {
+            changes: vec![ChangeWithStatus {
+                change: Change {
+                    change_id: change_id.trim().to_string(),
+                    commit_id: "def456".to_string(),
+                    description: "Add feature".to_string(),
+                    author: Default::default(),
+                    bookmarks: vec!["feature".to_string()],
+                    timestamp: String::new(),
+                    is_empty: false,
+                    committer: Default::default(),
+                    has_conflict: false,
+                    parents: Vec::new(),
+                },
+                bookmark: Some("feature".to_string()),
+                is_working: true,
+                has_remote: true,
+                sync_state: BookmarkSyncState::Ahead { count: 2 },
+                is_empty: false,
+                is_immutable: false,
+                additions: 0,
+                deletions: 0,
+                pr_state: None,
+            }],
+            skipped: 0,
+        })
+    }
+
+    fn stack_logs(runner: &MockRunner) -> usize {
+        runner.get_calls().iter().filter(|(_, args)| args[0] == "log").count()
+    }
+
+    #[test]
+    fn test_unchanged_op_hits_cache() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(CACHE_FILE);
+        let runner = MockRunner::new();
+        runner.mock_response(OP_LOG, "op1\n");
+        runner.mock_response(STACK_LOG, "qpvuntsm\n");
+
+        let first = cached_stack_with(&runner, &path, "::@ ~ ::main@origin", "origin", query).unwrap();
+        assert_eq!(stack_logs(&runner), 1);
+
+        let second = cached_stack_with(&runner, &path, "::@ ~ ::main@origin", "origin", query).unwrap();
+        assert_eq!(stack_logs(&runner), 1, "second call should not run jj log");
+        assert_eq!(second.changes[0].change.change_id, first.changes[0].change.change_id);
+        assert!(matches!(second.changes[0].sync_state, BookmarkSyncState::Ahead { count: 2 }));
+    }
+
+    #[test]
+    fn test_new_op_invalidates_cache() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(CACHE_FILE);
+        let runner = MockRunner::new();
+        runner.mock_sequence(OP_LOG, &[Ok("op1\n"), Ok("op2\n")]);
+        runner.mock_response(STACK_LOG, "qpvuntsm\n");
+
+        cached_stack_with(&runner, &path, "::@ ~ ::main@origin", "origin", query).unwrap();
+        cached_stack_with(&runner, &path, "::@ ~ ::main@origin", "origin", query).unwrap();
+        assert_eq!(stack_logs(&runner), 2);
+    }
+
+    #[test]
+    fn test_other_remote_misses_cache() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(CACHE_FILE);
+        let runner = MockRunner::new();
+        runner.mock_response(OP_LOG, "op1\n");
+        runner.mock_response(STACK_LOG, "qpvuntsm\n");
+
+        cached_stack_with(&runner, &path, "::@ ~ ::main@origin", "origin", query).unwrap();
+        cached_stack_with(&runner, &path, "::@ ~ ::main@origin", "upstream", query).unwrap();
+        assert_eq!(stack_logs(&runner), 2);
+    }
+}
@@ -1,13 +1,31 @@
+pub mod cache;
 pub mod query;
+pub mod refname;
 pub mod runner;
 pub mod types;
 
 pub use query::{
+    abandon_empty_in,
+    add_diff_stats,
+    add_full_descriptions,
     check_jj_available,
-    create_bookmark,
+    create_bookmark_with,
+    find_conflicts_in,
+    find_empty_in,
+    get_full_description,
     get_stack,
+    get_stack_result,
+    get_user_email,
+    jj_version,
+    old_jj_warning,
+    query_local_bookmark_names,
+    query_remote_presence_with,
+    query_sync_state_with,
+    query_tracked_bookmarks_with,
     query_changes,
     run_jj,
 };
-pub use runner::{CommandRunner, RealRunner};
-pub use types::Change;
+pub use cache::get_stack_result_cached;
+pub use refname::validate_bookmark_name;
+pub use runner::{enable_timings, print_timings_summary, CommandRunner, RealRunner, RetryRunner};
+pub use types::{Change, QueryResult};
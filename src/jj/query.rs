@@ -1,7 +1,12 @@
 use anyhow::{Context, Result};
-use std::process::Command;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{BufRead, BufReader, Lines, Read};
+use std::process::{Child, ChildStdout, Stdio};
+use std::sync::OnceLock;
 
-use super::types::{BookmarkSyncState, Change, ChangeWithStatus};
+use super::refname::validate_bookmark_name;
+use super::runner::{CommandRunner, RealRunner, TIMINGS};
+use super::types::{BookmarkSyncState, Change, ChangeWithStatus, QueryResult, TrackedBookmark};
 
 /// A bookmark from jj with sync information
 struct Bookmark {
@@ -13,10 +18,9 @@ struct Bookmark {
 }
 
 /// Execute jj command and return output
-pub fn run_jj(args: &[&str]) -> Result<String> {
-    let output = Command::new("jj")
-        .args(args)
-        .output()
+pub fn run_jj(runner: &RealRunner, args: &[&str]) -> Result<String> {
+    let output = TIMINGS
+        .time("jj", args, || runner.command("jj").args(args).output())
         .context("Failed to execute jj command. Is jj installed?")?;
 
     if !output.status.success() {
@@ -27,37 +31,197 @@ pub fn run_jj(args: &[&str]) -> Result<String> {
     Ok(String::from_utf8(output.stdout)?)
 }
 
-/// Query changes using a revset
-pub fn query_changes(revset: &str) -> Result<Vec<Change>> {
+/// How a template field's value is written into the JSON line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldKind {
+    /// Quoted JSON string
+    Str,
+    /// Emitted as-is (the expression produces its own JSON: bool, object, array)
+    Raw,
+}
+
+/// A field emitted by the `query_changes` template, keyed by its `Change` field name
+#[derive(Debug, Clone, Copy)]
+pub struct TemplateField {
+    key: &'static str,
+    expr: &'static str,
+    kind: FieldKind,
+}
+
+/// Fields every query emits (output is unchanged from the original hand-written template)
+const DEFAULT_FIELDS: &[TemplateField] = &[
+    TemplateField { key: "change_id", expr: "change_id", kind: FieldKind::Str },
+    TemplateField { key: "commit_id", expr: "commit_id", kind: FieldKind::Str },
+    TemplateField { key: "description", expr: "description.first_line()", kind: FieldKind::Str },
+    TemplateField {
+        key: "author",
+        expr: r#"concat("{\"name\":\"", author.name(), "\",\"email\":\"", author.email(), "\"}")"#,
+        kind: FieldKind::Raw,
+    },
+    TemplateField {
+        key: "bookmarks",
+        expr: r#"concat("[", bookmarks.map(|b| concat("\"", b.name(), "\"")).join(","), "]")"#,
+        kind: FieldKind::Raw,
+    },
+    TemplateField {
+        key: "timestamp",
+        expr: r#"author.timestamp().utc().format("%Y-%m-%dT%H:%M:%SZ")"#,
+        kind: FieldKind::Str,
+    },
+];
+
+/// Optional fields callers can request by key
+const EXTRA_FIELDS: &[TemplateField] = &[
+    TemplateField { key: "is_empty", expr: r#"if(empty, "true", "false")"#, kind: FieldKind::Raw },
+    TemplateField { key: "has_conflict", expr: r#"if(conflict, "true", "false")"#, kind: FieldKind::Raw },
+    TemplateField {
+        key: "parents",
+        expr: r#"concat("[", parents.map(|p| concat("\"", p.change_id(), "\"")).join(","), "]")"#,
+        kind: FieldKind::Raw,
+    },
+    TemplateField {
+        key: "committer",
+        expr: r#"concat("{\"name\":\"", committer.name(), "\",\"email\":\"", committer.email(), "\"}")"#,
+        kind: FieldKind::Raw,
+    },
+];
+
+/// Look up an optional template field by its `Change` field name
+pub fn extra_field(key: &str) -> Option<TemplateField> {
+    EXTRA_FIELDS.iter().find(|f| f.key == key).copied()
+}
+
+/// Build a jj template that prints one JSON object per line with the given fields
+fn build_template(fields: &[TemplateField]) -> String {
     // jj template syntax uses concat() and string literals
-    let template = r#"concat(
-        "{\"change_id\":\"", change_id, "\",",
-        "\"commit_id\":\"", commit_id, "\",",
-        "\"description\":\"", description.first_line(), "\",",
-        "\"author\":{\"name\":\"", author.name(), "\",\"email\":\"", author.email(), "\"},",
-        "\"bookmarks\":[", bookmarks.map(|b| concat("\"", b.name(), "\"")).join(","), "]",
-        "}\n"
-    )"#;
-
-    let output = run_jj(&["log", "-r", revset, "-T", template, "--no-graph"])?;
-
-    // Parse each line as JSON
-    let mut changes = Vec::new();
-    for line in output.lines() {
-        if line.trim().is_empty() {
-            continue;
+    let parts: Vec<String> = fields
+        .iter()
+        .map(|f| match f.kind {
+            FieldKind::Str => format!(r#""\"{}\":\"", {}, "\"""#, f.key, f.expr),
+            FieldKind::Raw => format!(r#""\"{}\":", {}"#, f.key, f.expr),
+        })
+        .collect();
+    format!(r#"concat("{{", {}, "}}\n")"#, parts.join(r#", ",", "#))
+}
+
+/// Query changes using a revset, skipping (and warning about) lines that fail to parse
+pub fn query_changes(runner: &RealRunner, revset: &str) -> Result<Vec<Change>> {
+    Ok(query_changes_result(runner, revset)?.changes)
+}
+
+/// Query changes using a revset, reporting how many lines failed to parse
+pub fn query_changes_result(runner: &RealRunner, revset: &str) -> Result<QueryResult> {
+    parse_changes_result(query_changes_iter(runner, revset)?)
+}
+
+/// Query changes with additional template fields (e.g., `extra_field("is_empty")`)
+pub fn query_changes_with(runner: &RealRunner, revset: &str, extra: &[TemplateField]) -> Result<QueryResult> {
+    parse_changes_result(spawn_changes(runner, revset, extra)?)
+}
+
+/// Stream changes matching a revset as `jj log` prints them, without waiting for
+/// the whole log. Lines that fail to parse are skipped with a warning.
+pub fn query_changes_iter(runner: &RealRunner, revset: &str) -> Result<ChangeIter<BufReader<ChildStdout>>> {
+    spawn_changes(runner, revset, &[])
+}
+
+/// Start `jj log` for `revset` and iterate over its output as it arrives
+fn spawn_changes(
+    runner: &RealRunner,
+    revset: &str,
+    extra: &[TemplateField],
+) -> Result<ChangeIter<BufReader<ChildStdout>>> {
+    let fields: Vec<TemplateField> = DEFAULT_FIELDS.iter().chain(extra).copied().collect();
+    let template = build_template(&fields);
+
+    let mut child = runner
+        .command("jj")
+        .args(["log", "-r", revset, "-T", &template, "--no-graph"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to execute jj command. Is jj installed?")?;
+    let stdout = child.stdout.take().context("jj log stdout was not captured")?;
+
+    let mut iter = ChangeIter::new(BufReader::new(stdout));
+    iter.child = Some(child);
+    Ok(iter)
+}
+
+/// Changes parsed one JSON line at a time from `jj log` output, as lines are read.
+/// When backed by a running `jj`, its exit status is checked once output ends.
+pub struct ChangeIter<R> {
+    lines: Lines<R>,
+    child: Option<Child>,
+    skipped: usize,
+}
+
+impl<R: BufRead> ChangeIter<R> {
+    /// Parse changes from any line reader (a pipe, a file, a byte slice)
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            child: None,
+            skipped: 0,
         }
+    }
 
-        match serde_json::from_str::<Change>(line) {
-            Ok(change) => changes.push(change),
-            Err(e) => {
-                eprintln!("Warning: Failed to parse change: {}", e);
-                eprintln!("Line: {}", line);
+    /// Lines skipped so far because they failed to parse
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Wait for jj (if any) and fail if it exited unsuccessfully
+    fn finish(&mut self) -> Result<()> {
+        let Some(mut child) = self.child.take() else {
+            return Ok(());
+        };
+        let status = child.wait()?;
+        if !status.success() {
+            let mut stderr = String::new();
+            if let Some(mut pipe) = child.stderr.take() {
+                pipe.read_to_string(&mut stderr)?;
+            }
+            anyhow::bail!("jj command failed: {}", stderr);
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Iterator for ChangeIter<R> {
+    type Item = Result<Change>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(e)) => return Some(Err(e.into())),
+                None => return self.finish().err().map(Err),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str::<Change>(&line) {
+                Ok(change) => return Some(Ok(change)),
+                Err(e) => {
+                    eprintln!("Warning: Failed to parse change: {}", e);
+                    eprintln!("Line: {}", line);
+                    self.skipped += 1;
+                }
             }
         }
     }
+}
 
-    Ok(changes)
+impl<R> Drop for ChangeIter<R> {
+    /// Stop jj if the caller quit reading early, so it doesn't linger
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
 }
 
 /// Raw bookmark entry from jj
@@ -66,26 +230,60 @@ pub struct BookmarkEntry {
     name: String,
     remote: Option<String>,
     change_id: Option<String>,
+    #[serde(default)]
     synced: bool,
     ahead: Option<usize>,
     behind: Option<usize>,
+    /// True if the bookmark has conflicting targets (change_id is null)
+    #[serde(default)]
+    conflict: bool,
 }
 
-/// Get all bookmarks with sync state
-fn query_bookmarks(remote_name: &str) -> Result<Vec<Bookmark>> {
+/// Template for `jj bookmark list --all`, one JSON object per line.
+/// Use self.tracking_present() to check if this is a tracked remote ref before accessing tracking counts
+pub(crate) const BOOKMARK_TEMPLATE: &str = r#"concat(
+    "{\"name\":\"", name, "\",",
+    "\"remote\":", if(remote, concat("\"", remote, "\""), "null"), ",",
+    "\"change_id\":", if(normal_target, concat("\"", normal_target.change_id().short(), "\""), "null"), ",",
+    "\"synced\":", self.synced(), ",",
+    "\"ahead\":", if(self.tracking_present(), tracking_ahead_count.exact(), "null"), ",",
+    "\"behind\":", if(self.tracking_present(), tracking_behind_count.exact(), "null"), ",",
+    "\"conflict\":", if(conflict, "true", "false"),
+    "}\n"
+)"#;
+
+/// Template for jj versions without the tracking template functions: sync state
+/// is then computed from revset counts instead
+const LEGACY_BOOKMARK_TEMPLATE: &str = r#"concat(
+    "{\"name\":\"", name, "\",",
+    "\"remote\":", if(remote, concat("\"", remote, "\""), "null"), ",",
+    "\"change_id\":", if(normal_target, concat("\"", normal_target.change_id().short(), "\""), "null"), ",",
+    "\"ahead\":null,\"behind\":null,",
+    "\"conflict\":", if(conflict, "true", "false"),
+    "}\n"
+)"#;
+
+/// jj's pseudo-remote for the colocated git repo's own refs; never a push target
+const GIT_PSEUDO_REMOTE: &str = "git";
+
+/// Whether `entry` is `name`'s tracking entry on `remote_name`. Only the configured
+/// remote counts: other remotes and the `@git` pseudo-remote never drive sync state.
+fn is_remote_entry(entry: &BookmarkEntry, name: &str, remote_name: &str) -> bool {
+    remote_name != GIT_PSEUDO_REMOTE && entry.name == name && entry.remote.as_deref() == Some(remote_name)
+}
+
+/// Get all bookmarks with sync state against `remote_name`, running jj through `runner`.
+/// On a `version` too old for the tracking template functions, fall back to counting revisions.
+fn query_bookmarks_with(
+    runner: &dyn CommandRunner,
+    remote_name: &str,
+    version: Option<JjVersion>,
+) -> Result<Vec<Bookmark>> {
+    let legacy = version.is_some_and(|v| !v.supports_tracking_templates());
+
     // Use jj template to get structured bookmark data
-    // Use self.tracking_present() to check if this is a tracked remote ref before accessing tracking counts
-    let template = r#"concat(
-        "{\"name\":\"", name, "\",",
-        "\"remote\":", if(remote, concat("\"", remote, "\""), "null"), ",",
-        "\"change_id\":", if(normal_target, concat("\"", normal_target.change_id().short(), "\""), "null"), ",",
-        "\"synced\":", self.synced(), ",",
-        "\"ahead\":", if(self.tracking_present(), tracking_ahead_count.exact(), "null"), ",",
-        "\"behind\":", if(self.tracking_present(), tracking_behind_count.exact(), "null"),
-        "}\n"
-    )"#;
-
-    let output = run_jj(&["bookmark", "list", "--all", "-T", template])?;
+    let template = if legacy { LEGACY_BOOKMARK_TEMPLATE } else { BOOKMARK_TEMPLATE };
+    let output = runner.run("jj", &["bookmark", "list", "--all", "-T", template])?;
 
     // Parse JSON entries
     let mut entries: Vec<BookmarkEntry> = Vec::new();
@@ -114,22 +312,26 @@ fn query_bookmarks(remote_name: &str) -> Result<Vec<Bookmark>> {
         .collect();
 
     for local in local_entries {
-        // Find the corresponding remote entry (not @git)
-        let remote_entry = entries.iter().find(|e| {
-            e.name == local.name
-                && e.remote.as_ref().map(|r| r == remote_name).unwrap_or(false)
-        });
+        // Find the corresponding entry on the configured remote
+        let remote_entry = entries.iter().find(|e| is_remote_entry(e, &local.name, remote_name));
 
         let (has_remote, sync_state) = match remote_entry {
             Some(remote) => {
-                let ahead = remote.behind.unwrap_or(0); // remote behind = local ahead
-                let behind = remote.ahead.unwrap_or(0); // remote ahead = local behind
+                let (ahead, behind, synced) = if legacy {
+                    let remote_ref = format!("{}@{}", local.name, remote_name);
+                    let ahead = count_revisions(runner, &format!("({})..({})", remote_ref, local.name));
+                    let behind = count_revisions(runner, &format!("({})..({})", local.name, remote_ref));
+                    (ahead, behind, ahead == 0 && behind == 0)
+                } else {
+                    // remote behind = local ahead, remote ahead = local behind
+                    (remote.behind.unwrap_or(0), remote.ahead.unwrap_or(0), remote.synced)
+                };
 
-                let state = if remote.synced {
+                let state = if synced {
                     BookmarkSyncState::Synced
                 } else if ahead > 0 && behind > 0 {
                     // Diverged - need to find fork point
-                    let fork_point = find_fork_point(&local.name, remote_name);
+                    let fork_point = find_fork_point(runner, &local.name, remote_name);
                     BookmarkSyncState::Diverged {
                         local_ahead: ahead,
                         remote_ahead: behind,
@@ -159,12 +361,37 @@ fn query_bookmarks(remote_name: &str) -> Result<Vec<Bookmark>> {
     Ok(bookmarks)
 }
 
+/// Local bookmarks tracking `remote_name`, with their sync state, running jj through `runner`
+pub fn query_tracked_bookmarks_with(
+    runner: &dyn CommandRunner,
+    remote_name: &str,
+    version: Option<JjVersion>,
+) -> Result<Vec<TrackedBookmark>> {
+    Ok(query_bookmarks_with(runner, remote_name, version)?
+        .into_iter()
+        .filter(|b| b.has_remote)
+        .map(|b| TrackedBookmark {
+            name: b.name,
+            change_id: b.change_id,
+            sync_state: b.sync_state,
+        })
+        .collect())
+}
+
+/// Number of revisions in `revset` (0 if jj can't evaluate it)
+fn count_revisions(runner: &dyn CommandRunner, revset: &str) -> usize {
+    runner
+        .run("jj", &["log", "-r", revset, "--no-graph", "-T", "\"x\\n\""])
+        .map(|output| output.lines().count())
+        .unwrap_or(0)
+}
+
 /// Find the fork point (common ancestor) between local and remote bookmark
-fn find_fork_point(bookmark: &str, remote: &str) -> Option<String> {
+fn find_fork_point(runner: &dyn CommandRunner, bookmark: &str, remote: &str) -> Option<String> {
     let remote_ref = format!("{}@{}", bookmark, remote);
     // Use revset to find common ancestor
     let revset = format!("heads(::({}) & ::({}))", bookmark, remote_ref);
-    match run_jj(&["log", "-r", &revset, "-T", "change_id.short()", "--no-graph", "--limit", "1"]) {
+    match runner.run("jj", &["log", "-r", &revset, "-T", "change_id.short()", "--no-graph", "--limit", "1"]) {
         Ok(output) => {
             let id = output.trim().to_string();
             if id.is_empty() {
@@ -177,17 +404,100 @@ fn find_fork_point(bookmark: &str, remote: &str) -> Option<String> {
     }
 }
 
+/// Get the change IDs in `revset` that are immutable (empty if jj can't tell)
+fn query_immutable(runner: &RealRunner, revset: &str) -> HashSet<String> {
+    let immutable = format!("({}) & immutable()", revset);
+    run_jj(runner, &["log", "-r", &immutable, "--no-graph", "-T", "change_id ++ \"\\n\""])
+        .map(|output| output.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+/// Template printing "<change_id> <added> <removed>" per change
+const DIFF_STAT_TEMPLATE: &str =
+    r#"change_id ++ " " ++ self.diff().stat(80).total_added() ++ " " ++ self.diff().stat(80).total_removed() ++ "\n""#;
+
+/// Fill in `additions`/`deletions` for every change in the stack, with one jj call
+pub fn add_diff_stats(runner: &RealRunner, stack: &mut [ChangeWithStatus], revset: &str) -> Result<()> {
+    let output = run_jj(runner, &["log", "-r", revset, "--no-graph", "-T", DIFF_STAT_TEMPLATE])?;
+    let stats = parse_diff_stats(&output);
+    for item in stack {
+        if let Some(&(additions, deletions)) = stats.get(&item.change.change_id) {
+            item.additions = additions;
+            item.deletions = deletions;
+        }
+    }
+    Ok(())
+}
+
+/// Parse `DIFF_STAT_TEMPLATE` output into change ID -> (added, removed) lines
+fn parse_diff_stats(output: &str) -> HashMap<String, (usize, usize)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let change_id = parts.next()?;
+            let added = parts.next()?.parse().ok()?;
+            let removed = parts.next()?.parse().ok()?;
+            Some((change_id.to_string(), (added, removed)))
+        })
+        .collect()
+}
+
+/// Change ID and full description, each NUL-terminated (descriptions span lines)
+const FULL_DESCRIPTION_TEMPLATE: &str = r#"change_id ++ "\0" ++ description ++ "\0""#;
+
+/// Replace each change's first-line description with the full one, with one jj call
+pub fn add_full_descriptions(runner: &RealRunner, stack: &mut [ChangeWithStatus], revset: &str) -> Result<()> {
+    let output = run_jj(runner, &["log", "-r", revset, "--no-graph", "-T", FULL_DESCRIPTION_TEMPLATE])?;
+    let descriptions = parse_full_descriptions(&output);
+    for item in stack {
+        if let Some(description) = descriptions.get(&item.change.change_id) {
+            item.change.description = description.clone();
+        }
+    }
+    Ok(())
+}
+
+/// Parse `FULL_DESCRIPTION_TEMPLATE` output into change ID -> description
+fn parse_full_descriptions(output: &str) -> HashMap<String, String> {
+    let mut fields = output.split('\0');
+    let mut descriptions = HashMap::new();
+    while let (Some(change_id), Some(description)) = (fields.next(), fields.next()) {
+        descriptions.insert(change_id.trim().to_string(), description.trim_end().to_string());
+    }
+    descriptions
+}
+
 /// Get current working copy change ID
-fn get_working_copy_id() -> Result<String> {
-    let output = run_jj(&["log", "-r", "@", "-T", "change_id", "--no-graph"])?;
+fn get_working_copy_id(runner: &RealRunner) -> Result<String> {
+    let output = run_jj(runner, &["log", "-r", "@", "-T", "change_id", "--no-graph"])?;
     Ok(output.trim().to_string())
 }
 
+/// Check if a change is the working copy.
+/// Both IDs are full change IDs from the `change_id` template, so compare exactly -
+/// prefix matching can flag unrelated changes that share a prefix.
+fn is_working_copy(change_id: &str, working_id: &str) -> bool {
+    !working_id.is_empty() && change_id == working_id
+}
+
 /// Get stack with status information
-pub fn get_stack(revset: &str, remote_name: &str) -> Result<Vec<ChangeWithStatus>> {
-    let changes = query_changes(revset)?;
-    let bookmarks = query_bookmarks(remote_name)?;
-    let working_id = get_working_copy_id()?;
+pub fn get_stack(runner: &RealRunner, revset: &str, remote_name: &str) -> Result<Vec<ChangeWithStatus>> {
+    Ok(get_stack_result(runner, revset, remote_name)?.changes)
+}
+
+/// Get stack with status information, reporting how many changes failed to parse
+pub fn get_stack_result(
+    runner: &RealRunner,
+    revset: &str,
+    remote_name: &str,
+) -> Result<QueryResult<ChangeWithStatus>> {
+    let extra: Vec<TemplateField> =
+        ["is_empty", "has_conflict", "parents"].into_iter().filter_map(extra_field).collect();
+    let QueryResult { changes, skipped } = query_changes_with(runner, revset, &extra)?;
+    let immutable = query_immutable(runner, revset);
+    let bookmarks = query_bookmarks_with(runner, remote_name, jj_version(runner))?;
+    let working_id = get_working_copy_id(runner)?;
 
     // Match bookmarks to changes
     // Note: bookmark list shows short IDs, changes have full IDs
@@ -203,7 +513,9 @@ pub fn get_stack(revset: &str, remote_name: &str) -> Result<Vec<ChangeWithStatus
         let sync_state = matched_bookmark
             .map(|b| b.sync_state.clone())
             .unwrap_or(BookmarkSyncState::NoBookmark);
-        let is_working = change.change_id.starts_with(&working_id) || working_id.starts_with(&change.change_id);
+        let is_working = is_working_copy(&change.change_id, &working_id);
+        let is_empty = change.is_empty;
+        let is_immutable = immutable.contains(&change.change_id);
 
         result.push(ChangeWithStatus {
             change,
@@ -211,40 +523,184 @@ pub fn get_stack(revset: &str, remote_name: &str) -> Result<Vec<ChangeWithStatus
             is_working,
             has_remote,
             sync_state,
+            is_empty,
+            is_immutable,
+            additions: 0,
+            deletions: 0,
+            pr_state: None,
         });
     }
 
-    Ok(result)
+    Ok(QueryResult { changes: result, skipped })
+}
+
+/// Get the current user's email from jj config (None if unset)
+pub fn get_user_email(runner: &RealRunner) -> Option<String> {
+    run_jj(runner, &["config", "get", "user.email"])
+        .ok()
+        .map(|output| output.trim().to_string())
+        .filter(|email| !email.is_empty())
 }
 
 /// Check if jj is available
-pub fn check_jj_available() -> Result<()> {
-    Command::new("jj")
+pub fn check_jj_available(runner: &RealRunner) -> Result<Option<JjVersion>> {
+    let output = runner
+        .command("jj")
         .arg("--version")
         .output()
         .context("jj command not found. Please install jujutsu: https://github.com/martinvonz/jj")?;
 
-    Ok(())
+    let version = parse_jj_version(&String::from_utf8_lossy(&output.stdout));
+    Ok(*JJ_VERSION.get_or_init(|| version))
 }
 
-/// Create a bookmark at a specific change
-pub fn create_bookmark(name: &str, change_id: &str) -> Result<()> {
-    run_jj(&["bookmark", "create", name, "-r", change_id])?;
-    Ok(())
+/// First jj release with `tracking_present()` and `tracking_{ahead,behind}_count`
+const MIN_TRACKING_TEMPLATE_VERSION: JjVersion = JjVersion { major: 0, minor: 20, patch: 0 };
+
+static JJ_VERSION: OnceLock<Option<JjVersion>> = OnceLock::new();
+
+/// A jj release version, as reported by `jj --version`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct JjVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
 }
 
-/// Parse changes from jj log JSON output (for testing)
-pub fn parse_changes_output(output: &str) -> Vec<Change> {
-    let mut changes = Vec::new();
-    for line in output.lines() {
-        if line.trim().is_empty() {
-            continue;
+impl JjVersion {
+    /// Whether the bookmark template can use the tracking functions
+    pub fn supports_tracking_templates(&self) -> bool {
+        *self >= MIN_TRACKING_TEMPLATE_VERSION
+    }
+}
+
+impl std::fmt::Display for JjVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Warning for a jj too old for the tracking template functions (None if it's recent
+/// enough or unknown)
+pub fn old_jj_warning(version: Option<JjVersion>) -> Option<String> {
+    version.filter(|v| !v.supports_tracking_templates()).map(|version| {
+        format!(
+            "jj {} predates {}; bookmark sync state is computed the slow way. Consider upgrading jj.",
+            version, MIN_TRACKING_TEMPLATE_VERSION
+        )
+    })
+}
+
+/// Version of the installed jj, queried once (None if it can't be determined)
+pub fn jj_version(runner: &RealRunner) -> Option<JjVersion> {
+    *JJ_VERSION.get_or_init(|| {
+        runner
+            .command("jj")
+            .arg("--version")
+            .output()
+            .ok()
+            .and_then(|output| parse_jj_version(&String::from_utf8_lossy(&output.stdout)))
+    })
+}
+
+/// Parse `jj --version` output, e.g. "jj 0.24.0" or "jj 0.24.0-8f3c1a2b..."
+pub fn parse_jj_version(output: &str) -> Option<JjVersion> {
+    let version = output.split_whitespace().nth(1)?;
+    let version = version.split(['-', '+']).next()?;
+    let mut parts = version.split('.').map(|part| part.parse::<u32>().ok());
+    Some(JjVersion {
+        major: parts.next()??,
+        minor: parts.next()??,
+        patch: parts.next().flatten().unwrap_or(0),
+    })
+}
+
+/// Get a change's full description (queries only carry the first line)
+pub fn get_full_description(runner: &dyn CommandRunner, change_id: &str) -> Result<String> {
+    runner.run("jj", &["log", "-r", change_id, "--no-graph", "-T", "description"])
+}
+
+/// Empty, description-less changes in `revset` that are safe to abandon: never the
+/// working copy, a bookmarked change, or a conflicted one
+pub fn find_empty_in(runner: &dyn CommandRunner, revset: &str) -> Result<Vec<String>> {
+    let empty = format!(
+        "(({}) & empty() & description(exact:\"\")) ~ (@ | bookmarks() | conflicts())",
+        revset
+    );
+    let output = runner.run("jj", &["log", "-r", &empty, "--no-graph", "-T", "change_id ++ \"\\n\""])?;
+    Ok(output
+        .lines()
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Changes in `revset` that have conflicts
+pub fn find_conflicts_in(runner: &dyn CommandRunner, revset: &str) -> Result<Vec<String>> {
+    let conflicted = format!("({}) & conflicts()", revset);
+    let output = runner.run("jj", &["log", "-r", &conflicted, "--no-graph", "-T", "change_id ++ \"\\n\""])?;
+    Ok(output
+        .lines()
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Abandon the changes `find_empty_in` finds, returning the ones abandoned
+pub fn abandon_empty_in(runner: &dyn CommandRunner, revset: &str) -> Result<Vec<String>> {
+    Ok(find_empty_in(runner, revset)?
+        .into_iter()
+        .filter(|change_id| runner.run("jj", &["abandon", change_id]).is_ok())
+        .collect())
+}
+
+/// Create a bookmark at a change, idempotently: a bookmark already at that change
+/// (e.g., from a half-finished push) is left alone, and one at a different change
+/// is an error rather than being moved out from under another change.
+pub fn create_bookmark_with(runner: &dyn CommandRunner, name: &str, change_id: &str) -> Result<()> {
+    validate_bookmark_name(name)?;
+    let revset = format!("bookmarks(exact:\"{}\")", name);
+    let existing = runner.run("jj", &["log", "-r", &revset, "--no-graph", "-T", "change_id ++ \"\\n\""])?;
+    let existing: Vec<&str> = existing.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+
+    match existing.as_slice() {
+        [] => {
+            runner.run("jj", &["bookmark", "create", name, "-r", change_id])?;
+            Ok(())
         }
-        if let Ok(change) = serde_json::from_str::<Change>(line) {
-            changes.push(change);
+        [current] if *current == change_id => Ok(()),
+        _ => {
+            let short = |id: &str| id[..8.min(id.len())].to_string();
+            let current: Vec<String> = existing.iter().map(|id| short(id)).collect();
+            anyhow::bail!(
+                "Bookmark '{}' already exists at {}, not {}; pick another name or move it with `jj bookmark set {} -r {}`",
+                name,
+                current.join(", "),
+                short(change_id),
+                name,
+                short(change_id)
+            )
         }
     }
-    changes
+}
+
+/// Read the remaining changes from `changes` (a running `jj log`, or any reader via
+/// `ChangeIter::new`) into a `QueryResult`, counting lines that failed to parse
+pub fn parse_changes_result<R: BufRead>(mut changes: ChangeIter<R>) -> Result<QueryResult> {
+    let mut result = QueryResult::default();
+    for change in &mut changes {
+        result.changes.push(change?);
+    }
+    result.skipped = changes.skipped();
+    Ok(result)
+}
+
+/// Parse changes from jj log JSON output (for testing)
+#[cfg(test)]
+pub fn parse_changes_output(output: &str) -> Vec<Change> {
+    parse_changes_result(ChangeIter::new(output.as_bytes())).unwrap().changes
 }
 
 /// Parse bookmark entries from jj bookmark list JSON output (for testing)
@@ -261,7 +717,70 @@ pub fn parse_bookmark_entries(output: &str) -> Vec<BookmarkEntry> {
     entries
 }
 
-/// Compute sync state from bookmark entries (for testing)
+/// Get the names of all local bookmarks (including conflicted ones, excluding deleted)
+pub fn query_local_bookmark_names(runner: &RealRunner) -> Result<Vec<String>> {
+    let output = run_jj(runner, &["bookmark", "list", "--all", "-T", BOOKMARK_TEMPLATE])?;
+    Ok(parse_local_bookmark_names(&output))
+}
+
+/// Extract local bookmark names from templated `jj bookmark list` output.
+/// Remote-tracking entries have a remote; deleted entries have no target and no conflict.
+pub fn parse_local_bookmark_names(output: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for entry in parse_bookmark_entries(output) {
+        let is_local = entry.remote.is_none();
+        let is_present = entry.change_id.is_some() || entry.conflict;
+        if is_local && is_present && !names.contains(&entry.name) {
+            names.push(entry.name);
+        }
+    }
+    names
+}
+
+/// For each local bookmark, whether `remote` has a bookmark of the same name
+pub fn query_remote_presence_with(runner: &dyn CommandRunner, remote: &str) -> Result<BTreeMap<String, bool>> {
+    let output = runner.run("jj", &["bookmark", "list", "--all", "-T", BOOKMARK_TEMPLATE])?;
+    Ok(parse_remote_presence(&output, remote))
+}
+
+/// Sync state of bookmark `name` against its entry on `remote`, or `NoBookmark` when
+/// there's no local bookmark of that name
+pub fn query_sync_state_with(runner: &dyn CommandRunner, name: &str, remote: &str) -> Result<BookmarkSyncState> {
+    let output = runner.run("jj", &["bookmark", "list", "--all", "-T", BOOKMARK_TEMPLATE, name])?;
+    Ok(parse_sync_state(&output, name, remote))
+}
+
+/// Sync state of `name` from templated `jj bookmark list` output, via `compute_sync_state`
+fn parse_sync_state(output: &str, name: &str, remote: &str) -> BookmarkSyncState {
+    let entries = parse_bookmark_entries(output);
+    let Some(local) = entries.iter().find(|e| e.name == name && e.remote.is_none()) else {
+        return BookmarkSyncState::NoBookmark;
+    };
+    let remote_entry = entries
+        .iter()
+        .find(|e| is_remote_entry(e, name, remote) && (e.change_id.is_some() || e.conflict));
+    compute_sync_state(local, remote_entry)
+}
+
+/// Map local bookmark names in templated `jj bookmark list` output to whether a
+/// present entry exists for them on `remote`
+fn parse_remote_presence(output: &str, remote: &str) -> BTreeMap<String, bool> {
+    let entries = parse_bookmark_entries(output);
+    let on_remote = |name: &str| {
+        entries
+            .iter()
+            .any(|e| is_remote_entry(e, name, remote) && (e.change_id.is_some() || e.conflict))
+    };
+    parse_local_bookmark_names(output)
+        .into_iter()
+        .map(|name| {
+            let present = on_remote(&name);
+            (name, present)
+        })
+        .collect()
+}
+
+/// Compute sync state of a local bookmark from its entry on the remote, if any
 pub fn compute_sync_state(
     _local: &BookmarkEntry,
     remote: Option<&BookmarkEntry>,
@@ -294,6 +813,94 @@ pub fn compute_sync_state(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::jj::runner::mock::MockRunner;
+
+    const BOOKMARK_QUERY: &str = "jj log -r bookmarks(exact:\"feature\") --no-graph -T change_id ++ \"\\n\"";
+
+    #[test]
+    fn test_create_bookmark_absent_creates() {
+        let runner = MockRunner::new();
+        runner.mock_response(BOOKMARK_QUERY, "");
+        runner.mock_response("jj bookmark create feature -r qpvuntsmwlqt", "");
+
+        create_bookmark_with(&runner, "feature", "qpvuntsmwlqt").unwrap();
+        assert!(runner.was_called("jj", &["bookmark", "create", "feature", "-r", "qpvuntsmwlqt"]));
+    }
+
+    #[test]
+    fn test_create_bookmark_exists_at_same_change() {
+        let runner = MockRunner::new();
+        runner.mock_response(BOOKMARK_QUERY, "qpvuntsmwlqt\n");
+
+        create_bookmark_with(&runner, "feature", "qpvuntsmwlqt").unwrap();
+        assert!(!runner.was_called("jj", &["bookmark", "create", "feature", "-r", "qpvuntsmwlqt"]));
+    }
+
+    #[test]
+    fn test_create_bookmark_exists_at_different_change() {
+        let runner = MockRunner::new();
+        runner.mock_response(BOOKMARK_QUERY, "rlvkpnrzqnoo\n");
+
+        let err = create_bookmark_with(&runner, "feature", "qpvuntsmwlqt").unwrap_err();
+        assert!(err.to_string().contains("already exists at rlvkpnrz"));
+        assert!(!runner.was_called("jj", &["bookmark", "create", "feature", "-r", "qpvuntsmwlqt"]));
+        assert!(!runner.was_called("jj", &["bookmark", "set", "feature", "-r", "qpvuntsmwlqt"]));
+    }
+
+    #[test]
+    fn test_parse_diff_stats() {
+        let output = "qpvuntsmwlqt 12 3\nrlvkpnrzqnoo 0 0\n\nbroken line\nzsuskuln x 1\n";
+        let stats = parse_diff_stats(output);
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats.get("qpvuntsmwlqt"), Some(&(12, 3)));
+        assert_eq!(stats.get("rlvkpnrzqnoo"), Some(&(0, 0)));
+    }
+
+    #[test]
+    fn test_build_template_default_fields() {
+        // Byte-compatible with the original hand-written template
+        let expected = concat!(
+            r#"concat("{", "\"change_id\":\"", change_id, "\"", ",", "#,
+            r#""\"commit_id\":\"", commit_id, "\"", ",", "#,
+            r#""\"description\":\"", description.first_line(), "\"", ",", "#,
+            r#""\"author\":", concat("{\"name\":\"", author.name(), "\",\"email\":\"", author.email(), "\"}"), ",", "#,
+            r#""\"bookmarks\":", concat("[", bookmarks.map(|b| concat("\"", b.name(), "\"")).join(","), "]"), ",", "#,
+            r#""\"timestamp\":\"", author.timestamp().utc().format("%Y-%m-%dT%H:%M:%SZ"), "\"", "}\n")"#,
+        );
+        assert_eq!(build_template(DEFAULT_FIELDS), expected);
+    }
+
+    #[test]
+    fn test_build_template_with_extra_field() {
+        let fields: Vec<TemplateField> = DEFAULT_FIELDS
+            .iter()
+            .copied()
+            .chain(extra_field("is_empty"))
+            .collect();
+        let template = build_template(&fields);
+        assert!(template.ends_with(r#", ",", "\"is_empty\":", if(empty, "true", "false"), "}\n")"#));
+    }
+
+    #[test]
+    fn test_parse_change_with_two_parents() {
+        let fields: Vec<TemplateField> = DEFAULT_FIELDS.iter().copied().chain(extra_field("parents")).collect();
+        assert!(build_template(&fields).contains(r#"parents.map(|p| concat("\"", p.change_id(), "\"")).join(",")"#));
+
+        let output = r#"{"change_id":"abc123","commit_id":"def456","description":"Merge","author":{"name":"Test","email":"test@test.com"},"bookmarks":[],"parents":["kkmpptxz","qpvuntsm"]}"#;
+        let change: Change = serde_json::from_str(output).unwrap();
+        assert_eq!(change.parents, ["kkmpptxz", "qpvuntsm"]);
+
+        // Older output without the field still parses
+        let output = r#"{"change_id":"abc123","commit_id":"def456"}"#;
+        assert!(serde_json::from_str::<Change>(output).unwrap().parents.is_empty());
+    }
+
+    #[test]
+    fn test_extra_field_unknown() {
+        assert!(extra_field("is_empty").is_some());
+        assert!(extra_field("committer").is_some());
+        assert!(extra_field("nonexistent").is_none());
+    }
 
     #[test]
     fn test_parse_changes_output_single() {
@@ -344,6 +951,67 @@ not valid json
         assert_eq!(changes.len(), 2);
     }
 
+    #[test]
+    fn test_parse_changes_result_counts_skipped() {
+        let output = r#"{"change_id":"abc123","commit_id":"def456","description":"Valid","author":{"name":"","email":""},"bookmarks":[]}
+{"change_id":"xyz789","commit_id":"#;
+
+        let result = parse_changes_result(ChangeIter::new(output.as_bytes())).unwrap();
+        assert_eq!(result.changes.len(), 1);
+        assert_eq!(result.changes[0].change_id, "abc123");
+        assert_eq!(result.skipped, 1);
+    }
+
+    /// Reader handing out one chunk per `read`, counting how many were consumed
+    struct ChunkReader {
+        chunks: std::collections::VecDeque<&'static str>,
+        reads: std::rc::Rc<std::cell::Cell<usize>>,
+    }
+
+    impl Read for ChunkReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let Some(chunk) = self.chunks.pop_front() else {
+                return Ok(0);
+            };
+            self.reads.set(self.reads.get() + 1);
+            buf[..chunk.len()].copy_from_slice(chunk.as_bytes());
+            Ok(chunk.len())
+        }
+    }
+
+    #[test]
+    fn test_change_iter_is_lazy() {
+        let reads = std::rc::Rc::new(std::cell::Cell::new(0));
+        let reader = ChunkReader {
+            chunks: [
+                "{\"change_id\":\"abc123\",\"commit_id\":\"def456\",\"description\":\"First\",\"author\":{\"name\":\"\",\"email\":\"\"},\"bookmarks\":[]}\n",
+                "not valid json\n\n",
+                "{\"change_id\":\"xyz789\",\"commit_id\":\"uvw012\",\"description\":\"Second\",\"author\":{\"name\":\"\",\"email\":\"\"},\"bookmarks\":[]}\n",
+            ]
+            .into(),
+            reads: reads.clone(),
+        };
+        let mut iter = ChangeIter::new(BufReader::new(reader));
+
+        // The first change arrives after reading only the first chunk
+        assert_eq!(iter.next().unwrap().unwrap().change_id, "abc123");
+        assert_eq!(reads.get(), 1);
+
+        let result = parse_changes_result(iter).unwrap();
+        assert_eq!(reads.get(), 3);
+        assert_eq!(result.changes.len(), 1);
+        assert_eq!(result.changes[0].change_id, "xyz789");
+        assert_eq!(result.skipped, 1);
+    }
+
+    #[test]
+    fn test_parse_changes_result_blank_lines_not_skipped() {
+        let output = "\n\n   \n";
+        let result = parse_changes_result(ChangeIter::new(output.as_bytes())).unwrap();
+        assert!(result.changes.is_empty());
+        assert_eq!(result.skipped, 0);
+    }
+
     #[test]
     fn test_parse_bookmark_entries_local() {
         let output = r#"{"name":"feature","remote":null,"change_id":"abc123","synced":false,"ahead":null,"behind":null}"#;
@@ -374,6 +1042,177 @@ not valid json
         assert_eq!(entries[0].behind, Some(2));
     }
 
+    const EMPTY_QUERY: &str = "jj log -r ((::@ ~ ::main@origin) & empty() & description(exact:\"\")) ~ (@ | bookmarks() | conflicts()) --no-graph -T change_id ++ \"\\n\"";
+
+    #[test]
+    fn test_find_empty_in_excludes_protected_changes() {
+        let runner = MockRunner::new();
+        runner.mock_response(EMPTY_QUERY, "aaaaaaaa\nbbbbbbbb\n");
+
+        assert_eq!(find_empty_in(&runner, "::@ ~ ::main@origin").unwrap(), ["aaaaaaaa", "bbbbbbbb"]);
+        // Listing never abandons anything
+        assert!(!runner.get_calls().iter().any(|(_, args)| args[0] == "abandon"));
+    }
+
+    #[test]
+    fn test_abandon_empty_in_reports_abandoned() {
+        let runner = MockRunner::new();
+        runner.mock_response(EMPTY_QUERY, "aaaaaaaa\nbbbbbbbb\n");
+        runner.mock_response("jj abandon aaaaaaaa", "");
+        runner.mock_error("jj abandon bbbbbbbb", "Error: change is immutable");
+
+        assert_eq!(abandon_empty_in(&runner, "::@ ~ ::main@origin").unwrap(), ["aaaaaaaa"]);
+        assert!(runner.was_called("jj", &["abandon", "bbbbbbbb"]));
+    }
+
+    #[test]
+    fn test_abandon_empty_in_nothing_to_do() {
+        let runner = MockRunner::new();
+        runner.mock_response(EMPTY_QUERY, "");
+
+        assert!(abandon_empty_in(&runner, "::@ ~ ::main@origin").unwrap().is_empty());
+        assert_eq!(runner.get_calls().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_full_descriptions() {
+        let output = "aaaa\0Add feature\n\nWhy it matters.\n\0\nbbbb\0Fix bug\n\0";
+        let descriptions = parse_full_descriptions(output);
+        assert_eq!(descriptions["aaaa"], "Add feature\n\nWhy it matters.");
+        assert_eq!(descriptions["bbbb"], "Fix bug");
+    }
+
+    #[test]
+    fn test_parse_jj_version() {
+        let version = |major, minor, patch| Some(JjVersion { major, minor, patch });
+        assert_eq!(parse_jj_version("jj 0.24.0\n"), version(0, 24, 0));
+        assert_eq!(parse_jj_version("jj 0.19.1-8f3c1a2b9d0e4f5a\n"), version(0, 19, 1));
+        assert_eq!(parse_jj_version("jj 1.2"), version(1, 2, 0));
+        assert_eq!(parse_jj_version("jj"), None);
+        assert_eq!(parse_jj_version("jj dev"), None);
+        assert_eq!(parse_jj_version(""), None);
+
+        assert!(!parse_jj_version("jj 0.19.1").unwrap().supports_tracking_templates());
+        assert!(parse_jj_version("jj 0.20.0").unwrap().supports_tracking_templates());
+        assert!(parse_jj_version("jj 1.0.0").unwrap().supports_tracking_templates());
+    }
+
+    #[test]
+    fn test_old_jj_warning() {
+        let warning = old_jj_warning(parse_jj_version("jj 0.19.1")).unwrap();
+        assert!(warning.starts_with("jj 0.19.1 predates 0.20.0"));
+        assert_eq!(old_jj_warning(parse_jj_version("jj 0.24.0")), None);
+        assert_eq!(old_jj_warning(None), None);
+    }
+
+    #[test]
+    fn test_query_bookmarks_legacy_jj_counts_revisions() {
+        let runner = MockRunner::new();
+        let output = [
+            r#"{"name":"feature","remote":null,"change_id":"abc123","ahead":null,"behind":null,"conflict":false}"#,
+            r#"{"name":"feature","remote":"origin","change_id":"def456","ahead":null,"behind":null,"conflict":false}"#,
+        ]
+        .join("\n");
+        runner.mock_response(&format!("jj bookmark list --all -T {}", LEGACY_BOOKMARK_TEMPLATE), &output);
+        runner.mock_response("jj log -r (feature@origin)..(feature) --no-graph -T \"x\\n\"", "x\nx\n");
+        runner.mock_response("jj log -r (feature)..(feature@origin) --no-graph -T \"x\\n\"", "");
+
+        let old = Some(JjVersion { major: 0, minor: 18, patch: 0 });
+        let bookmarks = query_bookmarks_with(&runner, "origin", old).unwrap();
+        assert!(matches!(bookmarks[0].sync_state, BookmarkSyncState::Ahead { count: 2 }));
+        assert!(!runner.was_called("jj", &["bookmark", "list", "--all", "-T", BOOKMARK_TEMPLATE]));
+    }
+
+    #[test]
+    fn test_query_bookmarks_only_configured_remote_drives_state() {
+        let runner = MockRunner::new();
+        let output = [
+            r#"{"name":"feature","remote":null,"change_id":"abc123","synced":false,"ahead":null,"behind":null}"#,
+            r#"{"name":"feature","remote":"git","change_id":"abc123","synced":true,"ahead":0,"behind":0}"#,
+            r#"{"name":"feature","remote":"upstream","change_id":"def456","synced":false,"ahead":0,"behind":4}"#,
+            r#"{"name":"feature","remote":"origin","change_id":"def456","synced":false,"ahead":0,"behind":1}"#,
+            r#"{"name":"local","remote":null,"change_id":"fed321","synced":false,"ahead":null,"behind":null}"#,
+            r#"{"name":"local","remote":"git","change_id":"fed321","synced":true,"ahead":0,"behind":0}"#,
+        ]
+        .join("\n");
+        runner.mock_response(&format!("jj bookmark list --all -T {}", BOOKMARK_TEMPLATE), &output);
+
+        let bookmarks = query_bookmarks_with(&runner, "origin", None).unwrap();
+        assert!(bookmarks[0].has_remote);
+        assert!(matches!(bookmarks[0].sync_state, BookmarkSyncState::Ahead { count: 1 }));
+        // Only exported to the colocated git repo, so not on any real remote
+        assert!(!bookmarks[1].has_remote);
+        assert!(matches!(bookmarks[1].sync_state, BookmarkSyncState::LocalOnly));
+
+        // The pseudo-remote is never a remote, even when configured as one
+        let git = query_bookmarks_with(&runner, "git", None).unwrap();
+        assert!(git.iter().all(|b| !b.has_remote));
+    }
+
+    #[test]
+    fn test_parse_remote_presence_ignores_git_pseudo_remote() {
+        let output = r#"{"name":"feature","remote":null,"change_id":"abc123","synced":false,"ahead":null,"behind":null,"conflict":false}
+{"name":"feature","remote":"git","change_id":"abc123","synced":true,"ahead":null,"behind":null,"conflict":false}"#;
+
+        assert_eq!(parse_remote_presence(output, "git").get("feature"), Some(&false));
+    }
+
+    #[test]
+    fn test_query_sync_state_uses_given_remote() {
+        let runner = MockRunner::new();
+        let output = [
+            r#"{"name":"feature","remote":null,"change_id":"abc123","synced":false,"ahead":null,"behind":null}"#,
+            r#"{"name":"feature","remote":"origin","change_id":"def456","synced":false,"ahead":1,"behind":2}"#,
+            r#"{"name":"feature","remote":"upstream","change_id":"abc123","synced":true,"ahead":0,"behind":0}"#,
+        ]
+        .join("\n");
+        runner.mock_response(&format!("jj bookmark list --all -T {} feature", BOOKMARK_TEMPLATE), &output);
+
+        let state = query_sync_state_with(&runner, "feature", "origin").unwrap();
+        assert!(matches!(state, BookmarkSyncState::Diverged { local_ahead: 2, remote_ahead: 1, .. }));
+        assert!(matches!(parse_sync_state(&output, "feature", "upstream"), BookmarkSyncState::Synced));
+        assert!(matches!(parse_sync_state(&output, "feature", "mirror"), BookmarkSyncState::LocalOnly));
+        assert!(matches!(parse_sync_state(&output, "other", "origin"), BookmarkSyncState::NoBookmark));
+    }
+
+    #[test]
+    fn test_query_bookmarks_uses_given_remote() {
+        let runner = MockRunner::new();
+        let output = [
+            r#"{"name":"feature","remote":null,"change_id":"abc123","synced":false,"ahead":null,"behind":null}"#,
+            r#"{"name":"feature","remote":"origin","change_id":"abc123","synced":true,"ahead":0,"behind":0}"#,
+            r#"{"name":"feature","remote":"upstream","change_id":"def456","synced":false,"ahead":1,"behind":2}"#,
+        ]
+        .join("\n");
+        runner.mock_response(&format!("jj bookmark list --all -T {}", BOOKMARK_TEMPLATE), &output);
+        runner.mock_response(
+            "jj log -r heads(::(feature) & ::(feature@upstream)) -T change_id.short() --no-graph --limit 1",
+            "fork1234",
+        );
+
+        let origin = query_bookmarks_with(&runner, "origin", None).unwrap();
+        assert!(matches!(origin[0].sync_state, BookmarkSyncState::Synced));
+
+        let upstream = query_bookmarks_with(&runner, "upstream", None).unwrap();
+        assert!(upstream[0].has_remote);
+        match &upstream[0].sync_state {
+            BookmarkSyncState::Diverged {
+                local_ahead,
+                remote_ahead,
+                fork_point,
+            } => {
+                assert_eq!(*local_ahead, 2);
+                assert_eq!(*remote_ahead, 1);
+                assert_eq!(fork_point.as_deref(), Some("fork1234"));
+            }
+            other => panic!("expected diverged, got {:?}", other),
+        }
+        assert!(!runner.was_called(
+            "jj",
+            &["log", "-r", "heads(::(feature) & ::(feature@origin))", "-T", "change_id.short()", "--no-graph", "--limit", "1"]
+        ));
+    }
+
     #[test]
     fn test_compute_sync_state_synced() {
         let local = BookmarkEntry {
@@ -383,6 +1222,7 @@ not valid json
             synced: false,
             ahead: None,
             behind: None,
+            conflict: false,
         };
         let remote = BookmarkEntry {
             name: "feature".to_string(),
@@ -391,6 +1231,7 @@ not valid json
             synced: true,
             ahead: Some(0),
             behind: Some(0),
+            conflict: false,
         };
 
         let state = compute_sync_state(&local, Some(&remote));
@@ -406,6 +1247,7 @@ not valid json
             synced: false,
             ahead: None,
             behind: None,
+            conflict: false,
         };
         let remote = BookmarkEntry {
             name: "feature".to_string(),
@@ -414,6 +1256,7 @@ not valid json
             synced: false,
             ahead: Some(0),
             behind: Some(3), // remote behind = local ahead
+            conflict: false,
         };
 
         let state = compute_sync_state(&local, Some(&remote));
@@ -429,6 +1272,7 @@ not valid json
             synced: false,
             ahead: None,
             behind: None,
+            conflict: false,
         };
         let remote = BookmarkEntry {
             name: "feature".to_string(),
@@ -437,6 +1281,7 @@ not valid json
             synced: false,
             ahead: Some(2), // remote ahead = local behind
             behind: Some(0),
+            conflict: false,
         };
 
         let state = compute_sync_state(&local, Some(&remote));
@@ -452,6 +1297,7 @@ not valid json
             synced: false,
             ahead: None,
             behind: None,
+            conflict: false,
         };
         let remote = BookmarkEntry {
             name: "feature".to_string(),
@@ -460,6 +1306,7 @@ not valid json
             synced: false,
             ahead: Some(2),
             behind: Some(3),
+            conflict: false,
         };
 
         let state = compute_sync_state(&local, Some(&remote));
@@ -482,6 +1329,7 @@ not valid json
             synced: false,
             ahead: None,
             behind: None,
+            conflict: false,
         };
 
         let state = compute_sync_state(&local, None);
@@ -625,6 +1473,7 @@ not valid json
             synced: false,
             ahead: None,
             behind: None,
+            conflict: false,
         };
         let remote = BookmarkEntry {
             name: "feature".to_string(),
@@ -633,6 +1482,7 @@ not valid json
             synced: false, // Not synced flag
             ahead: Some(0),
             behind: Some(0),
+            conflict: false,
         };
 
         let state = compute_sync_state(&local, Some(&remote));
@@ -649,6 +1499,7 @@ not valid json
             synced: false,
             ahead: None,
             behind: None,
+            conflict: false,
         };
         let remote = BookmarkEntry {
             name: "feature".to_string(),
@@ -657,6 +1508,7 @@ not valid json
             synced: false,
             ahead: None, // No ahead info
             behind: Some(5),
+            conflict: false,
         };
 
         let state = compute_sync_state(&local, Some(&remote));
@@ -672,6 +1524,7 @@ not valid json
             synced: false,
             ahead: None,
             behind: None,
+            conflict: false,
         };
         let remote = BookmarkEntry {
             name: "feature".to_string(),
@@ -680,6 +1533,7 @@ not valid json
             synced: false,
             ahead: Some(3),
             behind: None, // No behind info
+            conflict: false,
         };
 
         let state = compute_sync_state(&local, Some(&remote));
@@ -695,6 +1549,7 @@ not valid json
             synced: false,
             ahead: None,
             behind: None,
+            conflict: false,
         };
         let remote = BookmarkEntry {
             name: "feature".to_string(),
@@ -703,6 +1558,7 @@ not valid json
             synced: false,
             ahead: Some(1000),
             behind: Some(500),
+            conflict: false,
         };
 
         let state = compute_sync_state(&local, Some(&remote));
@@ -751,4 +1607,88 @@ not valid json
         let matches = !bookmark_change_id.is_empty() && change_id.starts_with(bookmark_change_id);
         assert!(!matches, "Empty change_id should not match any change");
     }
+
+    #[test]
+    fn test_is_working_copy_exact_match_only() {
+        // Two distinct full change IDs sharing a prefix
+        let working = "kmkuslswpqwqwtsnqyltqwkmmrtkpylr";
+        let sibling = "kmkuslswpqwqwtsnqyltqwkmmrtkpyzz";
+
+        assert!(is_working_copy(working, working));
+        assert!(!is_working_copy(sibling, working));
+        // Prefixes in either direction don't count
+        assert!(!is_working_copy("kmkuslsw", working));
+        assert!(!is_working_copy(working, "kmkuslsw"));
+    }
+
+    #[test]
+    fn test_is_working_copy_empty_working_id() {
+        assert!(!is_working_copy("", ""));
+        assert!(!is_working_copy("abc123", ""));
+    }
+
+    #[test]
+    fn test_parse_local_bookmark_names_tricky_names() {
+        let output = r#"{"name":"feature/nested/name","remote":null,"change_id":"abc123","synced":false,"ahead":null,"behind":null,"conflict":false}
+{"name":"feature/nested/name","remote":"origin","change_id":"abc123","synced":true,"ahead":0,"behind":0,"conflict":false}
+{"name":"fix: colon name","remote":null,"change_id":"def456","synced":false,"ahead":null,"behind":null,"conflict":false}
+{"name":"tab\tname","remote":null,"change_id":"ghi789","synced":false,"ahead":null,"behind":null,"conflict":false}"#;
+
+        let names = parse_local_bookmark_names(output);
+        assert_eq!(names, vec!["feature/nested/name", "fix: colon name", "tab\tname"]);
+    }
+
+    #[test]
+    fn test_parse_local_bookmark_names_skips_deleted() {
+        // A deleted bookmark has no local target but still has a remote entry
+        let output = r#"{"name":"gone (deleted)","remote":null,"change_id":null,"synced":false,"ahead":null,"behind":null,"conflict":false}
+{"name":"gone (deleted)","remote":"origin","change_id":"abc123","synced":false,"ahead":null,"behind":null,"conflict":false}
+{"name":"kept","remote":null,"change_id":"def456","synced":false,"ahead":null,"behind":null,"conflict":false}"#;
+
+        let names = parse_local_bookmark_names(output);
+        assert_eq!(names, vec!["kept"]);
+    }
+
+    #[test]
+    fn test_parse_remote_presence() {
+        let output = r#"{"name":"feature","remote":null,"change_id":"abc123","synced":false,"ahead":null,"behind":null,"conflict":false}
+{"name":"feature","remote":"origin","change_id":"abc123","synced":true,"ahead":0,"behind":0,"conflict":false}
+{"name":"local","remote":null,"change_id":"def456","synced":false,"ahead":null,"behind":null,"conflict":false}
+{"name":"upstream-only","remote":"upstream","change_id":"ghi789","synced":false,"ahead":0,"behind":0,"conflict":false}
+{"name":"upstream-only","remote":null,"change_id":"ghi789","synced":false,"ahead":null,"behind":null,"conflict":false}"#;
+
+        let presence = parse_remote_presence(output, "origin");
+        assert_eq!(
+            presence,
+            BTreeMap::from([
+                ("feature".to_string(), true),
+                ("local".to_string(), false),
+                ("upstream-only".to_string(), false),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_local_bookmark_names_includes_conflicted() {
+        let output = r#"{"name":"conflicted","remote":null,"change_id":null,"synced":false,"ahead":null,"behind":null,"conflict":true}
+{"name":"conflicted","remote":"origin","change_id":"abc123","synced":false,"ahead":null,"behind":null,"conflict":false}"#;
+
+        let names = parse_local_bookmark_names(output);
+        assert_eq!(names, vec!["conflicted"]);
+    }
+
+    #[test]
+    fn test_parse_local_bookmark_names_skips_remote_only() {
+        let output = r#"{"name":"teammate","remote":"origin","change_id":"abc123","synced":false,"ahead":null,"behind":null,"conflict":false}
+{"name":"teammate","remote":"git","change_id":"abc123","synced":true,"ahead":null,"behind":null,"conflict":false}"#;
+
+        assert!(parse_local_bookmark_names(output).is_empty());
+    }
+
+    #[test]
+    fn test_parse_bookmark_entries_conflict_defaults_false() {
+        let output = r#"{"name":"feature","remote":null,"change_id":"abc123","synced":false,"ahead":null,"behind":null}"#;
+        let entries = parse_bookmark_entries(output);
+        assert!(!entries[0].conflict);
+    }
 }
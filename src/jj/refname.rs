@@ -0,0 +1,120 @@
+use anyhow::Result;
+
+/// Check a full bookmark name (prefix included) against git's ref-name rules
+/// (`git check-ref-format`), so a bad name is rejected up front instead of failing
+/// deep inside jj or git.
+pub fn validate_bookmark_name(name: &str) -> Result<()> {
+    let reject = |reason: &str| anyhow::bail!("Invalid bookmark name '{}': {}", name, reason);
+
+    if name.is_empty() {
+        return reject("name is empty");
+    }
+    if name == "@" {
+        return reject("'@' alone is not a valid name");
+    }
+    if name.starts_with('-') {
+        return reject("must not start with '-'");
+    }
+    if name.starts_with('/') || name.ends_with('/') {
+        return reject("must not start or end with '/'");
+    }
+    if name.ends_with('.') {
+        return reject("must not end with '.'");
+    }
+    if name.contains("..") {
+        return reject("must not contain '..'");
+    }
+    if name.contains("//") {
+        return reject("must not contain consecutive slashes");
+    }
+    if name.contains("@{") {
+        return reject("must not contain '@{'");
+    }
+    if let Some(c) = name.chars().find(|c| c.is_ascii_control()) {
+        return reject(&format!("must not contain control characters ({:?})", c));
+    }
+    if name.contains(char::is_whitespace) {
+        return reject("must not contain spaces");
+    }
+    if let Some(c) = name.chars().find(|c| matches!(c, '~' | '^' | ':' | '?' | '*' | '[' | '\\')) {
+        return reject(&format!("must not contain '{}'", c));
+    }
+    for component in name.split('/') {
+        if component.starts_with('.') {
+            return reject("no part may start with '.'");
+        }
+        if component.ends_with(".lock") {
+            return reject("no part may end with '.lock'");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reason(name: &str) -> String {
+        validate_bookmark_name(name).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_valid_names() {
+        for name in ["feature", "fix-login-bug", "v1.2", "user_name", "添加功能", "a@b"] {
+            assert!(validate_bookmark_name(name).is_ok(), "{} should be valid", name);
+        }
+    }
+
+    #[test]
+    fn test_slashes_allowed() {
+        assert!(validate_bookmark_name("jf/feature").is_ok());
+        assert!(validate_bookmark_name("wip/alice/experiment").is_ok());
+    }
+
+    #[test]
+    fn test_empty_and_at() {
+        assert!(reason("").contains("empty"));
+        assert!(reason("@").contains("'@' alone"));
+    }
+
+    #[test]
+    fn test_leading_dash() {
+        assert!(reason("-feature").contains("start with '-'"));
+    }
+
+    #[test]
+    fn test_slash_placement() {
+        assert!(reason("/feature").contains("start or end with '/'"));
+        assert!(reason("feature/").contains("start or end with '/'"));
+        assert!(reason("jf//feature").contains("consecutive slashes"));
+    }
+
+    #[test]
+    fn test_dots() {
+        assert!(reason("feature.").contains("end with '.'"));
+        assert!(reason("feature..fix").contains("'..'"));
+        assert!(reason(".hidden").contains("start with '.'"));
+        assert!(reason("jf/.hidden").contains("start with '.'"));
+        assert!(reason("feature.lock").contains(".lock"));
+    }
+
+    #[test]
+    fn test_reflog_syntax() {
+        assert!(reason("feature@{1}").contains("'@{'"));
+    }
+
+    #[test]
+    fn test_whitespace_and_control_chars() {
+        assert!(reason("my feature").contains("spaces"));
+        assert!(reason("feature\tfix").contains("control characters"));
+        assert!(reason("feature\u{7f}").contains("control characters"));
+    }
+
+    #[test]
+    fn test_special_characters() {
+        for c in ['~', '^', ':', '?', '*', '[', '\\'] {
+            let name = format!("feature{}fix", c);
+            assert!(reason(&name).contains(&format!("'{}'", c)), "{} should be rejected", name);
+        }
+    }
+}
@@ -1,5 +1,11 @@
 use anyhow::{Context, Result};
-use std::process::Command;
+use std::io::Read;
+use std::process::{Command, Output, Stdio};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::config::ToolsConfig;
 
 /// Trait for executing shell commands, allowing for mocking in tests
 pub trait CommandRunner: Send + Sync {
@@ -12,16 +18,200 @@ pub trait CommandRunner: Send + Sync {
     }
 }
 
+/// How long one subprocess took
+#[derive(Debug, Clone)]
+pub struct Timing {
+    /// The command line, e.g. "jj log -r @"
+    pub command: String,
+    pub elapsed: Duration,
+}
+
+impl std::fmt::Display for Timing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:>8.1}ms  {}", self.elapsed.as_secs_f64() * 1000.0, self.command)
+    }
+}
+
+/// Subprocess timings, recorded and echoed to stderr once enabled (`--timings`)
+pub struct Timings {
+    /// None until enabled
+    entries: Mutex<Option<Vec<Timing>>>,
+}
+
+impl Timings {
+    pub const fn new() -> Self {
+        Self { entries: Mutex::new(None) }
+    }
+
+    pub fn enable(&self) {
+        self.entries.lock().unwrap().get_or_insert_with(Vec::new);
+    }
+
+    /// Run `f`, which runs `program args`, recording how long it took if enabled
+    pub fn time<T>(&self, program: &str, args: &[&str], f: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let result = f();
+        if let Some(entries) = self.entries.lock().unwrap().as_mut() {
+            let timing = Timing {
+                command: std::iter::once(program).chain(args.iter().copied()).collect::<Vec<_>>().join(" "),
+                elapsed: started.elapsed(),
+            };
+            eprintln!("{}", colored::Colorize::dimmed(timing.to_string().as_str()));
+            entries.push(timing);
+        }
+        result
+    }
+
+    /// Closing summary, e.g. "12 commands in 340.2ms" (None if not enabled)
+    pub fn summary(&self) -> Option<String> {
+        let entries = self.entries.lock().unwrap();
+        let entries = entries.as_ref()?;
+        let total: Duration = entries.iter().map(|t| t.elapsed).sum();
+        Some(format!("{} commands in {:.1}ms", entries.len(), total.as_secs_f64() * 1000.0))
+    }
+}
+
+/// Timings of every jj, gh, and git command jf runs
+pub static TIMINGS: Timings = Timings::new();
+
+/// Record and echo how long each subprocess takes, for `--timings`
+pub fn enable_timings() {
+    TIMINGS.enable();
+}
+
+/// Print the total subprocess time to stderr, if `--timings` is on
+pub fn print_timings_summary() {
+    if let Some(summary) = TIMINGS.summary() {
+        eprintln!("{}", colored::Colorize::dimmed(format!("total: {}", summary).as_str()));
+    }
+}
+
 /// Real command runner that executes actual shell commands
-#[derive(Default)]
-pub struct RealRunner;
+#[derive(Debug, Clone, Default)]
+pub struct RealRunner {
+    /// Binaries from `[tools]`, used for every jj, gh, and git command
+    tools: ToolsConfig,
+    /// Kill network commands that run longer than this (None waits forever)
+    timeout: Option<Duration>,
+}
+
+impl RealRunner {
+    /// Run jj, gh, and git from the binaries configured in `[tools]`
+    pub fn new(tools: &ToolsConfig) -> Self {
+        Self {
+            tools: tools.clone(),
+            timeout: None,
+        }
+    }
+
+    /// Kill network commands (see `is_network_command`) after `timeout`.
+    /// Local commands are never timed out, however slow.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl RealRunner {
+    /// A `Command` for `program` from `[tools]`, with `[tools] jj_args` placed
+    /// ahead of the subcommand for jj
+    pub fn command(&self, program: &str) -> Command {
+        let mut command = Command::new(self.tools.resolve(program));
+        if program == "jj" {
+            command.args(&self.tools.jj_args);
+        }
+        command
+    }
+
+    /// Run a command with the terminal's stdin, stdout, and stderr, so its output
+    /// streams as it runs (e.g., a hook's test output). Fails if it exits non-zero.
+    pub fn run_streaming(&self, program: &str, args: &[&str]) -> Result<()> {
+        let status = TIMINGS.time(program, args, || {
+            self.command(program)
+                .args(args)
+                .status()
+                .with_context(|| format!("Failed to execute {} command", program))
+        })?;
+        if !status.success() {
+            anyhow::bail!("{} command failed: {}", program, status);
+        }
+        Ok(())
+    }
+}
+
+/// A command was killed for running longer than its timeout
+#[derive(Debug)]
+pub struct TimeoutError {
+    pub program: String,
+    pub timeout: Duration,
+}
+
+impl std::fmt::Display for TimeoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} command timed out after {}s", self.program, self.timeout.as_secs_f64())
+    }
+}
+
+impl std::error::Error for TimeoutError {}
+
+/// Run a command like `Command::output`, killing it once `timeout` has passed
+fn output_with_timeout(runner: &RealRunner, program: &str, args: &[&str], timeout: Duration) -> Result<Output> {
+    let mut child = runner
+        .command(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to execute {} command", program))?;
+
+    // Drain the pipes on threads so a chatty child can't block on a full pipe
+    fn drain(pipe: Option<impl Read + Send + 'static>) -> Option<JoinHandle<Vec<u8>>> {
+        pipe.map(|mut pipe| {
+            std::thread::spawn(move || {
+                let mut buf = Vec::new();
+                let _ = pipe.read_to_end(&mut buf);
+                buf
+            })
+        })
+    }
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(TimeoutError {
+                program: program.to_string(),
+                timeout,
+            }
+            .into());
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+
+    let collect = |handle: Option<JoinHandle<Vec<u8>>>| handle.and_then(|h| h.join().ok()).unwrap_or_default();
+    Ok(Output {
+        status,
+        stdout: collect(stdout),
+        stderr: collect(stderr),
+    })
+}
 
 impl CommandRunner for RealRunner {
     fn run(&self, program: &str, args: &[&str]) -> Result<String> {
-        let output = Command::new(program)
-            .args(args)
-            .output()
-            .with_context(|| format!("Failed to execute {} command", program))?;
+        let output = TIMINGS.time(program, args, || match self.timeout.filter(|_| is_network_command(program, args)) {
+            Some(timeout) => output_with_timeout(self, program, args, timeout),
+            None => self.command(program)
+                .args(args)
+                .output()
+                .with_context(|| format!("Failed to execute {} command", program)),
+        })?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -32,16 +222,125 @@ impl CommandRunner for RealRunner {
     }
 }
 
+/// Error fragments that indicate a failure that may go away on retry: timeouts,
+/// dropped connections, server errors, and rate limits
+const TRANSIENT_ERRORS: &[&str] = &[
+    "timed out",
+    "timeout",
+    "connection reset",
+    "connection refused",
+    "connection closed",
+    "http 500",
+    "http 502",
+    "http 503",
+    "http 504",
+    "internal server error",
+    "bad gateway",
+    "service unavailable",
+    "rate limit",
+];
+
+/// Callback invoked with a message before each retry
+type RetryCallback<'a> = Box<dyn Fn(&str) + Send + Sync + 'a>;
+
+/// Runner wrapper that retries network-bound commands with exponential backoff
+pub struct RetryRunner<'a, R: CommandRunner> {
+    inner: R,
+    /// Number of retries after the first attempt
+    retries: u32,
+    /// Delay before the first retry (doubles on each subsequent retry)
+    base_delay: Duration,
+    /// Called with a message before each retry
+    on_retry: Option<RetryCallback<'a>>,
+}
+
+impl<'a, R: CommandRunner> RetryRunner<'a, R> {
+    pub fn new(inner: R, retries: u32) -> Self {
+        Self {
+            inner,
+            retries,
+            base_delay: Duration::from_secs(1),
+            on_retry: None,
+        }
+    }
+
+    /// Set the delay before the first retry
+    #[cfg(test)]
+    pub fn with_base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Set a callback to report each retry (e.g., via the renderer)
+    pub fn on_retry(mut self, callback: impl Fn(&str) + Send + Sync + 'a) -> Self {
+        self.on_retry = Some(Box::new(callback));
+        self
+    }
+}
+
+/// Check if a command talks to the network (and is worth retrying)
+pub fn is_network_command(program: &str, args: &[&str]) -> bool {
+    matches!(
+        (program, args),
+        ("jj", ["git", "push" | "fetch", ..]) | ("git", ["push" | "fetch", ..]) | ("gh", ["pr", ..])
+    )
+}
+
+/// Check if a command is safe to run twice. A retried `gh pr create` whose first
+/// attempt got through would fail with "already exists" (or open a second PR).
+pub fn is_idempotent_command(program: &str, args: &[&str]) -> bool {
+    !matches!((program, args), ("gh", ["pr", "create" | "merge" | "comment", ..]))
+}
+
+/// Check if an error message looks like a transient failure
+pub fn is_transient_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    TRANSIENT_ERRORS.iter().any(|e| message.contains(e))
+}
+
+impl<R: CommandRunner> CommandRunner for RetryRunner<'_, R> {
+    fn run(&self, program: &str, args: &[&str]) -> Result<String> {
+        if !is_network_command(program, args) || !is_idempotent_command(program, args) {
+            return self.inner.run(program, args);
+        }
+
+        let mut attempt = 0;
+        loop {
+            match self.inner.run(program, args) {
+                Ok(output) => return Ok(output),
+                Err(e) if attempt < self.retries && is_transient_error(&e.to_string()) => {
+                    let delay = self.base_delay * 2u32.pow(attempt);
+                    attempt += 1;
+                    if let Some(on_retry) = &self.on_retry {
+                        on_retry(&format!(
+                            "`{} {}` failed, retrying in {:.1}s ({}/{})",
+                            program,
+                            args.join(" "),
+                            delay.as_secs_f64(),
+                            attempt,
+                            self.retries
+                        ));
+                    }
+                    std::thread::sleep(delay);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 pub mod mock {
     use super::*;
-    use std::collections::HashMap;
+    use std::collections::{HashMap, VecDeque};
     use std::sync::Mutex;
 
     /// Mock runner for testing - returns pre-configured responses
     pub struct MockRunner {
         /// Map from (program, args) to response
         responses: Mutex<HashMap<String, Result<String, String>>>,
+        /// One-shot responses consumed in order before falling back to `responses`
+        sequences: Mutex<HashMap<String, VecDeque<Result<String, String>>>>,
         /// Track which commands were called
         calls: Mutex<Vec<(String, Vec<String>)>>,
     }
@@ -50,6 +349,7 @@ pub mod mock {
         pub fn new() -> Self {
             Self {
                 responses: Mutex::new(HashMap::new()),
+                sequences: Mutex::new(HashMap::new()),
                 calls: Mutex::new(Vec::new()),
             }
         }
@@ -71,6 +371,17 @@ pub mod mock {
                 .insert(key.to_string(), Err(error.to_string()));
         }
 
+        /// Add one-shot responses for a command, returned in order on successive calls
+        pub fn mock_sequence(&self, key: &str, responses: &[Result<&str, &str>]) {
+            self.sequences.lock().unwrap().insert(
+                key.to_string(),
+                responses
+                    .iter()
+                    .map(|r| r.map(|s| s.to_string()).map_err(|e| e.to_string()))
+                    .collect(),
+            );
+        }
+
         /// Get all commands that were called
         pub fn get_calls(&self) -> Vec<(String, Vec<String>)> {
             self.calls.lock().unwrap().clone()
@@ -105,6 +416,17 @@ pub mod mock {
                 .collect::<Vec<_>>()
                 .join(" ");
 
+            // One-shot responses take priority
+            if let Some(next) = self
+                .sequences
+                .lock()
+                .unwrap()
+                .get_mut(&key)
+                .and_then(|queue| queue.pop_front())
+            {
+                return next.map_err(|e| anyhow::anyhow!("{}", e));
+            }
+
             // Look up response
             let responses = self.responses.lock().unwrap();
             match responses.get(&key) {
@@ -171,28 +493,210 @@ mod tests {
 
     #[test]
     fn test_real_runner_echo() {
-        let runner = RealRunner;
+        let runner = RealRunner::default();
         let result = runner.run("echo", &["hello"]).unwrap();
         assert_eq!(result.trim(), "hello");
     }
 
     #[test]
     fn test_real_runner_failure() {
-        let runner = RealRunner;
+        let runner = RealRunner::default();
         let result = runner.run("false", &[]);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_real_runner_uses_configured_tool_path() {
+        let runner = RealRunner::new(&ToolsConfig {
+            git_path: "echo".to_string(),
+            ..ToolsConfig::default()
+        });
+        let result = runner.run("git", &["hello"]);
+
+        assert_eq!(result.unwrap().trim(), "hello");
+    }
+
+    #[test]
+    fn test_jj_args_precede_subcommand() {
+        let runner = RealRunner::new(&ToolsConfig {
+            jj_path: "echo".to_string(),
+            git_path: "echo".to_string(),
+            jj_args: vec!["--config".to_string(), "user.name=CI".to_string()],
+            ..ToolsConfig::default()
+        });
+        let jj = runner.run("jj", &["log", "-r", "@", "--no-graph"]);
+        let git = runner.run("git", &["status"]);
+
+        assert_eq!(jj.unwrap().trim(), "--config user.name=CI log -r @ --no-graph");
+        // Only jj gets the extra arguments
+        assert_eq!(git.unwrap().trim(), "status");
+    }
+
+    #[test]
+    fn test_timings_record_mock_command() {
+        let runner = mock::MockRunner::new();
+        runner.mock_response("jj log -r @", "qpvuntsm");
+        let timings = Timings::new();
+
+        // Nothing is recorded until enabled
+        let _ = timings.time("jj", &["log", "-r", "@"], || runner.run("jj", &["log", "-r", "@"]));
+        assert!(timings.entries.lock().unwrap().is_none());
+        assert_eq!(timings.summary(), None);
+
+        timings.enable();
+        let output = timings.time("jj", &["log", "-r", "@"], || runner.run("jj", &["log", "-r", "@"]));
+        assert_eq!(output.unwrap(), "qpvuntsm");
+
+        let entries = timings.entries.lock().unwrap().clone().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].command, "jj log -r @");
+        let line = entries[0].to_string();
+        assert!(line.contains("ms  jj log -r @"), "{}", line);
+        assert!(timings.summary().unwrap().starts_with("1 commands in "));
+    }
+
+    #[test]
+    fn test_output_with_timeout_kills_slow_command() {
+        let started = Instant::now();
+        let err = output_with_timeout(&RealRunner::default(), "sleep", &["5"], Duration::from_millis(100)).unwrap_err();
+
+        assert!(started.elapsed() < Duration::from_secs(2));
+        let timeout = err.downcast_ref::<TimeoutError>().expect("expected a TimeoutError");
+        assert_eq!(timeout.program, "sleep");
+        assert!(err.to_string().contains("timed out"));
+    }
+
+    #[test]
+    fn test_output_with_timeout_captures_output() {
+        let output = output_with_timeout(&RealRunner::default(), "echo", &["hello"], Duration::from_secs(5)).unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "hello");
+    }
+
+    #[test]
+    fn test_real_runner_timeout_skips_local_commands() {
+        // `sleep` isn't a network command, so the timeout doesn't apply
+        let runner = RealRunner::default().with_timeout(Some(Duration::from_millis(10)));
+        assert!(runner.run("sleep", &["0.1"]).is_ok());
+    }
+
+    #[test]
+    fn test_real_runner_run_streaming() {
+        let runner = RealRunner::default();
+        runner.run_streaming("true", &[]).unwrap();
+        let err = runner.run_streaming("sh", &["-c", "exit 3"]).unwrap_err();
+        assert!(err.to_string().contains("exit status: 3"), "{}", err);
+    }
+
     #[test]
     fn test_real_runner_nonexistent_command() {
-        let runner = RealRunner;
+        let runner = RealRunner::default();
         let result = runner.run("nonexistent_command_xyz", &[]);
         assert!(result.is_err());
     }
 
+    use super::mock::MockRunner;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_retry_runner_fails_twice_then_succeeds() {
+        let mock = MockRunner::new();
+        mock.mock_sequence(
+            "jj git push --bookmark feature",
+            &[Err("connection reset"), Err("connection reset"), Ok("pushed")],
+        );
+
+        let retries_reported = AtomicUsize::new(0);
+        let runner = RetryRunner::new(mock, 3)
+            .with_base_delay(Duration::ZERO)
+            .on_retry(|_| {
+                retries_reported.fetch_add(1, Ordering::SeqCst);
+            });
+
+        let result = runner.run("jj", &["git", "push", "--bookmark", "feature"]).unwrap();
+        assert_eq!(result, "pushed");
+        assert_eq!(retries_reported.load(Ordering::SeqCst), 2);
+        assert_eq!(runner.inner.get_calls().len(), 3);
+    }
+
+    #[test]
+    fn test_retry_runner_gives_up_after_retries() {
+        let mock = MockRunner::new();
+        mock.mock_error("jj git fetch --remote origin", "connection reset");
+
+        let runner = RetryRunner::new(mock, 2).with_base_delay(Duration::ZERO);
+        assert!(runner.run("jj", &["git", "fetch", "--remote", "origin"]).is_err());
+        assert_eq!(runner.inner.get_calls().len(), 3);
+    }
+
+    #[test]
+    fn test_retry_runner_skips_non_transient_errors() {
+        let mock = MockRunner::new();
+        mock.mock_error("jj git push --bookmark feature", "Permission denied (publickey)");
+
+        let runner = RetryRunner::new(mock, 3).with_base_delay(Duration::ZERO);
+        assert!(runner.run("jj", &["git", "push", "--bookmark", "feature"]).is_err());
+        assert_eq!(runner.inner.get_calls().len(), 1);
+    }
+
+    #[test]
+    fn test_retry_runner_skips_unrecognized_errors() {
+        let mock = MockRunner::new();
+        mock.mock_error(
+            "jj git push --bookmark feature",
+            "Refusing to push a bookmark that unexpectedly moved on the remote",
+        );
+
+        let runner = RetryRunner::new(mock, 3).with_base_delay(Duration::ZERO);
+        assert!(runner.run("jj", &["git", "push", "--bookmark", "feature"]).is_err());
+        assert_eq!(runner.inner.get_calls().len(), 1);
+    }
+
+    #[test]
+    fn test_retry_runner_never_retries_pr_create() {
+        let mock = MockRunner::new();
+        mock.mock_error("gh pr create --head feature --base main", "connection reset by peer");
+
+        let runner = RetryRunner::new(mock, 3).with_base_delay(Duration::ZERO);
+        assert!(runner.run("gh", &["pr", "create", "--head", "feature", "--base", "main"]).is_err());
+        assert_eq!(runner.inner.get_calls().len(), 1);
+    }
+
+    #[test]
+    fn test_is_transient_error() {
+        assert!(is_transient_error("jj command failed: Connection reset by peer"));
+        assert!(is_transient_error("gh command failed: HTTP 502: Bad Gateway"));
+        assert!(is_transient_error("API rate limit exceeded for user"));
+        assert!(is_transient_error("git command timed out after 30s"));
+        assert!(!is_transient_error("! [rejected] feature -> feature (non-fast-forward)"));
+        assert!(!is_transient_error("a pull request for branch \"feature\" already exists"));
+        assert!(!is_transient_error("Permission denied (publickey)"));
+    }
+
+    #[test]
+    fn test_retry_runner_skips_local_commands() {
+        let mock = MockRunner::new();
+        mock.mock_error("jj rebase -d main", "conflict");
+
+        let runner = RetryRunner::new(mock, 3).with_base_delay(Duration::ZERO);
+        assert!(runner.run("jj", &["rebase", "-d", "main"]).is_err());
+        assert_eq!(runner.inner.get_calls().len(), 1);
+    }
+
+    #[test]
+    fn test_is_network_command() {
+        assert!(is_network_command("jj", &["git", "push", "--bookmark", "x"]));
+        assert!(is_network_command("jj", &["git", "fetch"]));
+        assert!(is_network_command("git", &["push", "origin", "--delete", "x"]));
+        assert!(is_network_command("gh", &["pr", "create"]));
+        assert!(!is_network_command("jj", &["git", "remote", "list"]));
+        assert!(!is_network_command("jj", &["log"]));
+        assert!(!is_network_command("gh", &["--version"]));
+    }
+
     #[test]
     fn test_run_success() {
-        let runner = RealRunner;
+        let runner = RealRunner::default();
         assert!(runner.run_success("true", &[]));
         assert!(!runner.run_success("false", &[]));
     }
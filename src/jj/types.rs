@@ -14,6 +14,71 @@ pub struct Change {
 
     #[serde(default)]
     pub bookmarks: Vec<String>,
+
+    /// Author timestamp in ISO 8601 UTC (e.g., "2024-12-20T14:03:09Z")
+    #[serde(default)]
+    pub timestamp: String,
+
+    /// True if the change has no file changes (only present when requested)
+    #[serde(default)]
+    pub is_empty: bool,
+
+    /// Committer (only present when requested)
+    #[serde(default)]
+    pub committer: Author,
+
+    /// True if the change has unresolved conflicts (only present when requested)
+    #[serde(default)]
+    pub has_conflict: bool,
+
+    /// Change IDs of the parents, two or more for a merge (only present when requested)
+    #[serde(default)]
+    pub parents: Vec<String>,
+}
+
+impl Change {
+    /// Check if this change was authored by the given email (case-insensitive)
+    pub fn is_authored_by(&self, email: &str) -> bool {
+        !email.is_empty() && self.author.email.eq_ignore_ascii_case(email.trim())
+    }
+}
+
+/// PR title for a full description: the `PR-Title:` trailer if present,
+/// otherwise the first line
+pub fn pr_title(description: &str) -> String {
+    parse_trailers(description)
+        .into_iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("PR-Title"))
+        .map(|(_, value)| value)
+        .unwrap_or_else(|| description.lines().next().unwrap_or("").to_string())
+}
+
+/// Parse `Key: value` trailers from the last paragraph of a description.
+/// As with git, the subject paragraph never counts, and every line of the
+/// trailer paragraph must be a trailer.
+pub fn parse_trailers(description: &str) -> Vec<(String, String)> {
+    let paragraphs: Vec<&str> = description
+        .trim()
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect();
+    if paragraphs.len() < 2 {
+        return Vec::new();
+    }
+
+    let mut trailers = Vec::new();
+    for line in paragraphs[paragraphs.len() - 1].lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            return Vec::new();
+        };
+        let key = key.trim_end();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Vec::new();
+        }
+        trailers.push((key.to_string(), value.trim().to_string()));
+    }
+    trailers
 }
 
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
@@ -26,7 +91,7 @@ pub struct Author {
 }
 
 /// Sync state between local bookmark and remote
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub enum BookmarkSyncState {
     /// No bookmark on this change
     #[default]
@@ -51,8 +116,26 @@ pub enum BookmarkSyncState {
     },
 }
 
-/// A change with additional status information
+/// A local bookmark tracking the remote, for listing branches outside the stack
 #[derive(Debug, Clone)]
+pub struct TrackedBookmark {
+    pub name: String,
+    /// Short change ID the local bookmark points at
+    pub change_id: String,
+    pub sync_state: BookmarkSyncState,
+}
+
+/// State of a bookmark's pull request, as `gh pr list --json state` reports it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum PrState {
+    Open,
+    Merged,
+    Closed,
+}
+
+/// A change with additional status information
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ChangeWithStatus {
     pub change: Change,
     pub bookmark: Option<String>,
@@ -61,6 +144,34 @@ pub struct ChangeWithStatus {
     pub has_remote: bool,
     /// Sync state between local and remote
     pub sync_state: BookmarkSyncState,
+    /// True if the change has no file changes
+    pub is_empty: bool,
+    /// True if the change is below the immutable boundary (immutable_heads())
+    pub is_immutable: bool,
+    /// Lines added by the change (0 unless diff stats were requested)
+    pub additions: usize,
+    /// Lines removed by the change (0 unless diff stats were requested)
+    pub deletions: usize,
+    /// State of the bookmark's PR, if it has one (None when gh wasn't asked)
+    #[serde(default)]
+    pub pr_state: Option<PrState>,
+}
+
+/// Result of a query, with a count of entries that failed to parse
+#[derive(Debug, Clone)]
+pub struct QueryResult<T = Change> {
+    pub changes: Vec<T>,
+    /// Lines of jj output that could not be parsed and were skipped
+    pub skipped: usize,
+}
+
+impl<T> Default for QueryResult<T> {
+    fn default() -> Self {
+        Self {
+            changes: Vec::new(),
+            skipped: 0,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -100,6 +211,31 @@ mod tests {
         assert_eq!(change.author.name, "");
         assert_eq!(change.author.email, "");
         assert!(change.bookmarks.is_empty());
+        assert_eq!(change.timestamp, "");
+    }
+
+    #[test]
+    fn test_change_deserialize_with_timestamp() {
+        let json = r#"{"change_id":"abc","commit_id":"def","description":"","author":{"name":"","email":""},"bookmarks":[],"timestamp":"2024-12-20T14:03:09Z"}"#;
+        let change: Change = serde_json::from_str(json).unwrap();
+        assert_eq!(change.timestamp, "2024-12-20T14:03:09Z");
+    }
+
+    #[test]
+    fn test_change_deserialize_extra_fields() {
+        let json = r#"{"change_id":"abc","commit_id":"def","description":"","author":{"name":"A","email":"a@test.com"},"bookmarks":[],"timestamp":"2024-12-20T14:03:09Z","is_empty":true,"committer":{"name":"C","email":"c@test.com"}}"#;
+        let change: Change = serde_json::from_str(json).unwrap();
+        assert!(change.is_empty);
+        assert_eq!(change.committer.name, "C");
+        assert_eq!(change.committer.email, "c@test.com");
+    }
+
+    #[test]
+    fn test_change_deserialize_without_extra_fields() {
+        let json = r#"{"change_id":"abc","commit_id":"def"}"#;
+        let change: Change = serde_json::from_str(json).unwrap();
+        assert!(!change.is_empty);
+        assert_eq!(change.committer.email, "");
     }
 
     #[test]
@@ -127,6 +263,11 @@ mod tests {
                 email: "test@test.com".to_string(),
             },
             bookmarks: vec!["branch1".to_string()],
+            timestamp: "2024-12-20T14:03:09Z".to_string(),
+            is_empty: false,
+            committer: Author::default(),
+            has_conflict: false,
+            parents: Vec::new(),
         };
 
         let json = serde_json::to_string(&change).unwrap();
@@ -136,6 +277,42 @@ mod tests {
         assert_eq!(change.commit_id, parsed.commit_id);
         assert_eq!(change.description, parsed.description);
         assert_eq!(change.bookmarks, parsed.bookmarks);
+        assert_eq!(change.timestamp, parsed.timestamp);
+    }
+
+    #[test]
+    fn test_parse_trailers_absent() {
+        assert!(parse_trailers("Add feature").is_empty());
+        assert!(parse_trailers("Add feature\n\nJust a body paragraph.").is_empty());
+        // A subject that looks like a trailer isn't one
+        assert!(parse_trailers("feat: add feature").is_empty());
+    }
+
+    #[test]
+    fn test_parse_trailers_multiple() {
+        let description = "feat: add feature\n\nBody text.\n\nPR-Title: Add a nicer feature\nReviewed-by: Alice <alice@example.com>\n";
+        assert_eq!(
+            parse_trailers(description),
+            vec![
+                ("PR-Title".to_string(), "Add a nicer feature".to_string()),
+                ("Reviewed-by".to_string(), "Alice <alice@example.com>".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_trailers_mixed_paragraph_ignored() {
+        let description = "Subject\n\nPR-Title: Nicer\nnot a trailer line";
+        assert!(parse_trailers(description).is_empty());
+    }
+
+    #[test]
+    fn test_pr_title_override() {
+        assert_eq!(pr_title("feat: add feature\n\nBody text."), "feat: add feature");
+        assert_eq!(
+            pr_title("feat: add feature\n\nBody text.\n\npr-title: Add feature"),
+            "Add feature"
+        );
     }
 
     #[test]
@@ -167,6 +344,11 @@ mod tests {
             description: "Test".to_string(),
             author: Author::default(),
             bookmarks: vec![],
+            timestamp: String::new(),
+            is_empty: false,
+            committer: Author::default(),
+            has_conflict: false,
+            parents: Vec::new(),
         };
 
         let status = ChangeWithStatus {
@@ -175,6 +357,11 @@ mod tests {
             is_working: true,
             has_remote: true,
             sync_state: BookmarkSyncState::Ahead { count: 2 },
+            is_empty: false,
+            is_immutable: false,
+            additions: 0,
+            deletions: 0,
+            pr_state: None,
         };
 
         assert_eq!(status.bookmark, Some("feature".to_string()));
@@ -183,6 +370,24 @@ mod tests {
         assert!(matches!(status.sync_state, BookmarkSyncState::Ahead { count: 2 }));
     }
 
+    #[test]
+    fn test_is_authored_by_case_insensitive() {
+        let json = r#"{"change_id":"abc","commit_id":"def","author":{"name":"Test","email":"Test.User@Example.com"}}"#;
+        let change: Change = serde_json::from_str(json).unwrap();
+        assert!(change.is_authored_by("test.user@example.com"));
+        assert!(change.is_authored_by("TEST.USER@EXAMPLE.COM"));
+        assert!(change.is_authored_by("test.user@example.com\n"));
+        assert!(!change.is_authored_by("other@example.com"));
+    }
+
+    #[test]
+    fn test_is_authored_by_empty_email() {
+        let json = r#"{"change_id":"abc","commit_id":"def"}"#;
+        let change: Change = serde_json::from_str(json).unwrap();
+        // An unknown current user never matches, even against an empty author
+        assert!(!change.is_authored_by(""));
+    }
+
     #[test]
     fn test_author_default() {
         let author = Author::default();
@@ -348,6 +553,11 @@ mod tests {
             description: "Test".to_string(),
             author: Author::default(),
             bookmarks: vec![],
+            timestamp: String::new(),
+            is_empty: false,
+            committer: Author::default(),
+            has_conflict: false,
+            parents: Vec::new(),
         };
         let status = ChangeWithStatus {
             change,
@@ -355,6 +565,11 @@ mod tests {
             is_working: false,
             has_remote: false,
             sync_state: BookmarkSyncState::NoBookmark,
+            is_empty: true,
+            is_immutable: true,
+            additions: 0,
+            deletions: 0,
+            pr_state: None,
         };
         assert!(status.bookmark.is_none());
         assert!(matches!(status.sync_state, BookmarkSyncState::NoBookmark));
@@ -1,12 +1,17 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 
+mod capabilities;
 mod commands;
 mod config;
 mod jj;
+mod state;
 mod ui;
 
+use capabilities::Capabilities;
 use config::Config;
+use jj::RealRunner;
+use ui::ColorChoice;
 
 #[derive(Parser)]
 #[command(name = "jf")]
@@ -14,6 +19,34 @@ use config::Config;
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// When to use colors
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Skip all network access (also JF_OFFLINE=1)
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Measure the stack from this revision instead of the primary branch
+    #[arg(long, global = true, value_name = "REV")]
+    base: Option<String>,
+
+    /// Color theme for this run (overrides [display] theme)
+    #[arg(long, global = true, value_name = "NAME")]
+    theme: Option<String>,
+
+    /// Icon set for this run: unicode, ascii, nerdfont, or auto (overrides [display] icons)
+    #[arg(long, global = true, value_name = "SET")]
+    icons: Option<String>,
+
+    /// Print how long each jj/gh/git command takes, and the total, to stderr
+    #[arg(long, global = true)]
+    timings: bool,
+
+    /// Only print errors and requested data, not progress messages or the stack
+    #[arg(short, long, global = true)]
+    quiet: bool,
 }
 
 #[derive(Subcommand)]
@@ -33,8 +66,61 @@ enum Commands {
         local: bool,
     },
 
+    /// Show the effective configuration and where each value comes from
+    Config {
+        /// Only print which config files were found
+        #[arg(long)]
+        path: bool,
+    },
+
     /// Show your stack with PR status
-    Status,
+    Status {
+        /// Only show changes authored by you (user.email)
+        #[arg(long)]
+        mine: bool,
+
+        /// Show sync state against this remote instead of the configured one
+        #[arg(long)]
+        remote: Option<String>,
+
+        /// One line per change, without the box
+        #[arg(long)]
+        compact: bool,
+
+        /// Text only: no icons, box drawing, or color (for screen readers and logs)
+        #[arg(long, conflicts_with = "compact")]
+        plain: bool,
+
+        /// Show +added/-removed line counts per change
+        #[arg(long)]
+        stat: bool,
+
+        /// Show each change's full description under its subject
+        #[arg(long, conflicts_with_all = ["compact", "plain"])]
+        full: bool,
+
+        /// Only list each bookmarked change's PR and CI result
+        #[arg(long, conflicts_with_all = ["compact", "plain", "stat", "full"])]
+        ci: bool,
+
+        /// Group changes under the bookmark (PR) they land with
+        #[arg(long, conflicts_with_all = ["compact", "plain", "ci"])]
+        group: bool,
+
+        /// Tint each change by author, with a legend under the stack
+        #[arg(long, conflicts_with_all = ["compact", "plain", "ci"])]
+        author_colors: bool,
+
+        /// Also list tracked remote branches outside your stack (e.g., teammates')
+        #[arg(long, conflicts_with_all = ["plain", "ci"])]
+        all: bool,
+
+        /// One line per change from a template, e.g. "{short_id} {sync} {bookmark} {desc}"
+        /// (placeholders: short_id, change_id, commit_id, bookmark, sync, desc, author)
+        #[arg(long, value_name = "TEMPLATE", value_parser = ui::LineFormat::parse,
+              conflicts_with_all = ["compact", "plain", "ci", "full", "group", "author_colors", "all"])]
+        format: Option<ui::LineFormat>,
+    },
 
     /// Push changes to GitHub, creating or updating PRs
     Push {
@@ -42,8 +128,8 @@ enum Commands {
         #[arg(short, long)]
         revision: Option<String>,
 
-        /// Bookmark name for the change (required for new PRs)
-        #[arg(short, long)]
+        /// Bookmark name for the single unbookmarked change selected by --revision
+        #[arg(short, long, requires = "revision")]
         bookmark: Option<String>,
 
         /// Force squash-style push (override config)
@@ -57,6 +143,62 @@ enum Commands {
         /// Dry run - show what would be done
         #[arg(short = 'n', long)]
         dry_run: bool,
+
+        /// With --revision, also push unpushed stack ancestors down to trunk
+        #[arg(long, requires = "revision")]
+        with_ancestors: bool,
+
+        /// Force-push even over other authors' commits on the remote bookmark
+        #[arg(short, long)]
+        force: bool,
+
+        /// With --dry-run, print the plan as JSON
+        #[arg(long, requires = "dry_run")]
+        json: bool,
+
+        /// Open created or existing PRs in the browser when done
+        #[arg(long, overrides_with = "no_open")]
+        open: bool,
+
+        /// Don't open PRs in the browser (default)
+        #[arg(long, overrides_with = "open")]
+        no_open: bool,
+
+        /// Skip the [hooks] pre_push command and the conventional-commit check
+        #[arg(long)]
+        no_verify: bool,
+
+        /// Largest stack to push without confirmation (override config)
+        #[arg(long, value_name = "N")]
+        max_stack: Option<usize>,
+
+        /// For append-style pushes, build new PRs from all their commits' descriptions
+        #[arg(long, conflicts_with = "squash")]
+        squash_descriptions: bool,
+
+        /// Ask for all missing bookmark names up front, then push
+        #[arg(short, long, conflicts_with_all = ["bookmark", "dry_run"])]
+        interactive: bool,
+
+        /// Push and track bookmarks, but don't create or look up PRs yet
+        #[arg(long, conflicts_with = "open")]
+        no_pr: bool,
+
+        /// Prompt for descriptions of undescribed changes instead of refusing to push
+        #[arg(long, conflicts_with = "dry_run")]
+        amend_descriptions: bool,
+
+        /// Refuse to push a --revision that selects changes outside the stack
+        #[arg(long, requires = "revision")]
+        stack_only: bool,
+
+        /// Push bottom-up, basing each new PR on the bookmark of the change below it
+        #[arg(long)]
+        base_auto_stack: bool,
+
+        /// Edit each new PR's body in $EDITOR before creating it
+        #[arg(long, conflicts_with_all = ["dry_run", "no_pr"])]
+        edit: bool,
     },
 
     /// Clean up after PRs are merged
@@ -67,6 +209,18 @@ enum Commands {
         /// Dry run - show what would be done
         #[arg(short = 'n', long)]
         dry_run: bool,
+
+        /// Keep empty changes instead of abandoning them (override config)
+        #[arg(long)]
+        no_prune: bool,
+
+        /// Finish a land that stopped on rebase conflicts
+        #[arg(long = "continue", conflicts_with_all = ["bookmark", "dry_run"])]
+        continue_land: bool,
+
+        /// Print a JSON report of what was cleaned up
+        #[arg(long, conflicts_with = "dry_run")]
+        json: bool,
     },
 
     /// Pull from remote and rebase your stack
@@ -74,12 +228,75 @@ enum Commands {
         /// Remote to pull from
         #[arg(short, long)]
         remote: Option<String>,
+
+        /// Only fetch; don't rebase the stack
+        #[arg(long)]
+        no_rebase: bool,
+
+        /// Delete local bookmarks whose remote branch was deleted
+        #[arg(long, alias = "remote-prune")]
+        prune: bool,
+    },
+
+    /// Pull, rebase, and push bookmarked changes, refreshing PR stack context
+    Sync {
+        /// Dry run - show what would be done
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+    },
+
+    /// Move the working copy to a change in the stack (jj edit)
+    Edit {
+        /// Change ID or bookmark to edit
+        revision: String,
+    },
+
+    /// Show the diff of a change (jj diff)
+    Diff {
+        /// Revision to diff (default: @)
+        revision: Option<String>,
+
+        /// Show a summary of changed files instead of the full diff
+        #[arg(long)]
+        stat: bool,
+    },
+
+    /// Create a new change on top of a revision (default: @)
+    New {
+        /// Revision to create the new change on top of
+        revision: Option<String>,
+
+        /// Description for the new change
+        #[arg(short, long)]
+        message: Option<String>,
+    },
+
+    /// Shelve the working change and move to a new change on trunk
+    Stash,
+
+    /// Return to the change shelved by `jf stash`
+    Restore,
+
+    /// Abandon empty, description-less changes in the stack
+    Clean {
+        /// Show what would be abandoned without abandoning it
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+    },
+
+    /// Rebase the stack onto another branch or change (jj rebase -d)
+    Rebase {
+        /// Destination revision (bookmark, change ID, or revset)
+        dest: String,
+
+        /// Rebase this change and its descendants instead of the whole stack
+        #[arg(short, long)]
+        source: Option<String>,
     },
 
     /// Reorder changes in the stack
     Reorder {
         /// Changes to reorder (in desired order, e.g., "abc def ghi")
-        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         changes: Vec<String>,
 
         /// Invert (reverse) the stack order
@@ -89,6 +306,32 @@ enum Commands {
         /// Starting change for --invert (default: entire stack)
         #[arg(short, long)]
         from: Option<String>,
+
+        /// Show the planned rebases without running them
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+
+        /// Move the single given change directly on top of this one
+        #[arg(long, value_name = "CHANGE", conflicts_with_all = ["invert", "from", "before"])]
+        after: Option<String>,
+
+        /// Move the single given change directly below this one
+        #[arg(long, value_name = "CHANGE", conflicts_with_all = ["invert", "from"])]
+        before: Option<String>,
+    },
+
+    /// Manage remote tracking for bookmarks
+    Bookmark {
+        #[command(subcommand)]
+        command: BookmarkCommand,
+    },
+
+    /// Rename existing bookmarks under the configured prefix (dry run unless --apply)
+    #[command(alias = "import")]
+    Adopt {
+        /// Rename and track the bookmarks instead of listing them
+        #[arg(long)]
+        apply: bool,
     },
 
     /// Sync work-in-progress between machines
@@ -96,63 +339,250 @@ enum Commands {
         /// Subcommand: push, pull, clean (or none for status)
         subcommand: Option<String>,
 
-        /// Force overwrite (push) or delete without PR check (clean)
+        /// Overwrite the remote wip if unchanged since last seen (push), or delete without PR check (clean)
         #[arg(short, long)]
         force: bool,
+
+        /// Bookmark local stack changes aside instead of refusing to pull (pull)
+        #[arg(long)]
+        stash: bool,
     },
 }
 
+#[derive(Subcommand)]
+enum BookmarkCommand {
+    /// Track <name>@<remote> so its sync state shows in the stack
+    Track {
+        /// Bookmark name (the configured prefix is added if missing)
+        name: String,
+    },
+
+    /// Stop tracking <name>@<remote>
+    Untrack {
+        /// Bookmark name (the configured prefix is added if missing)
+        name: String,
+    },
+}
+
+/// The command a bare `jf` runs, parsed like `jf <default_command>` so it gets
+/// the same defaults as typing it
+fn default_command(config: &Config) -> Commands {
+    Cli::parse_from(["jf", config.general.default_command.as_str()])
+        .command
+        .expect("default_command is always a subcommand")
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    ui::apply_color_choice(cli.color);
+    if cli.timings {
+        jj::enable_timings();
+    }
+    let offline = cli.offline || std::env::var_os("JF_OFFLINE").is_some_and(|v| !v.is_empty() && v != "0");
 
     match cli.command {
         Some(Commands::Init { defaults, github, local }) => {
-            // Init doesn't need existing config
-            commands::init::run(defaults, github, local)?
-        }
-        None => {
-            // No command = run status
-            let config = Config::load_or_default()?;
-            commands::status::run(&config)?
+            // Init doesn't need existing config, but honors [tools] if there is one
+            let tools = Config::load_or_default().map(|c| c.tools).unwrap_or_default();
+            let runner = RealRunner::new(&tools);
+            let caps = Capabilities::probe(&runner);
+            commands::init::run(&runner, &caps, defaults, github, local)?
         }
-        Some(cmd) => {
+        cmd => {
             // Other commands load config normally
-            let config = Config::load_or_default()?;
+            let mut config = Config::load_or_default()?;
+            // No command = run `[general] default_command` (status unless configured)
+            let cmd = cmd.unwrap_or_else(|| default_command(&config));
+            config.offline = offline;
+            config.quiet = cli.quiet;
+            if cli.base.is_some() {
+                config.remote.base_override = cli.base;
+            }
+            for warning in config.override_display(cli.theme.as_deref(), cli.icons.as_deref()) {
+                ui::Renderer::from_config(&config).warning(&warning);
+            }
+            config.validate_base()?;
+            // Old jj still works, just slower; say so once, up front
+            if let Some(warning) = jj::old_jj_warning(jj::jj_version(&RealRunner::new(&config.tools))) {
+                ui::Renderer::from_config(&config).warning(&warning);
+            }
+
+            // Commands that talk to the remote can't run offline
+            match &cmd {
+                Commands::Push { .. } => config.require_online("push")?,
+                Commands::Pull { .. } => config.require_online("pull")?,
+                Commands::Land { .. } => config.require_online("land")?,
+                Commands::Sync { .. } => config.require_online("sync")?,
+                Commands::Wip { subcommand: Some(_), .. } => config.require_online("wip")?,
+                _ => {}
+            }
 
             match cmd {
                 Commands::Init { .. } => unreachable!(),
-                Commands::Status => commands::status::run(&config)?,
+                Commands::Config { path } => commands::config::run(&config, path)?,
+                Commands::Status {
+                    mine,
+                    remote,
+                    compact,
+                    plain,
+                    stat,
+                    full,
+                    ci,
+                    group,
+                    author_colors,
+                    all,
+                    format,
+                } => {
+                    let options = commands::status::StatusOptions {
+                        mine,
+                        remote: remote.as_deref(),
+                        compact,
+                        plain,
+                        stat,
+                        full,
+                        ci,
+                        group,
+                        author_colors,
+                        all,
+                        format: format.as_ref(),
+                    };
+                    let caps = Capabilities::probe(&RealRunner::new(&config.tools));
+                    commands::status::run(&config, &caps, &options)?
+                }
                 Commands::Push {
                     revision,
                     bookmark,
                     squash,
                     append,
                     dry_run,
+                    with_ancestors,
+                    force,
+                    json,
+                    open,
+                    no_open: _,
+                    no_verify,
+                    max_stack,
+                    interactive,
+                    squash_descriptions,
+                    no_pr,
+                    amend_descriptions,
+                    stack_only,
+                    base_auto_stack,
+                    edit,
                 } => {
-                    commands::push::run(
-                        &config,
-                        revision.as_deref(),
-                        bookmark.as_deref(),
-                        squash,
-                        append,
+                    let options = commands::push::PushOptions {
+                        revision: revision.as_deref(),
+                        bookmark: bookmark.as_deref(),
+                        force_squash: squash,
+                        force_append: append,
                         dry_run,
-                    )?
+                        with_ancestors,
+                        force,
+                        json,
+                        open,
+                        no_verify,
+                        max_stack,
+                        interactive,
+                        squash_descriptions,
+                        no_pr,
+                        amend_descriptions,
+                        stack_only,
+                        base_auto_stack,
+                        edit,
+                    };
+                    let caps = Capabilities::probe(&RealRunner::new(&config.tools));
+                    commands::push::run(&config, &caps, &options)?
                 }
-                Commands::Land { bookmark, dry_run } => {
-                    commands::land::run(&config, bookmark.as_deref(), dry_run)?
+                Commands::Land {
+                    bookmark,
+                    dry_run,
+                    no_prune,
+                    continue_land,
+                    json,
+                } => {
+                    let options = commands::land::LandOptions {
+                        bookmark: bookmark.as_deref(),
+                        dry_run,
+                        no_prune,
+                        continue_land,
+                        json,
+                    };
+                    commands::land::run(&config, &options)?
+                }
+                Commands::Pull { remote, no_rebase, prune } => {
+                    commands::pull::run(&config, remote.as_deref(), no_rebase, prune)?
+                }
+                Commands::Sync { dry_run } => {
+                    let caps = Capabilities::probe(&RealRunner::new(&config.tools));
+                    commands::sync::run(&config, &caps, dry_run)?
+                }
+                Commands::Edit { revision } => commands::edit::run(&config, &revision)?,
+                Commands::Diff { revision, stat } => {
+                    commands::diff::run(&config, revision.as_deref(), stat)?
                 }
-                Commands::Pull { remote } => {
-                    commands::pull::run(&config, remote.as_deref())?
+                Commands::New { revision, message } => {
+                    commands::new::run(&config, revision.as_deref(), message.as_deref())?
                 }
-                Commands::Reorder { changes, invert, from } => {
-                    commands::reorder::run(&config, changes, invert, from.as_deref())?
+                Commands::Stash => commands::stash::run(&config)?,
+                Commands::Restore => commands::stash::restore(&config)?,
+                Commands::Clean { dry_run } => commands::clean::run(&config, dry_run)?,
+                Commands::Rebase { dest, source } => {
+                    commands::rebase::run(&config, &dest, source.as_deref())?
                 }
-                Commands::Wip { subcommand, force } => {
-                    commands::wip::run(&config, subcommand.as_deref(), force)?
+                Commands::Reorder { changes, invert, from, dry_run, after, before } => {
+                    use commands::reorder::Placement;
+                    let placement = after
+                        .as_deref()
+                        .map(Placement::After)
+                        .or(before.as_deref().map(Placement::Before));
+                    commands::reorder::run(&config, changes, invert, from.as_deref(), placement, dry_run)?
+                }
+                Commands::Bookmark { command } => match command {
+                    BookmarkCommand::Track { name } => commands::bookmark::track(&config, &name)?,
+                    BookmarkCommand::Untrack { name } => commands::bookmark::untrack(&config, &name)?,
+                },
+                Commands::Adopt { apply } => commands::bookmark::adopt(&config, apply)?,
+                Commands::Wip { subcommand, force, stash } => {
+                    commands::wip::run(&config, subcommand.as_deref(), force, stash)?
                 }
             }
         }
     }
 
+    jj::print_timings_summary();
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_command_dispatch() {
+        assert!(matches!(default_command(&Config::default()), Commands::Status { .. }));
+
+        let config = Config::from_toml("[general]\ndefault_command = \"diff\"").unwrap();
+        assert!(matches!(default_command(&config), Commands::Diff { revision: None, stat: false }));
+
+        let config = Config::from_toml("[general]\ndefault_command = \"config\"").unwrap();
+        assert!(matches!(default_command(&config), Commands::Config { path: false }));
+    }
+
+    #[test]
+    fn test_reorder_relative_move_parses_after_change() {
+        let cli = Cli::try_parse_from(["jf", "reorder", "abc", "--after", "def"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Reorder { ref changes, after: Some(ref after), before: None, .. })
+                if changes == &["abc"] && after == "def"
+        ));
+
+        let cli = Cli::try_parse_from(["jf", "reorder", "abc", "def", "-n"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Reorder { ref changes, dry_run: true, after: None, .. }) if changes.len() == 2
+        ));
+
+        assert!(Cli::try_parse_from(["jf", "reorder", "abc", "--after", "def", "--before", "ghi"]).is_err());
+    }
+}
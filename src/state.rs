@@ -0,0 +1,255 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Directory inside the repo root holding jflow's own files
+const STATE_DIR: &str = ".jflow";
+
+/// Written into a fresh state directory so git never picks it up
+const GITIGNORE: &str = "# Created by jflow; local state, never commit\n*\n";
+
+/// How long to wait for another jf process to release the state lock
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+const LOCK_POLL: Duration = Duration::from_millis(50);
+
+/// Per-repository jflow state, stored in `.jflow/state.toml` at the repo root
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct State {
+    /// Change ID shelved by `jf stash`, waiting for `jf restore`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stashed_change: Option<String>,
+
+    /// Merged bookmarks `jf land` stopped before deleting, waiting for `jf land --continue`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub land_pending: Vec<String>,
+
+    /// Last-known remote commit of each wip bookmark, the lease `jf wip push --force` checks
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub wip_remote_tips: BTreeMap<String, String>,
+}
+
+impl State {
+    /// Path to the state file for the current repository (`.jflow/state.toml`),
+    /// creating the state directory on first use
+    pub fn path() -> Result<PathBuf> {
+        let cwd = std::env::current_dir()?;
+        let root = find_repo_root(&cwd).context("Not inside a jj repository")?;
+        let path = state_dir(&root)?.join("state.toml");
+
+        // State used to live under .jj; carry it over once
+        let legacy = root.join(".jj").join("jflow-state.toml");
+        if legacy.exists() && !path.exists() {
+            std::fs::rename(&legacy, &path)
+                .with_context(|| format!("Failed to move {:?} to {:?}", legacy, path))?;
+        }
+        Ok(path)
+    }
+
+    /// Take the lock guarding the state file at `path`, held until the guard drops.
+    /// Callers that load, modify and save hold it across all three.
+    pub fn lock(path: &Path) -> Result<StateLock> {
+        StateLock::acquire(&path.with_extension("lock"), LOCK_TIMEOUT)
+    }
+
+    /// Load state from `path`, or the default state if the file doesn't exist
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read state file: {:?}", path))?;
+        toml::from_str(&contents).with_context(|| format!("Failed to parse state file: {:?}", path))
+    }
+
+    /// Write state to `path`, removing the file when there's nothing to keep
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if *self == Self::default() {
+            if path.exists() {
+                std::fs::remove_file(path)
+                    .with_context(|| format!("Failed to remove state file: {:?}", path))?;
+            }
+            return Ok(());
+        }
+        let contents = toml::to_string(self).context("Failed to serialize state")?;
+        std::fs::write(path, contents).with_context(|| format!("Failed to write state file: {:?}", path))
+    }
+}
+
+/// Find the repository root: the nearest directory at or above `start` with a `.jj`
+/// directory (walking up like the local config lookup)
+pub fn find_repo_root(start: &Path) -> Option<PathBuf> {
+    start.ancestors().find(|dir| dir.join(".jj").is_dir()).map(Path::to_path_buf)
+}
+
+/// The `.jflow/` directory under `root`, created with a catch-all `.gitignore` if missing
+pub fn state_dir(root: &Path) -> Result<PathBuf> {
+    let dir = root.join(STATE_DIR);
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create state directory: {:?}", dir))?;
+
+    let gitignore = dir.join(".gitignore");
+    if !gitignore.exists() {
+        std::fs::write(&gitignore, GITIGNORE)
+            .with_context(|| format!("Failed to write {:?}", gitignore))?;
+    }
+    Ok(dir)
+}
+
+/// Exclusive lock on the state file, a lock file removed when the guard drops
+#[derive(Debug)]
+pub struct StateLock {
+    path: PathBuf,
+}
+
+impl StateLock {
+    /// Create the lock file at `path`, polling while another process holds it
+    fn acquire(path: &Path, timeout: Duration) -> Result<Self> {
+        let start = Instant::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(path) {
+                Ok(_) => return Ok(Self { path: path.to_path_buf() }),
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    if start.elapsed() >= timeout {
+                        anyhow::bail!(
+                            "Another jf command is using the repository state. \
+                             If none is running, remove {:?}",
+                            path
+                        );
+                    }
+                    std::thread::sleep(LOCK_POLL);
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to create lock file: {:?}", path));
+                }
+            }
+        }
+    }
+}
+
+impl Drop for StateLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_load_missing_file_is_default() {
+        let dir = tempdir().unwrap();
+        let state = State::load_from(&dir.path().join("jflow-state.toml")).unwrap();
+        assert_eq!(state, State::default());
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("jflow-state.toml");
+
+        let state = State {
+            stashed_change: Some("qpvuntsmwlqt".to_string()),
+            land_pending: vec!["feature".to_string()],
+            wip_remote_tips: BTreeMap::from([("wip/alice".to_string(), "0123abcd".to_string())]),
+        };
+        state.save_to(&path).unwrap();
+
+        let loaded = State::load_from(&path).unwrap();
+        assert_eq!(loaded, state);
+    }
+
+    #[test]
+    fn test_save_default_removes_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("jflow-state.toml");
+
+        State {
+            stashed_change: Some("qpvuntsmwlqt".to_string()),
+            ..State::default()
+        }
+        .save_to(&path)
+        .unwrap();
+        assert!(path.exists());
+
+        State::default().save_to(&path).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_load_invalid_file_errors() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("jflow-state.toml");
+        std::fs::write(&path, "stashed_change = [").unwrap();
+
+        assert!(State::load_from(&path).is_err());
+    }
+
+    #[test]
+    fn test_find_repo_root_from_nested_dir() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join(".jj")).unwrap();
+        let nested = dir.path().join("src").join("deep");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        assert_eq!(find_repo_root(&nested).as_deref(), Some(dir.path()));
+        assert_eq!(find_repo_root(dir.path()).as_deref(), Some(dir.path()));
+    }
+
+    #[test]
+    fn test_find_repo_root_outside_repo() {
+        let dir = tempdir().unwrap();
+        // A stray .jj file isn't a repo
+        std::fs::write(dir.path().join(".jj"), "").unwrap();
+        assert_eq!(find_repo_root(dir.path()), None);
+    }
+
+    #[test]
+    fn test_state_dir_is_gitignored() {
+        let dir = tempdir().unwrap();
+        let state = state_dir(dir.path()).unwrap();
+
+        assert_eq!(state, dir.path().join(".jflow"));
+        let gitignore = std::fs::read_to_string(state.join(".gitignore")).unwrap();
+        assert!(gitignore.lines().any(|line| line == "*"));
+
+        // An edited .gitignore is left alone
+        std::fs::write(state.join(".gitignore"), "state.toml\n").unwrap();
+        state_dir(dir.path()).unwrap();
+        assert_eq!(std::fs::read_to_string(state.join(".gitignore")).unwrap(), "state.toml\n");
+    }
+
+    #[test]
+    fn test_roundtrip_in_state_dir() {
+        let dir = tempdir().unwrap();
+        let path = state_dir(dir.path()).unwrap().join("state.toml");
+
+        let state = State {
+            land_pending: vec!["feature".to_string()],
+            ..State::default()
+        };
+        let lock = State::lock(&path).unwrap();
+        state.save_to(&path).unwrap();
+        drop(lock);
+
+        assert_eq!(State::load_from(&path).unwrap(), state);
+    }
+
+    #[test]
+    fn test_lock_is_exclusive_until_dropped() {
+        let dir = tempdir().unwrap();
+        let lock_path = dir.path().join("state.lock");
+
+        let held = StateLock::acquire(&lock_path, Duration::ZERO).unwrap();
+        assert!(lock_path.exists());
+        let err = StateLock::acquire(&lock_path, Duration::ZERO).unwrap_err();
+        assert!(err.to_string().contains("Another jf command"));
+
+        drop(held);
+        assert!(!lock_path.exists());
+        assert!(StateLock::acquire(&lock_path, Duration::ZERO).is_ok());
+    }
+}
@@ -1,4 +1,34 @@
 use colored::Color;
+use std::io::IsTerminal;
+
+/// When to emit colored output (`--color`), following git's convention
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// Color when stdout is a terminal and `NO_COLOR` isn't set
+    #[default]
+    Auto,
+    /// Always color, even when piped
+    Always,
+    /// Never color
+    Never,
+}
+
+/// Decide whether to colorize. An explicit `always`/`never` beats `NO_COLOR`.
+pub fn should_colorize(choice: ColorChoice, no_color: bool, is_tty: bool) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => !no_color && is_tty,
+    }
+}
+
+/// Apply `choice` to all subsequent colored output
+pub fn apply_color_choice(choice: ColorChoice) {
+    // NO_COLOR only counts when set to a non-empty value
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let is_tty = std::io::stdout().is_terminal();
+    colored::control::set_override(should_colorize(choice, no_color, is_tty));
+}
 
 /// Color theme for terminal output
 pub struct Theme {
@@ -71,6 +101,40 @@ pub const DRACULA: Theme = Theme {
     overlay: Color::TrueColor { r: 98, g: 114, b: 164 },  // #6272a4
 };
 
+/// Gruvbox (dark) theme
+pub const GRUVBOX: Theme = Theme {
+    base: Color::TrueColor { r: 40, g: 40, b: 40 },       // #282828
+    text: Color::TrueColor { r: 235, g: 219, b: 178 },    // #ebdbb2
+    subtext: Color::TrueColor { r: 213, g: 196, b: 161 }, // #d5c4a1
+    
+    green: Color::TrueColor { r: 184, g: 187, b: 38 },    // #b8bb26
+    yellow: Color::TrueColor { r: 250, g: 189, b: 47 },   // #fabd2f
+    red: Color::TrueColor { r: 251, g: 73, b: 52 },       // #fb4934
+    blue: Color::TrueColor { r: 131, g: 165, b: 152 },    // #83a598
+    mauve: Color::TrueColor { r: 211, g: 134, b: 155 },   // #d3869b
+    teal: Color::TrueColor { r: 142, g: 192, b: 124 },    // #8ec07c
+    
+    surface: Color::TrueColor { r: 60, g: 56, b: 54 },    // #3c3836
+    overlay: Color::TrueColor { r: 146, g: 131, b: 116 }, // #928374
+};
+
+/// Solarized (dark) theme
+pub const SOLARIZED: Theme = Theme {
+    base: Color::TrueColor { r: 0, g: 43, b: 54 },        // #002b36
+    text: Color::TrueColor { r: 131, g: 148, b: 150 },    // #839496
+    subtext: Color::TrueColor { r: 88, g: 110, b: 117 },  // #586e75
+    
+    green: Color::TrueColor { r: 133, g: 153, b: 0 },     // #859900
+    yellow: Color::TrueColor { r: 181, g: 137, b: 0 },    // #b58900
+    red: Color::TrueColor { r: 220, g: 50, b: 47 },       // #dc322f
+    blue: Color::TrueColor { r: 38, g: 139, b: 210 },     // #268bd2
+    mauve: Color::TrueColor { r: 108, g: 113, b: 196 },   // #6c71c4
+    teal: Color::TrueColor { r: 42, g: 161, b: 152 },     // #2aa198
+    
+    surface: Color::TrueColor { r: 7, g: 54, b: 66 },     // #073642
+    overlay: Color::TrueColor { r: 101, g: 123, b: 131 }, // #657b83
+};
+
 /// Default theme (uses terminal colors)
 pub const DEFAULT: Theme = Theme {
     base: Color::Black,
@@ -88,11 +152,16 @@ pub const DEFAULT: Theme = Theme {
     overlay: Color::BrightBlack,
 };
 
+/// Theme names `get_theme` knows (anything else falls back to default)
+pub const THEME_NAMES: &[&str] = &["catppuccin", "nord", "dracula", "gruvbox", "solarized", "default"];
+
 pub fn get_theme(name: &str) -> &'static Theme {
     match name {
         "catppuccin" => &CATPPUCCIN,
         "nord" => &NORD,
         "dracula" => &DRACULA,
+        "gruvbox" => &GRUVBOX,
+        "solarized" => &SOLARIZED,
         _ => &DEFAULT,
     }
 }
@@ -122,6 +191,20 @@ mod tests {
         assert!(matches!(theme.green, Color::TrueColor { r: 80, g: 250, b: 123 }));
     }
 
+    #[test]
+    fn test_get_theme_gruvbox() {
+        let theme = get_theme("gruvbox");
+        // Gruvbox green is #b8bb26
+        assert!(matches!(theme.green, Color::TrueColor { r: 184, g: 187, b: 38 }));
+    }
+
+    #[test]
+    fn test_get_theme_solarized() {
+        let theme = get_theme("solarized");
+        // Solarized green is #859900
+        assert!(matches!(theme.green, Color::TrueColor { r: 133, g: 153, b: 0 }));
+    }
+
     #[test]
     fn test_get_theme_default() {
         let theme = get_theme("default");
@@ -134,10 +217,29 @@ mod tests {
         assert!(matches!(theme.green, Color::Green));
     }
 
+    #[test]
+    fn test_should_colorize_auto() {
+        assert!(should_colorize(ColorChoice::Auto, false, true));
+        assert!(!should_colorize(ColorChoice::Auto, false, false));
+        assert!(!should_colorize(ColorChoice::Auto, true, true));
+    }
+
+    #[test]
+    fn test_should_colorize_always_overrides_no_color_and_pipe() {
+        assert!(should_colorize(ColorChoice::Always, true, false));
+        assert!(should_colorize(ColorChoice::Always, false, false));
+    }
+
+    #[test]
+    fn test_should_colorize_never() {
+        assert!(!should_colorize(ColorChoice::Never, false, true));
+        assert!(!should_colorize(ColorChoice::Never, true, true));
+    }
+
     #[test]
     fn test_themes_have_all_colors() {
         // Verify all themes have properly defined colors
-        for theme in [&CATPPUCCIN, &NORD, &DRACULA, &DEFAULT] {
+        for theme in [&CATPPUCCIN, &NORD, &DRACULA, &GRUVBOX, &SOLARIZED, &DEFAULT] {
             // Just access each field to ensure they exist
             let _ = theme.text;
             let _ = theme.green;
@@ -0,0 +1,181 @@
+use anyhow::Result;
+
+use crate::jj::types::{BookmarkSyncState, ChangeWithStatus};
+
+/// Placeholders a `jf status --format` template may use
+pub const PLACEHOLDERS: &[&str] = &["short_id", "change_id", "commit_id", "bookmark", "sync", "desc", "author"];
+
+/// A per-change line template, e.g. `"{short_id} {sync} {bookmark} {desc}"`.
+/// `{{` and `}}` stand for literal braces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineFormat {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Field(&'static str),
+}
+
+impl LineFormat {
+    /// Parse a template, rejecting unknown placeholders and unbalanced braces
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => anyhow::bail!("Unclosed '{{{}' in format", name),
+                        }
+                    }
+                    let Some(field) = PLACEHOLDERS.iter().find(|p| **p == name) else {
+                        anyhow::bail!(
+                            "Unknown placeholder '{{{}}}' (expected one of: {})",
+                            name,
+                            PLACEHOLDERS.join(", ")
+                        );
+                    };
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field(field));
+                }
+                '}' => anyhow::bail!("Unmatched '}}' in format (use '}}}}' for a literal brace)"),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Render the template for one change
+    pub fn render(&self, item: &ChangeWithStatus) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(text) => text.clone(),
+                Part::Field(field) => field_value(item, field),
+            })
+            .collect()
+    }
+}
+
+/// Value of a placeholder for a change; empty when the change has none (e.g. no bookmark)
+fn field_value(item: &ChangeWithStatus, field: &str) -> String {
+    let change = &item.change;
+    match field {
+        "short_id" => change.change_id[..8.min(change.change_id.len())].to_string(),
+        "change_id" => change.change_id.clone(),
+        "commit_id" => change.commit_id.clone(),
+        "bookmark" => item.bookmark.clone().unwrap_or_default(),
+        "sync" => sync_label(&item.sync_state).unwrap_or_default(),
+        "desc" => change.description.lines().next().unwrap_or("").to_string(),
+        "author" => {
+            if change.author.name.is_empty() {
+                change.author.email.clone()
+            } else {
+                change.author.name.clone()
+            }
+        }
+        _ => String::new(),
+    }
+}
+
+/// Sync state in words, e.g. "ahead 2" (None without a bookmark)
+pub fn sync_label(state: &BookmarkSyncState) -> Option<String> {
+    match state {
+        BookmarkSyncState::NoBookmark => None,
+        BookmarkSyncState::LocalOnly => Some("local only".to_string()),
+        BookmarkSyncState::Synced => Some("synced".to_string()),
+        BookmarkSyncState::Ahead { count } => Some(format!("ahead {}", count)),
+        BookmarkSyncState::Behind { count } => Some(format!("behind {}", count)),
+        BookmarkSyncState::Diverged { local_ahead, remote_ahead, .. } => {
+            Some(format!("diverged +{} -{}", local_ahead, remote_ahead))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item() -> ChangeWithStatus {
+        serde_json::from_value(serde_json::json!({
+            "change": {
+                "change_id": "qpvuntsmwlqtpkxy",
+                "commit_id": "abc123def456",
+                "description": "Add widgets\n\nWith a body",
+                "author": {"name": "Alice", "email": "alice@example.com"},
+            },
+            "bookmark": "jf/widgets",
+            "is_working": false,
+            "has_remote": true,
+            "sync_state": {"Ahead": {"count": 2}},
+            "is_empty": false,
+            "is_immutable": false,
+            "additions": 0,
+            "deletions": 0,
+        }))
+        .unwrap()
+    }
+
+    fn render(template: &str, item: &ChangeWithStatus) -> String {
+        LineFormat::parse(template).unwrap().render(item)
+    }
+
+    #[test]
+    fn test_each_placeholder() {
+        let item = item();
+        assert_eq!(render("{short_id}", &item), "qpvuntsm");
+        assert_eq!(render("{change_id}", &item), "qpvuntsmwlqtpkxy");
+        assert_eq!(render("{commit_id}", &item), "abc123def456");
+        assert_eq!(render("{bookmark}", &item), "jf/widgets");
+        assert_eq!(render("{sync}", &item), "ahead 2");
+        assert_eq!(render("{desc}", &item), "Add widgets");
+        assert_eq!(render("{author}", &item), "Alice");
+    }
+
+    #[test]
+    fn test_template_with_literals() {
+        assert_eq!(
+            render("{short_id} [{sync}] {bookmark}: {desc}", &item()),
+            "qpvuntsm [ahead 2] jf/widgets: Add widgets"
+        );
+        assert_eq!(render("{{{short_id}}}", &item()), "{qpvuntsm}");
+    }
+
+    #[test]
+    fn test_missing_fields_render_empty() {
+        let mut item = item();
+        item.bookmark = None;
+        item.sync_state = BookmarkSyncState::NoBookmark;
+        item.change.author.name = String::new();
+        assert_eq!(render("{bookmark}|{sync}|{author}", &item), "||alice@example.com");
+    }
+
+    #[test]
+    fn test_unknown_placeholder_rejected() {
+        let err = LineFormat::parse("{short_id} {branch}").unwrap_err();
+        assert!(err.to_string().contains("'{branch}'"));
+        assert!(LineFormat::parse("{desc").is_err());
+        assert!(LineFormat::parse("desc}").is_err());
+    }
+}
@@ -15,9 +15,11 @@ pub struct IconSet {
     pub pr_open: &'static str,
     pub pr_approved: &'static str,
     pub pr_merged: &'static str,
+    pub pr_closed: &'static str,
     pub ci_running: &'static str,
     pub ci_passed: &'static str,
     pub ci_failed: &'static str,
+    pub locked: &'static str,
     
     // Actions
     pub ready: &'static str,
@@ -29,6 +31,9 @@ pub struct IconSet {
     pub warning: &'static str,
     pub error: &'static str,
     pub info: &'static str,
+
+    // Progress
+    pub spinner: &'static [&'static str],
 }
 
 pub const UNICODE_ICONS: IconSet = IconSet {
@@ -47,9 +52,11 @@ pub const UNICODE_ICONS: IconSet = IconSet {
     pr_open: "◈",
     pr_approved: "✓",
     pr_merged: "✔",
+    pr_closed: "⊘",
     ci_running: "⟳",
     ci_passed: "✓",
     ci_failed: "✗",
+    locked: "🔒",
     
     // Actions
     ready: "◉",
@@ -61,6 +68,9 @@ pub const UNICODE_ICONS: IconSet = IconSet {
     warning: "⚠",
     error: "✗",
     info: "ℹ",
+
+    // Progress
+    spinner: &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"],
 };
 
 pub const ASCII_ICONS: IconSet = IconSet {
@@ -79,9 +89,11 @@ pub const ASCII_ICONS: IconSet = IconSet {
     pr_open: "PR",
     pr_approved: "OK",
     pr_merged: "++",
+    pr_closed: "--",
     ci_running: "~~",
     ci_passed: "OK",
     ci_failed: "XX",
+    locked: "[ro]",
 
     // Actions
     ready: "!",
@@ -93,6 +105,9 @@ pub const ASCII_ICONS: IconSet = IconSet {
     warning: "!",
     error: "X",
     info: "i",
+
+    // Progress
+    spinner: &["|", "/", "-", "\\"],
 };
 
 pub const NERDFONT_ICONS: IconSet = IconSet {
@@ -111,9 +126,11 @@ pub const NERDFONT_ICONS: IconSet = IconSet {
     pr_open: "\u{f407}",       //  pull request
     pr_approved: "\u{f058}",   //  check circle
     pr_merged: "\u{e727}",     //  git merge
+    pr_closed: "\u{f05e}",     //  ban
     ci_running: "\u{f021}",    //  sync/refresh
     ci_passed: "\u{f00c}",     //  check
     ci_failed: "\u{f00d}",     //  times
+    locked: "\u{f023}",        //  lock
 
     // Actions
     ready: "\u{f058}",    //  check circle
@@ -125,16 +142,57 @@ pub const NERDFONT_ICONS: IconSet = IconSet {
     warning: "\u{f071}",    //  warning triangle
     error: "\u{f057}",      //  times circle
     info: "\u{f05a}",       //  info circle
+
+    // Progress
+    spinner: &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"],
 };
 
+/// Icon set names `get_icon_set` knows (anything else falls back to unicode)
+pub const ICON_SET_NAMES: &[&str] = &["unicode", "ascii", "nerdfont", "nerd", "auto"];
+
 pub fn get_icon_set(style: &str) -> &'static IconSet {
     match style {
         "ascii" => &ASCII_ICONS,
         "nerdfont" | "nerd" => &NERDFONT_ICONS,
+        "auto" => detect_icon_set(|name| std::env::var(name).ok()),
         _ => &UNICODE_ICONS,
     }
 }
 
+/// Guess the icon set the terminal can draw from its environment (`env` looks up a
+/// variable). `JF_NERDFONT` decides outright; otherwise terminals that bundle nerd font
+/// symbols get nerdfont, dumb terminals and non-UTF-8 locales get ascii, and anything
+/// else gets unicode.
+fn detect_icon_set(env: impl Fn(&str) -> Option<String>) -> &'static IconSet {
+    let set = |name: &str| env(name).filter(|value| !value.is_empty());
+
+    if let Some(hint) = set("JF_NERDFONT") {
+        if matches!(hint.to_lowercase().as_str(), "1" | "true" | "yes") {
+            return &NERDFONT_ICONS;
+        }
+    }
+
+    let term = set("TERM").unwrap_or_default();
+    // The first locale variable set wins, as in setlocale
+    let locale = set("LC_ALL").or_else(|| set("LC_CTYPE")).or_else(|| set("LANG"));
+    let utf8 = locale.map(|locale| {
+        let locale = locale.to_lowercase();
+        locale.contains("utf-8") || locale.contains("utf8")
+    });
+    if term == "dumb" || utf8 == Some(false) {
+        return &ASCII_ICONS;
+    }
+
+    // These ship nerd font symbols as a fallback font, so the glyphs always render
+    let bundles_nerd_symbols =
+        term == "xterm-kitty" || term == "wezterm" || set("TERM_PROGRAM").is_some_and(|p| p == "WezTerm");
+    if bundles_nerd_symbols && set("JF_NERDFONT").is_none() {
+        return &NERDFONT_ICONS;
+    }
+
+    &UNICODE_ICONS
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -187,6 +245,8 @@ mod tests {
             assert!(!icons.lightbulb.is_empty());
             assert!(!icons.info.is_empty());
             assert!(!icons.error.is_empty());
+            assert!(!icons.locked.is_empty());
+            assert!(!icons.spinner.is_empty());
         }
     }
 
@@ -197,5 +257,36 @@ mod tests {
         assert!(icons.change.is_ascii());
         assert!(icons.main.is_ascii());
         assert!(icons.pipe.is_ascii());
+        assert!(icons.spinner.iter().all(|frame| frame.is_ascii()));
+    }
+
+    fn detect(vars: &[(&str, &str)]) -> &'static IconSet {
+        detect_icon_set(|name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn test_auto_icons_detection() {
+        // Unsure: unicode
+        assert_eq!(detect(&[]).working, UNICODE_ICONS.working);
+        assert_eq!(detect(&[("TERM", "xterm-256color"), ("LANG", "en_US.UTF-8")]).working, UNICODE_ICONS.working);
+
+        // An explicit hint wins either way
+        assert_eq!(detect(&[("JF_NERDFONT", "1"), ("TERM", "xterm-256color")]).working, NERDFONT_ICONS.working);
+        assert_eq!(detect(&[("JF_NERDFONT", "0"), ("TERM", "xterm-kitty")]).working, UNICODE_ICONS.working);
+
+        // Terminals bundling nerd font symbols
+        assert_eq!(detect(&[("TERM", "xterm-kitty")]).working, NERDFONT_ICONS.working);
+        assert_eq!(detect(&[("TERM_PROGRAM", "WezTerm")]).working, NERDFONT_ICONS.working);
+
+        // Terminals that can't draw unicode at all
+        assert_eq!(detect(&[("TERM", "dumb")]).working, ASCII_ICONS.working);
+        assert_eq!(detect(&[("LANG", "C")]).working, ASCII_ICONS.working);
+        // LC_ALL overrides LANG
+        assert_eq!(detect(&[("LC_ALL", "C"), ("LANG", "en_US.UTF-8")]).working, ASCII_ICONS.working);
+        assert_eq!(detect(&[("LC_ALL", "en_US.utf8"), ("LANG", "C")]).working, UNICODE_ICONS.working);
     }
 }
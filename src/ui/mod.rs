@@ -1,7 +1,11 @@
 pub mod colors;
+pub mod format;
 pub mod icons;
 pub mod render;
+pub mod text;
+pub mod time;
 
-pub use colors::{get_theme, Theme};
-pub use icons::{get_icon_set, IconSet};
+pub use colors::{apply_color_choice, get_theme, ColorChoice, Theme, THEME_NAMES};
+pub use format::LineFormat;
+pub use icons::{get_icon_set, IconSet, ICON_SET_NAMES};
 pub use render::Renderer;
@@ -1,19 +1,251 @@
-use colored::Colorize;
-use crate::jj::types::{BookmarkSyncState, ChangeWithStatus};
-use super::{IconSet, Theme};
+use anyhow::Result;
+use colored::{Color, ColoredString, Colorize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use crate::config::Config;
+use crate::jj::types::{BookmarkSyncState, ChangeWithStatus, PrState, TrackedBookmark};
+use super::format::{sync_label, LineFormat};
+use super::text::{truncate_to_width, wrap_to_width};
+use super::time::format_timestamp_relative;
+use super::{get_icon_set, get_theme, IconSet, Theme};
+
+/// Box width when the terminal width can't be detected
+const FALLBACK_BOX_WIDTH: usize = 60;
+
+/// Upper bound for the auto-detected box width
+const MAX_BOX_WIDTH: usize = 80;
+
+/// Changes touching more lines than this get their diff stat highlighted
+const LARGE_CHANGE_LINES: usize = 400;
+
+/// Index into a palette of `len` colors for an author, from an FNV-1a hash of their
+/// email so the same author gets the same color on every run and machine
+fn author_color_index(email: &str, len: usize) -> usize {
+    let hash = email
+        .trim()
+        .to_lowercase()
+        .bytes()
+        .fold(0x811c9dc5u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x01000193));
+    hash as usize % len
+}
+
+/// Default box width: terminal width - 2, capped at 80 (60 if undetectable)
+fn detect_box_width() -> usize {
+    box_width_for(terminal_columns())
+}
+
+/// Terminal width in columns: `COLUMNS` when set to a number (as git honors it,
+/// so tests and CI get stable output), otherwise the detected width
+fn terminal_columns() -> Option<usize> {
+    parse_columns(std::env::var("COLUMNS").ok().as_deref())
+        .or_else(|| console::Term::stdout().size_checked().map(|(_rows, cols)| cols as usize))
+}
+
+fn parse_columns(value: Option<&str>) -> Option<usize> {
+    value?.trim().parse().ok().filter(|cols| *cols > 0)
+}
+
+fn box_width_for(columns: Option<usize>) -> usize {
+    columns
+        .map(|cols| cols.saturating_sub(2).min(MAX_BOX_WIDTH))
+        .filter(|width| *width > 0)
+        .unwrap_or(FALLBACK_BOX_WIDTH)
+}
 
 pub struct Renderer {
     theme: &'static Theme,
     icons: &'static IconSet,
+    /// Current user's email; changes by other authors show their name
+    current_user: Option<String>,
+    /// Width of the status box in columns
+    width: usize,
+    /// Text-only output: no icons, box drawing, or color (accessibility mode)
+    plain: bool,
+    /// Show `+X/-Y` line counts after each description
+    show_stats: bool,
+    /// Suppress info, success, and spinners so stdout carries only machine output
+    quiet: bool,
+    /// Spell out sync states with distinct shapes and labels instead of relying on color
+    colorblind: bool,
+    /// Show each change's description body under its subject
+    full_descriptions: bool,
+    /// Cluster the stack under the bookmark (PR) each change lands with
+    grouped: bool,
+    /// Bookmarks whose PRs are merged, for the `jf land` suggestion
+    merged_bookmarks: Vec<String>,
+    /// Most commits per side drawn as glyphs in a diverged bookmark's fork
+    max_fork_glyphs: usize,
+    /// Tint each change by its author, with a legend under the box
+    author_colors: bool,
+    /// Whether a spinner frame is on the current line; held while drawing or printing over it
+    spinner_line: Mutex<bool>,
+}
+
+/// A run of stack changes that land together: a bookmarked change and the
+/// unbookmarked ancestors below it, or the unbookmarked changes above the top bookmark
+#[derive(Debug)]
+pub struct StackGroup<'a> {
+    /// The PR's bookmark, None for changes not in any PR yet
+    pub bookmark: Option<&'a str>,
+    /// Changes in stack order (head first)
+    pub changes: &'a [ChangeWithStatus],
+}
+
+/// A next step the stack footer offers, chosen from the stack's state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Suggestion {
+    /// A change has conflicts
+    ResolveConflicts,
+    /// A bookmark's PR is merged
+    Land,
+    /// A bookmark is behind its remote
+    Pull,
+    /// A change (other than the working copy) has no bookmark
+    Push,
+}
+
+/// Suggestions for `changes`, most urgent first. `merged` names bookmarks whose
+/// PRs are known to be merged.
+pub fn suggest(changes: &[ChangeWithStatus], merged: &[String]) -> Vec<Suggestion> {
+    let mut suggestions = Vec::new();
+    if changes.iter().any(|c| c.change.has_conflict) {
+        suggestions.push(Suggestion::ResolveConflicts);
+    }
+    if changes.iter().any(|c| c.bookmark.as_ref().is_some_and(|b| merged.contains(b))) {
+        suggestions.push(Suggestion::Land);
+    }
+    if changes.iter().any(|c| matches!(c.sync_state, BookmarkSyncState::Behind { .. })) {
+        suggestions.push(Suggestion::Pull);
+    }
+    if changes.iter().any(|c| c.bookmark.is_none() && !c.is_working) {
+        suggestions.push(Suggestion::Push);
+    }
+    suggestions
+}
+
+/// Split a head-first stack into groups by the nearest descendant bookmark
+pub fn group_by_bookmark(changes: &[ChangeWithStatus]) -> Vec<StackGroup<'_>> {
+    let mut groups = Vec::new();
+    let mut start = 0;
+    for i in 1..=changes.len() {
+        if i == changes.len() || changes[i].bookmark.is_some() {
+            groups.push(StackGroup {
+                bookmark: changes[start].bookmark.as_deref(),
+                changes: &changes[start..i],
+            });
+            start = i;
+        }
+    }
+    groups
 }
 
 impl Renderer {
     pub fn new(theme: &'static Theme, icons: &'static IconSet) -> Self {
-        Self { theme, icons }
+        Self {
+            theme,
+            icons,
+            current_user: None,
+            width: detect_box_width(),
+            plain: false,
+            show_stats: false,
+            quiet: false,
+            colorblind: false,
+            full_descriptions: false,
+            grouped: false,
+            merged_bookmarks: Vec::new(),
+            max_fork_glyphs: 10,
+            author_colors: false,
+            spinner_line: Mutex::new(false),
+        }
+    }
+
+    /// Create a renderer using the theme, icons, and box width from config
+    pub fn from_config(config: &Config) -> Self {
+        let theme = get_theme(&config.display.theme);
+        let icons = get_icon_set(&config.display.icons);
+        Self::new(theme, icons)
+            .with_box_width(config.display.box_width)
+            .with_colorblind(config.display.colorblind)
+            .with_max_fork_glyphs(config.display.max_fork_glyphs)
+            .with_quiet(config.quiet)
+    }
+
+    /// Override the box width (None keeps the width from `COLUMNS` or the terminal)
+    pub fn with_box_width(mut self, width: Option<usize>) -> Self {
+        if let Some(width) = width {
+            self.width = width;
+        }
+        self
+    }
+
+    /// Show author names on changes not authored by `email`
+    pub fn with_current_user(mut self, email: Option<String>) -> Self {
+        self.current_user = email;
+        self
+    }
+
+    /// Label sync states ("AHEAD 3", "BEHIND 2", "DIVERGED") alongside their color
+    pub fn with_colorblind(mut self, colorblind: bool) -> Self {
+        self.colorblind = colorblind;
+        self
+    }
+
+    /// Summarize diverged bookmarks with more than `max` commits on either side
+    /// instead of drawing one glyph per commit
+    pub fn with_max_fork_glyphs(mut self, max: usize) -> Self {
+        self.max_fork_glyphs = max;
+        self
+    }
+
+    /// Color each change's ID and subject by author (stable per email)
+    pub fn with_author_colors(mut self, author_colors: bool) -> Self {
+        self.author_colors = author_colors;
+        self
+    }
+
+    /// Show description bodies under subjects (needs `jj::add_full_descriptions`)
+    pub fn with_full_descriptions(mut self, full: bool) -> Self {
+        self.full_descriptions = full;
+        self
+    }
+
+    /// Group changes under the bookmark (PR) that covers them
+    pub fn with_grouped(mut self, grouped: bool) -> Self {
+        self.grouped = grouped;
+        self
+    }
+
+    /// Bookmarks whose PRs are merged, so the footer can suggest `jf land`
+    pub fn with_merged_bookmarks(mut self, merged: Vec<String>) -> Self {
+        self.merged_bookmarks = merged;
+        self
+    }
+
+    /// Show `+X/-Y` line counts per change (needs `jj::add_diff_stats`)
+    pub fn with_stats(mut self, show_stats: bool) -> Self {
+        self.show_stats = show_stats;
+        self
+    }
+
+    /// Suppress info and success messages, spinners, and the stack shown after a
+    /// command (e.g., for `--json` or `--quiet`); errors and warnings still print
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Emit text only: no icons, box drawing, or color
+    pub fn with_plain(mut self, plain: bool) -> Self {
+        self.plain = plain;
+        self
     }
     
     /// Render the stack status
     pub fn render_stack(&self, changes: &[ChangeWithStatus], main_ref: &str) {
+        if self.quiet {
+            return;
+        }
         let total = changes.len();
 
         println!();
@@ -29,7 +261,21 @@ impl Renderer {
             println!("  No changes in stack");
             println!("  (All work is integrated into {})", main_ref);
         } else {
+            // Group headers, keyed by the index of each group's first change
+            let mut headers = Vec::new();
+            if self.grouped {
+                let mut start = 0;
+                for group in group_by_bookmark(changes) {
+                    headers.push((start, self.format_group_header(&group)));
+                    start += group.changes.len();
+                }
+            }
+
             for (i, item) in changes.iter().enumerate() {
+                if let Some((_, header)) = headers.iter().find(|(start, _)| *start == i) {
+                    println!("{}", header);
+                }
+
                 // Position: 1 is closest to trunk, total is the head
                 let position = total - i;
                 self.render_change(item, position, total);
@@ -51,11 +297,187 @@ impl Renderer {
         self.print_box_bottom();
         println!();
 
+        if let Some(legend) = self.format_author_legend(changes) {
+            println!("{}", legend);
+            println!();
+        }
+
         // Print suggestions
         self.print_suggestions(changes);
     }
     
+    /// Render bookmarks tracking the remote that aren't in the stack (e.g., teammates'
+    /// branches), each with its sync state
+    pub fn render_tracked_bookmarks(&self, bookmarks: &[TrackedBookmark]) {
+        if bookmarks.is_empty() {
+            return;
+        }
+        println!("Tracked remote branches:");
+        for bookmark in bookmarks {
+            let short_id = &bookmark.change_id[..8.min(bookmark.change_id.len())];
+            println!("  {}", short_id.color(self.theme.blue));
+            for line in self.format_sync_state(&bookmark.name, &bookmark.sync_state) {
+                println!("{}", line);
+            }
+        }
+        println!();
+    }
+
+    /// Render each change as a line from `format` (`jf status --format`), without the box
+    pub fn render_stack_formatted(&self, changes: &[ChangeWithStatus], format: &LineFormat) {
+        for item in changes {
+            println!("{}", format.render(item));
+        }
+    }
+
+    /// Render the stack as plain text, one line per change, for screen readers and logs
+    pub fn render_stack_plain(&self, changes: &[ChangeWithStatus]) {
+        if changes.is_empty() {
+            println!("No changes in stack");
+        }
+        for item in changes {
+            println!("{}", Self::format_plain_line(item));
+        }
+    }
+
+    /// Format a plain line: short ID, textual sync state, bookmark, and description
+    fn format_plain_line(item: &ChangeWithStatus) -> String {
+        let change_id = &item.change.change_id[..8.min(item.change.change_id.len())];
+        let mut parts = vec![change_id.to_string()];
+
+        if let Some(bookmark) = &item.bookmark {
+            parts.extend(sync_label(&item.sync_state).map(|state| format!("[{}]", state)));
+            parts.push(bookmark.clone());
+        }
+
+        let description = item.change.description.lines().next().unwrap_or("");
+        parts.push(if description.is_empty() { "(no description)" } else { description }.to_string());
+
+        parts.join(" ")
+    }
+
+    /// Render the stack one line per change, without the box or suggestions
+    pub fn render_stack_compact(&self, changes: &[ChangeWithStatus]) {
+        if changes.is_empty() {
+            println!("No changes in stack");
+        }
+        for item in changes {
+            println!("{}", self.format_compact_line(item));
+        }
+    }
+
+    /// Format a compact line: short ID, sync glyph, bookmark, and description
+    fn format_compact_line(&self, item: &ChangeWithStatus) -> String {
+        let change_id = &item.change.change_id[..8.min(item.change.change_id.len())];
+        let mut parts = vec![change_id.color(self.theme.blue).to_string()];
+
+        if let Some(bookmark) = &item.bookmark {
+            let glyph = match &item.sync_state {
+                BookmarkSyncState::NoBookmark | BookmarkSyncState::LocalOnly => None,
+                state if self.colorblind => self.colorblind_label(state),
+                BookmarkSyncState::Synced => Some("✓".color(self.theme.green)),
+                BookmarkSyncState::Ahead { count } => Some(format!("↑{}", count).color(self.theme.green)),
+                BookmarkSyncState::Behind { count } => Some(format!("↓{}", count).color(self.theme.yellow)),
+                BookmarkSyncState::Diverged { .. } => Some("⚠".color(self.theme.red)),
+            };
+            if let Some(glyph) = glyph {
+                parts.push(glyph.to_string());
+            }
+            parts.push(bookmark.color(self.theme.teal).to_string());
+        }
+
+        let description = item.change.description.lines().next().unwrap_or("");
+        let description = if description.is_empty() { "(no description)" } else { description };
+        parts.push(if item.is_empty {
+            description.dimmed().to_string()
+        } else {
+            description.color(self.theme.text).to_string()
+        });
+
+        if let Some(stat) = self.format_diff_stat(item) {
+            parts.push(stat);
+        }
+
+        parts.join("  ")
+    }
+
+    /// Format the line opening a group: its bookmark (or "not in a PR yet") and size
+    fn format_group_header(&self, group: &StackGroup) -> String {
+        let count = group.changes.len();
+        let size = format!("({} change{})", count, if count == 1 { "" } else { "s" });
+        match group.bookmark {
+            Some(bookmark) => format!(
+                "  {} {} {}",
+                self.icons.bookmark.color(self.theme.teal),
+                bookmark.color(self.theme.teal).bold(),
+                size.color(self.theme.subtext)
+            ),
+            None => format!("  {} {}", "not in a PR yet".dimmed(), size.color(self.theme.subtext)),
+        }
+    }
+
     fn render_change(&self, item: &ChangeWithStatus, position: usize, total: usize) {
+        // Main line with position
+        println!("{}", self.format_change_line(item, position, total));
+
+        // Description body, wrapped under the subject
+        for line in self.format_description_body(item) {
+            println!("{}", line);
+        }
+
+        // Bookmark line with sync state and PR marker (if exists)
+        if let Some(bookmark) = &item.bookmark {
+            self.render_sync_state(bookmark, &item.sync_state, item.pr_state);
+        }
+        
+        // Status line (aligned with bookmark line)
+        if let Some(status_msg) = self.format_status(item) {
+            println!("         {}", status_msg);
+        }
+    }
+
+    /// Format the description lines after the subject, wrapped to the box width and
+    /// indented to line up with the bookmark line. Empty unless full descriptions are shown.
+    fn format_description_body(&self, item: &ChangeWithStatus) -> Vec<String> {
+        if !self.full_descriptions {
+            return Vec::new();
+        }
+        let indent = "         ";
+        let width = self.width.saturating_sub(indent.len()).max(20);
+        let body: Vec<&str> = item.change.description.lines().skip(1).collect();
+        let Some(start) = body.iter().position(|line| !line.trim().is_empty()) else {
+            return Vec::new();
+        };
+
+        let mut lines = Vec::new();
+        for line in &body[start..] {
+            if line.trim().is_empty() {
+                // Keep paragraph breaks
+                lines.push(String::new());
+                continue;
+            }
+            for wrapped in wrap_to_width(line, width) {
+                lines.push(format!("{}{}", indent, wrapped.color(self.theme.subtext)));
+            }
+        }
+        lines
+    }
+
+    /// Format `+X/-Y` for a change when stats are shown: dimmed, or yellow when large
+    fn format_diff_stat(&self, item: &ChangeWithStatus) -> Option<String> {
+        if !self.show_stats {
+            return None;
+        }
+        let stat = format!("+{}/-{}", item.additions, item.deletions);
+        Some(if item.additions + item.deletions > LARGE_CHANGE_LINES {
+            stat.color(self.theme.yellow).to_string()
+        } else {
+            stat.dimmed().to_string()
+        })
+    }
+
+    /// Format the main line for a change: position, icon, ID, and description
+    fn format_change_line(&self, item: &ChangeWithStatus, position: usize, total: usize) -> String {
         let is_working = item.is_working;
 
         // Icon
@@ -74,128 +496,232 @@ impl Renderer {
         // Position marker (e.g., "3/5")
         let position_marker = format!("{}/{}", position, total).color(self.theme.overlay);
 
+        // Author tint for the ID and subject (with --author-colors)
+        let author_color = self.author_colors.then(|| self.author_color(&item.change.author.email));
+
         // Change ID (first 8 chars)
         let change_id = &item.change.change_id[..8.min(item.change.change_id.len())];
-        let change_id_colored = change_id.color(self.theme.blue);
+        let change_id_colored = change_id.color(author_color.unwrap_or(self.theme.blue));
+
+        // Lock for changes below the immutable boundary
+        let lock = if item.is_immutable {
+            format!("{} ", self.icons.locked.color(self.theme.yellow))
+        } else {
+            String::new()
+        };
+
+        // Relative age (e.g., "2h ago")
+        let age = format_timestamp_relative(&item.change.timestamp)
+            .map(|age| format!("  {}", age.color(self.theme.overlay)))
+            .unwrap_or_default();
+
+        // Author (only when it's someone other than the current user)
+        let author = match &self.current_user {
+            Some(email) if !item.change.is_authored_by(email) => {
+                let name = if item.change.author.name.is_empty() {
+                    &item.change.author.email
+                } else {
+                    &item.change.author.name
+                };
+                format!("  {}", format!("({})", name).color(self.theme.mauve))
+            }
+            _ => String::new(),
+        };
+
+        // Description, truncated so the whole line fits inside the box
+        let prefix_width = console::measure_text_width(&format!(
+            "  {}/{} {}  {}  {}",
+            position, total, icon, change_id, lock
+        ));
+        // Line counts (with --stat)
+        let stat = self
+            .format_diff_stat(item)
+            .map(|stat| format!("  {}", stat))
+            .unwrap_or_default();
 
-        // Description
+        let suffix_width = console::measure_text_width(&stat)
+            + console::measure_text_width(&author)
+            + console::measure_text_width(&age);
+        let budget = self.width.saturating_sub(prefix_width + suffix_width);
         let description = item.change.description
             .lines()
             .next()
-            .unwrap_or("(no description)")
-            .color(self.theme.text);
+            .unwrap_or("(no description)");
+        let description = self.style_description(item, &truncate_to_width(description, budget), author_color);
 
-        // Main line with position
-        println!(
-            "  {} {}  {}  {}",
-            position_marker, icon_colored, change_id_colored, description
-        );
-        
-        // Bookmark line with sync state (if exists)
-        if let Some(bookmark) = &item.bookmark {
-            self.render_sync_state(bookmark, &item.sync_state);
+        format!(
+            "  {} {}  {}  {}{}{}{}{}",
+            position_marker,
+            icon_colored,
+            change_id_colored,
+            lock,
+            description,
+            stat,
+            author,
+            age
+        )
+    }
+    
+    /// Render bookmark with sync state visualization, its PR marker on the first line
+    fn render_sync_state(&self, bookmark: &str, sync_state: &BookmarkSyncState, pr_state: Option<PrState>) {
+        let mut lines = self.format_sync_state(bookmark, sync_state);
+        if let (Some(first), Some(state)) = (lines.first_mut(), pr_state) {
+            first.push_str(&format!("  {}", self.format_pr_marker(state)));
         }
-        
-        // Status line (aligned with bookmark line)
-        if let Some(status_msg) = self.format_status(item) {
-            println!("         {}", status_msg);
+        for line in lines {
+            println!("{}", line);
         }
     }
-    
-    /// Render bookmark with sync state visualization
-    fn render_sync_state(&self, bookmark: &str, sync_state: &BookmarkSyncState) {
+
+    /// Format the PR marker for a bookmark, e.g. "◈ open"
+    fn format_pr_marker(&self, state: PrState) -> ColoredString {
+        match state {
+            PrState::Open => format!("{} open", self.icons.pr_open).color(self.theme.blue),
+            PrState::Merged => format!("{} merged", self.icons.pr_merged).color(self.theme.mauve),
+            PrState::Closed => format!("{} closed", self.icons.pr_closed).color(self.theme.overlay),
+        }
+    }
+
+    /// Format the bookmark line(s) under a change in the box view
+    fn format_sync_state(&self, bookmark: &str, sync_state: &BookmarkSyncState) -> Vec<String> {
         let bookmark_icon = self.icons.bookmark.color(self.theme.teal);
         let bookmark_name = bookmark.color(self.theme.teal);
 
         match sync_state {
             BookmarkSyncState::NoBookmark => {
                 // Shouldn't happen since we're called with a bookmark
+                vec![]
             }
             BookmarkSyncState::LocalOnly => {
-                println!(
+                vec![format!(
                     "         {} {} {}",
                     bookmark_icon,
                     bookmark_name,
                     "(local only)".color(self.theme.overlay)
-                );
+                )]
+            }
+            BookmarkSyncState::Diverged { local_ahead, remote_ahead, fork_point }
+                if (*local_ahead).max(*remote_ahead) > self.max_fork_glyphs =>
+            {
+                // Too many commits to draw one by one
+                vec![self.format_diverged_summary(bookmark, *local_ahead, *remote_ahead, fork_point.as_deref())]
+            }
+            BookmarkSyncState::Diverged { local_ahead, remote_ahead, fork_point } => {
+                // Show diverged state with fork visualization
+                self.format_diverged(bookmark, *local_ahead, *remote_ahead, fork_point.as_deref())
+                    .to_vec()
+            }
+            state if self.colorblind => {
+                let label = self.colorblind_label(state).unwrap_or_default();
+                vec![format!("         {} {} {}", bookmark_icon, bookmark_name, label)]
             }
             BookmarkSyncState::Synced => {
-                println!(
+                vec![format!(
                     "         {} {} {}",
                     bookmark_icon,
                     bookmark_name,
                     "✓".color(self.theme.green)
-                );
+                )]
             }
             BookmarkSyncState::Ahead { count } => {
                 // Local is ahead of remote
-                println!(
+                vec![format!(
                     "         {} {} {} {}",
                     bookmark_icon,
                     bookmark_name,
                     format!("↑{}", count).color(self.theme.green),
                     "ahead".color(self.theme.overlay)
-                );
+                )]
             }
             BookmarkSyncState::Behind { count } => {
                 // Local is behind remote
-                println!(
+                vec![format!(
                     "         {} {} {} {}",
                     bookmark_icon,
                     bookmark_name,
                     format!("↓{}", count).color(self.theme.yellow),
                     "behind".color(self.theme.overlay)
-                );
-            }
-            BookmarkSyncState::Diverged { local_ahead, remote_ahead, fork_point } => {
-                // Show diverged state with fork visualization
-                let fork_id = fork_point.as_deref().unwrap_or("???");
-
-                // Fork visualization - the ○ fork point must align with ╭ and ╰:
-                //                   ╭──●──●    local (+2)
-                //       bookmark ───○ abc123
-                //                   ╰──○──○    origin (+1) ⚠ diverged
-
-                // Base indent for bookmark line (9 spaces to align with change_id)
-                let base_indent = "         ";
-
-                // Build the bookmark prefix: "{base_indent}{bookmark_icon} {bookmark_name} ───"
-                let prefix = format!("{}{} {} ───", base_indent, self.icons.bookmark, bookmark);
-                let prefix_width = console::measure_text_width(&prefix);
-
-                // Fork arms (╭ and ╰) start at same column as the ○
-                let fork_indent = " ".repeat(prefix_width);
-
-                // Build chains: ──●──●──● for local, ──○──○──○ for remote
-                let local_chain_dots: Vec<&str> = (0..*local_ahead).map(|_| "●").collect();
-                let local_chain_str = local_chain_dots.join("──");
-                let local_chain = format!("╭──{}    local (+{})", local_chain_str, local_ahead);
-                println!(
-                    "{}{}",
-                    fork_indent,
-                    local_chain.color(self.theme.green)
-                );
-
-                // Fork point with bookmark
-                println!(
-                    "{}○ {}",
-                    prefix.color(self.theme.teal),
-                    fork_id.color(self.theme.overlay)
-                );
-
-                // Remote branch (below fork point)
-                let remote_chain_dots: Vec<&str> = (0..*remote_ahead).map(|_| "○").collect();
-                let remote_chain_str = remote_chain_dots.join("──");
-                let remote_chain = format!("╰──{}    origin (+{}) ⚠ diverged", remote_chain_str, remote_ahead);
-                println!(
-                    "{}{}",
-                    fork_indent,
-                    remote_chain.color(self.theme.red)
-                );
+                )]
             }
         }
     }
 
+    /// Sync state as a distinct shape plus a word, still colored, for colorblind mode
+    fn colorblind_label(&self, sync_state: &BookmarkSyncState) -> Option<ColoredString> {
+        match sync_state {
+            BookmarkSyncState::NoBookmark | BookmarkSyncState::LocalOnly => None,
+            BookmarkSyncState::Synced => Some("● SYNCED".color(self.theme.green)),
+            BookmarkSyncState::Ahead { count } => Some(format!("▲ AHEAD {}", count).color(self.theme.green)),
+            BookmarkSyncState::Behind { count } => Some(format!("▼ BEHIND {}", count).color(self.theme.yellow)),
+            BookmarkSyncState::Diverged { .. } => Some("✖ DIVERGED".color(self.theme.red)),
+        }
+    }
+
+    /// Format the three-line fork visualization for a diverged bookmark.
+    ///
+    /// The ○ fork point must align with ╭ and ╰:
+    ///                   ╭──●──●    local (+2)
+    ///       bookmark ───○ abc123
+    ///                   ╰──○──○    origin (+1) ⚠ diverged
+    fn format_diverged(
+        &self,
+        bookmark: &str,
+        local_ahead: usize,
+        remote_ahead: usize,
+        fork_point: Option<&str>,
+    ) -> [String; 3] {
+        let fork_id = fork_point.unwrap_or("???");
+
+        // Base indent for bookmark line (9 spaces to align with change_id)
+        let base_indent = "         ";
+        let connector = " ───";
+
+        // Fork arms (╭ and ╰) start at the same column as the ○
+        let prefix = format!("{}{} {}{}", base_indent, self.icons.bookmark, bookmark, connector);
+        let fork_indent = " ".repeat(console::measure_text_width(&prefix));
+
+        // Build chains: ──●──●──● for local, ──○──○──○ for remote
+        let local_chain = vec!["●"; local_ahead].join("──");
+        let local_line = format!("╭──{}    local (+{})", local_chain, local_ahead);
+
+        let remote_chain = vec!["○"; remote_ahead].join("──");
+        let flag = if self.colorblind { "✖ DIVERGED" } else { "⚠ diverged" };
+        let remote_line = format!("╰──{}    origin (+{}) {}", remote_chain, remote_ahead, flag);
+
+        [
+            format!("{}{}", fork_indent, local_line.color(self.theme.green)),
+            format!(
+                "{}{} {}{}○ {}",
+                base_indent,
+                self.icons.bookmark.color(self.theme.teal),
+                bookmark.color(self.theme.teal),
+                connector.color(self.theme.teal),
+                fork_id.color(self.theme.overlay)
+            ),
+            format!("{}{}", fork_indent, remote_line.color(self.theme.red)),
+        ]
+    }
+
+    /// Format a diverged bookmark as one line, e.g. "→ feature +500 / -300 ⚠ diverged (from abc123)"
+    fn format_diverged_summary(
+        &self,
+        bookmark: &str,
+        local_ahead: usize,
+        remote_ahead: usize,
+        fork_point: Option<&str>,
+    ) -> String {
+        let flag = if self.colorblind { "✖ DIVERGED" } else { "⚠ diverged" };
+        format!(
+            "         {} {} {} / {} {} {}",
+            self.icons.bookmark.color(self.theme.teal),
+            bookmark.color(self.theme.teal),
+            format!("+{}", local_ahead).color(self.theme.green),
+            format!("-{}", remote_ahead).color(self.theme.red),
+            flag.color(self.theme.red),
+            format!("(from {})", fork_point.unwrap_or("???")).color(self.theme.overlay)
+        )
+    }
+
     fn format_status(&self, item: &ChangeWithStatus) -> Option<String> {
         if item.bookmark.is_none() && !item.is_working {
             Some(format!("{} ready to create PR", self.icons.lightbulb))
@@ -224,7 +750,7 @@ impl Renderer {
     
     fn print_box_top(&self, title: &str) {
         let title_with_padding = format!(" {} ", title);
-        let width: usize = 60;
+        let width = self.width;
         let title_len = console::measure_text_width(&title_with_padding);
         let remaining = width.saturating_sub(title_len + 2);
         let left_padding = remaining / 2;
@@ -238,35 +764,73 @@ impl Renderer {
         );
     }
     
+    /// Style a change's subject: dimmed when empty (e.g., scaffolding), otherwise in
+    /// `author_color` or the text color
+    fn style_description(
+        &self,
+        item: &ChangeWithStatus,
+        description: &str,
+        author_color: Option<Color>,
+    ) -> ColoredString {
+        if item.is_empty {
+            description.dimmed()
+        } else {
+            description.color(author_color.unwrap_or(self.theme.text))
+        }
+    }
+
+    /// Accent color for an author, picked by a stable hash of their email
+    fn author_color(&self, email: &str) -> Color {
+        let palette = [
+            self.theme.blue,
+            self.theme.teal,
+            self.theme.mauve,
+            self.theme.green,
+            self.theme.yellow,
+        ];
+        palette[author_color_index(email, palette.len())]
+    }
+
+    /// Format the legend for `--author-colors`: each author in stack order, in their color
+    fn format_author_legend(&self, changes: &[ChangeWithStatus]) -> Option<String> {
+        if !self.author_colors || changes.is_empty() {
+            return None;
+        }
+        let mut seen: Vec<&str> = Vec::new();
+        let mut names = Vec::new();
+        for item in changes {
+            let author = &item.change.author;
+            if seen.iter().any(|email| email.eq_ignore_ascii_case(&author.email)) {
+                continue;
+            }
+            seen.push(&author.email);
+            let name = if author.name.is_empty() { &author.email } else { &author.name };
+            names.push(format!("{} {}", self.icons.change, name).color(self.author_color(&author.email)).to_string());
+        }
+        Some(format!("  Authors: {}", names.join("  ")))
+    }
+
     fn print_box_bottom(&self) {
-        println!("╰{}╯", "─".repeat(60));
+        println!("╰{}╯", "─".repeat(self.width));
     }
     
     fn print_suggestions(&self, changes: &[ChangeWithStatus]) {
-        let mut suggestions = Vec::new();
-
-        // Check if there are changes without bookmarks
-        let needs_bookmark = changes.iter().any(|c| c.bookmark.is_none() && !c.is_working);
-        if needs_bookmark {
-            suggestions.push(format!(
-                "  {} Push to GitHub: jf push",
-                self.icons.lightbulb
-            ));
+        let suggestions = suggest(changes, &self.merged_bookmarks);
+        if suggestions.is_empty() {
+            return;
         }
 
-        // Suggest pulling
-        suggestions.push(format!(
-            "  {} Update from remote: jf pull",
-            self.icons.info
-        ));
-
-        if !suggestions.is_empty() {
-            println!("{} Quick commands:", self.icons.lightbulb);
-            for suggestion in suggestions {
-                println!("{}", suggestion);
-            }
-            println!();
+        println!("{} Quick commands:", self.icons.lightbulb);
+        for suggestion in suggestions {
+            let (icon, text) = match suggestion {
+                Suggestion::ResolveConflicts => (self.icons.warning, "Resolve conflicts: jj resolve"),
+                Suggestion::Land => (self.icons.lightbulb, "Clean up merged PRs: jf land"),
+                Suggestion::Pull => (self.icons.info, "Update from remote: jf pull"),
+                Suggestion::Push => (self.icons.lightbulb, "Push to GitHub: jf push"),
+            };
+            println!("  {} {}", icon, text);
         }
+        println!();
     }
     
     /// Render error message
@@ -280,19 +844,526 @@ impl Renderer {
     
     /// Render success message
     pub fn success(&self, message: &str) {
-        println!(
+        if let Some(line) = self.format_success(message) {
+            println!("{}", line);
+        }
+    }
+
+    /// Format a success message, or None when quiet
+    fn format_success(&self, message: &str) -> Option<String> {
+        if self.quiet {
+            return None;
+        }
+        Some(format!(
             "{} {}",
             self.icons.pr_approved.color(self.theme.green),
             message.color(self.theme.green)
-        );
+        ))
     }
     
+    /// Run a fallible operation while showing a spinner with `message`.
+    /// The spinner is only drawn when stdout is a terminal, so piped output stays clean.
+    pub fn with_spinner<T>(&self, message: &str, operation: impl FnOnce() -> Result<T>) -> Result<T> {
+        if self.quiet || !console::Term::stdout().is_term() {
+            return operation();
+        }
+
+        let done = AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let term = console::Term::stdout();
+                let mut frames = self.icons.spinner.iter().cycle();
+                while !done.load(Ordering::Relaxed) {
+                    if let Some(frame) = frames.next() {
+                        let mut drawn = self.spinner_line.lock().unwrap();
+                        let _ = term.write_str(&format!("\r{} {}", frame.color(self.theme.blue), message));
+                        *drawn = true;
+                    }
+                    std::thread::sleep(Duration::from_millis(80));
+                }
+                drop(self.clear_spinner_line());
+            });
+
+            let result = operation();
+            done.store(true, Ordering::Relaxed);
+            result
+        })
+    }
+
+    /// Clear a spinner frame off the current line, keeping the spinner from redrawing
+    /// until the returned guard is dropped, so messages don't print over it
+    fn clear_spinner_line(&self) -> MutexGuard<'_, bool> {
+        let mut drawn = self.spinner_line.lock().unwrap();
+        if *drawn {
+            let _ = console::Term::stdout().clear_line();
+            *drawn = false;
+        }
+        drawn
+    }
+
+    /// Render warning message
+    pub fn warning(&self, message: &str) {
+        let _spinner = self.clear_spinner_line();
+        if self.plain {
+            eprintln!("warning: {}", message);
+            return;
+        }
+        eprintln!(
+            "{} {}",
+            self.icons.warning.color(self.theme.yellow),
+            message.color(self.theme.yellow)
+        );
+    }
+
     /// Render info message
     pub fn info(&self, message: &str) {
-        println!(
-            "{} {}",
-            self.icons.info.color(self.theme.blue),
-            message
+        if let Some(line) = self.format_info(message) {
+            let _spinner = self.clear_spinner_line();
+            println!("{}", line);
+        }
+    }
+
+    /// Format an info message, or None when quiet
+    fn format_info(&self, message: &str) -> Option<String> {
+        if self.quiet {
+            return None;
+        }
+        if self.plain {
+            return Some(message.to_string());
+        }
+        Some(format!("{} {}", self.icons.info.color(self.theme.blue), message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jj::types::{Author, Change};
+    use crate::ui::icons::UNICODE_ICONS;
+    use crate::ui::{get_icon_set, get_theme};
+    use colored::Color;
+
+    fn test_item(is_empty: bool, is_immutable: bool) -> ChangeWithStatus {
+        ChangeWithStatus {
+            change: Change {
+                change_id: "abcdefgh12345678".to_string(),
+                commit_id: "def456".to_string(),
+                description: "Add feature".to_string(),
+                author: Author::default(),
+                bookmarks: vec![],
+                timestamp: String::new(),
+                is_empty,
+                committer: Author::default(),
+                has_conflict: false,
+                parents: Vec::new(),
+            },
+            bookmark: None,
+            is_working: false,
+            has_remote: false,
+            sync_state: BookmarkSyncState::NoBookmark,
+            is_empty,
+            is_immutable,
+            additions: 0,
+            deletions: 0,
+            pr_state: None,
+        }
+    }
+
+    fn test_renderer() -> Renderer {
+        Renderer::new(get_theme("default"), get_icon_set("unicode")).with_box_width(Some(80))
+    }
+
+    fn bookmarked(id: &str, bookmark: Option<&str>) -> ChangeWithStatus {
+        let mut item = test_item(false, false);
+        item.change.change_id = id.to_string();
+        item.bookmark = bookmark.map(str::to_string);
+        item
+    }
+
+    #[test]
+    fn test_suggestions_follow_stack_state() {
+        use Suggestion::*;
+        let synced = |name: &str| {
+            let mut item = bookmarked(name, Some(name));
+            item.sync_state = BookmarkSyncState::Synced;
+            item
+        };
+
+        // Everything pushed and up to date: nothing to suggest
+        assert_eq!(suggest(&[synced("a"), synced("b")], &[]), vec![]);
+
+        // Unbookmarked change (not the working copy) -> push
+        assert_eq!(suggest(&[bookmarked("x", None), synced("a")], &[]), vec![Push]);
+        let mut working = bookmarked("wc", None);
+        working.is_working = true;
+        assert_eq!(suggest(&[working, synced("a")], &[]), vec![]);
+
+        // Behind its remote -> pull
+        let mut behind = synced("a");
+        behind.sync_state = BookmarkSyncState::Behind { count: 2 };
+        assert_eq!(suggest(&[behind.clone()], &[]), vec![Pull]);
+
+        // Merged PR -> land
+        assert_eq!(suggest(&[synced("a"), synced("b")], &["b".to_string()]), vec![Land]);
+        assert_eq!(suggest(&[synced("a")], &["other".to_string()]), vec![]);
+
+        // Conflicts come first
+        let mut conflicted = bookmarked("c", None);
+        conflicted.change.has_conflict = true;
+        assert_eq!(
+            suggest(&[conflicted, behind, synced("b")], &["b".to_string()]),
+            vec![ResolveConflicts, Land, Pull, Push]
+        );
+    }
+
+    #[test]
+    fn test_group_by_bookmark_boundaries() {
+        // Head first: two unbookmarked on top, then "api" covering one ancestor, then "db"
+        let stack = vec![
+            bookmarked("wip1", None),
+            bookmarked("wip2", None),
+            bookmarked("api", Some("api")),
+            bookmarked("api-base", None),
+            bookmarked("db", Some("db")),
+        ];
+        let groups = group_by_bookmark(&stack);
+        let shape: Vec<(Option<&str>, Vec<&str>)> = groups
+            .iter()
+            .map(|g| (g.bookmark, g.changes.iter().map(|c| c.change.change_id.as_str()).collect()))
+            .collect();
+
+        assert_eq!(
+            shape,
+            vec![
+                (None, vec!["wip1", "wip2"]),
+                (Some("api"), vec!["api", "api-base"]),
+                (Some("db"), vec!["db"]),
+            ]
+        );
+        assert!(group_by_bookmark(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_format_group_header() {
+        let renderer = Renderer::new(get_theme("default"), get_icon_set("ascii"));
+        let stack = [bookmarked("api", Some("api")), bookmarked("base", None), bookmarked("top", None)];
+        let header = |group: &StackGroup| console::strip_ansi_codes(&renderer.format_group_header(group)).to_string();
+
+        let api = StackGroup { bookmark: Some("api"), changes: &stack[..2] };
+        assert_eq!(header(&api), "  -> api (2 changes)");
+        let loose = StackGroup { bookmark: None, changes: &stack[2..] };
+        assert_eq!(header(&loose), "  not in a PR yet (1 change)");
+    }
+
+    #[test]
+    fn test_format_change_line_normal() {
+        let renderer = test_renderer();
+        let item = test_item(false, false);
+        let line = console::strip_ansi_codes(&renderer.format_change_line(&item, 1, 1)).to_string();
+        assert!(line.contains("Add feature"));
+        assert!(!line.contains(UNICODE_ICONS.locked));
+
+        let description = renderer.style_description(&item, "Add feature", None);
+        assert_eq!(description.fgcolor, Some(renderer.theme.text));
+        assert!(!description.style.contains(colored::Styles::Dimmed));
+    }
+
+    #[test]
+    fn test_format_change_line_empty_is_dimmed() {
+        let renderer = test_renderer();
+        let item = test_item(true, false);
+        let line = console::strip_ansi_codes(&renderer.format_change_line(&item, 1, 1)).to_string();
+        assert!(line.contains("Add feature"));
+
+        let description = renderer.style_description(&item, "Add feature", None);
+        assert!(description.style.contains(colored::Styles::Dimmed));
+        assert_eq!(description.fgcolor, None);
+    }
+
+    #[test]
+    fn test_format_change_line_immutable_has_lock() {
+        let renderer = test_renderer();
+        let line = renderer.format_change_line(&test_item(false, true), 1, 1);
+        let line = console::strip_ansi_codes(&line);
+        assert!(line.contains(&format!("{} Add feature", UNICODE_ICONS.locked)));
+    }
+
+    fn compact_line(bookmark: Option<&str>, sync_state: BookmarkSyncState) -> String {
+        let mut item = test_item(false, false);
+        item.bookmark = bookmark.map(str::to_string);
+        item.sync_state = sync_state;
+        test_renderer().format_compact_line(&item)
+    }
+
+    #[test]
+    fn test_format_compact_line_sync_states() {
+        let id = "abcdefgh".color(Color::Blue);
+        let bookmark = "feature".color(Color::Cyan);
+        let desc = "Add feature".color(Color::White);
+
+        assert_eq!(compact_line(None, BookmarkSyncState::NoBookmark), format!("{}  {}", id, desc));
+        assert_eq!(
+            compact_line(Some("feature"), BookmarkSyncState::LocalOnly),
+            format!("{}  {}  {}", id, bookmark, desc)
+        );
+        assert_eq!(
+            compact_line(Some("feature"), BookmarkSyncState::Synced),
+            format!("{}  {}  {}  {}", id, "✓".color(Color::Green), bookmark, desc)
+        );
+        assert_eq!(
+            compact_line(Some("feature"), BookmarkSyncState::Ahead { count: 2 }),
+            format!("{}  {}  {}  {}", id, "↑2".color(Color::Green), bookmark, desc)
+        );
+        assert_eq!(
+            compact_line(Some("feature"), BookmarkSyncState::Behind { count: 1 }),
+            format!("{}  {}  {}  {}", id, "↓1".color(Color::Yellow), bookmark, desc)
+        );
+        let diverged = BookmarkSyncState::Diverged {
+            local_ahead: 1,
+            remote_ahead: 1,
+            fork_point: None,
+        };
+        assert_eq!(
+            compact_line(Some("feature"), diverged),
+            format!("{}  {}  {}  {}", id, "⚠".color(Color::Red), bookmark, desc)
+        );
+    }
+
+    #[test]
+    fn test_full_description_body_wraps_paragraphs() {
+        let mut item = test_item(false, false);
+        item.change.description = "Add feature\n\nThe first paragraph explains why this change exists in some detail.\n\nSecond paragraph.".to_string();
+
+        assert!(test_renderer().format_description_body(&item).is_empty());
+
+        let renderer = test_renderer().with_full_descriptions(true).with_box_width(Some(40));
+        let body: Vec<String> = renderer
+            .format_description_body(&item)
+            .iter()
+            .map(|line| console::strip_ansi_codes(line).to_string())
+            .collect();
+        assert_eq!(
+            body,
+            [
+                "         The first paragraph explains",
+                "         why this change exists in some",
+                "         detail.",
+                "",
+                "         Second paragraph.",
+            ]
         );
+        for line in &body {
+            assert!(console::measure_text_width(line) <= 40);
+        }
+
+        // The subject line is unchanged
+        let line = renderer.format_change_line(&item, 1, 1);
+        assert!(!line.contains("paragraph"));
+    }
+
+    #[test]
+    fn test_pr_marker_glyphs() {
+        let renderer = test_renderer();
+        let text = |state| console::strip_ansi_codes(&renderer.format_pr_marker(state).to_string()).to_string();
+        assert_eq!(text(PrState::Open), "◈ open");
+        assert_eq!(text(PrState::Merged), "✔ merged");
+        assert_eq!(text(PrState::Closed), "⊘ closed");
+
+        let ascii = Renderer::new(get_theme("default"), get_icon_set("ascii"));
+        let text = |state| console::strip_ansi_codes(&ascii.format_pr_marker(state).to_string()).to_string();
+        assert_eq!(text(PrState::Open), "PR open");
+        assert_eq!(text(PrState::Merged), "++ merged");
+        assert_eq!(text(PrState::Closed), "-- closed");
+    }
+
+    #[test]
+    fn test_colorblind_sync_states_have_labels() {
+        let renderer = test_renderer().with_colorblind(true);
+        let text = |state: BookmarkSyncState| {
+            console::strip_ansi_codes(&renderer.format_sync_state("feature", &state).join("\n")).to_string()
+        };
+
+        assert!(text(BookmarkSyncState::Synced).ends_with("feature ● SYNCED"));
+        assert!(text(BookmarkSyncState::Ahead { count: 3 }).ends_with("feature ▲ AHEAD 3"));
+        assert!(text(BookmarkSyncState::Behind { count: 2 }).ends_with("feature ▼ BEHIND 2"));
+        let diverged = BookmarkSyncState::Diverged {
+            local_ahead: 1,
+            remote_ahead: 2,
+            fork_point: None,
+        };
+        assert!(text(diverged).contains("✖ DIVERGED"));
+
+        let mut item = test_item(false, false);
+        item.bookmark = Some("feature".to_string());
+        item.sync_state = BookmarkSyncState::Behind { count: 2 };
+        let compact = console::strip_ansi_codes(&renderer.format_compact_line(&item)).to_string();
+        assert_eq!(compact, "abcdefgh  ▼ BEHIND 2  feature  Add feature");
+    }
+
+    #[test]
+    fn test_with_spinner_non_tty_runs_operation() {
+        // Test output is captured, so stdout is not a terminal
+        let renderer = Renderer::new(get_theme("default"), get_icon_set("ascii"));
+        let mut ran = false;
+        let result = renderer.with_spinner("working", || {
+            ran = true;
+            Ok(42)
+        });
+        assert!(ran);
+        assert_eq!(result.unwrap(), 42);
+    }
+
+    #[test]
+    fn test_with_spinner_propagates_error() {
+        let renderer = Renderer::new(get_theme("default"), get_icon_set("ascii"));
+        let result: Result<()> = renderer.with_spinner("working", || anyhow::bail!("boom"));
+        assert_eq!(result.unwrap_err().to_string(), "boom");
+    }
+
+    /// Display column where `marker` starts in `line`, ignoring color codes
+    fn column_of(line: &str, marker: char) -> usize {
+        let plain = console::strip_ansi_codes(line);
+        let offset = plain.find(marker).expect("marker not found");
+        console::measure_text_width(&plain[..offset])
+    }
+
+    #[test]
+    fn test_diverged_arms_align_with_fork_point_cjk_nerdfont() {
+        let renderer = Renderer::new(get_theme("default"), get_icon_set("nerdfont"));
+        let [local, fork, remote] = renderer.format_diverged("jf/添加功能", 2, 1, Some("abc123"));
+
+        // 9 indent + icon + space + "jf/" + four double-width characters + " ───"
+        assert_eq!(column_of(&fork, '○'), 26);
+        assert_eq!(column_of(&local, '╭'), 26);
+        assert_eq!(column_of(&remote, '╰'), 26);
+    }
+
+    #[test]
+    fn test_diverged_collapses_above_max_fork_glyphs() {
+        let renderer = test_renderer().with_max_fork_glyphs(10);
+        let lines = |local_ahead, remote_ahead| {
+            let state = BookmarkSyncState::Diverged {
+                local_ahead,
+                remote_ahead,
+                fork_point: Some("abc123".to_string()),
+            };
+            renderer
+                .format_sync_state("feature", &state)
+                .iter()
+                .map(|line| console::strip_ansi_codes(line).to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // At the threshold: the full chain, one glyph per commit
+        let at = lines(10, 3);
+        assert_eq!(at.len(), 3);
+        assert_eq!(at[0].matches('●').count(), 10);
+
+        // Above it: a single summary line
+        let above = lines(500, 300);
+        assert_eq!(above, ["         → feature +500 / -300 ⚠ diverged (from abc123)"]);
+        assert_eq!(lines(3, 11).len(), 1);
+    }
+
+    #[test]
+    fn test_format_plain_line_is_ascii() {
+        let states = [
+            (None, BookmarkSyncState::NoBookmark),
+            (Some("feature"), BookmarkSyncState::LocalOnly),
+            (Some("feature"), BookmarkSyncState::Synced),
+            (Some("feature"), BookmarkSyncState::Ahead { count: 3 }),
+            (Some("feature"), BookmarkSyncState::Behind { count: 1 }),
+            (
+                Some("feature"),
+                BookmarkSyncState::Diverged {
+                    local_ahead: 2,
+                    remote_ahead: 1,
+                    fork_point: Some("abc123".to_string()),
+                },
+            ),
+        ];
+
+        for (bookmark, sync_state) in states {
+            let mut item = test_item(false, false);
+            item.bookmark = bookmark.map(str::to_string);
+            item.sync_state = sync_state;
+            let line = Renderer::format_plain_line(&item);
+            assert!(line.is_ascii(), "non-ASCII output: {:?}", line);
+        }
+    }
+
+    #[test]
+    fn test_format_plain_line_text() {
+        let mut item = test_item(false, false);
+        item.bookmark = Some("feature".to_string());
+        item.sync_state = BookmarkSyncState::Ahead { count: 3 };
+        assert_eq!(Renderer::format_plain_line(&item), "abcdefgh [ahead 3] feature Add feature");
+
+        item.change.description = String::new();
+        item.bookmark = None;
+        assert_eq!(Renderer::format_plain_line(&item), "abcdefgh (no description)");
+    }
+
+    #[test]
+    fn test_format_diff_stat() {
+        let mut item = test_item(false, false);
+        item.additions = 12;
+        item.deletions = 3;
+        assert_eq!(test_renderer().format_diff_stat(&item), None);
+
+        let renderer = test_renderer().with_stats(true);
+        assert_eq!(renderer.format_diff_stat(&item), Some("+12/-3".dimmed().to_string()));
+        assert!(renderer.format_change_line(&item, 1, 1).contains(&"+12/-3".dimmed().to_string()));
+
+        item.additions = 380;
+        item.deletions = 40;
+        assert_eq!(renderer.format_diff_stat(&item), Some("+380/-40".color(Color::Yellow).to_string()));
+    }
+
+    #[test]
+    fn test_box_width_from_columns() {
+        assert_eq!(box_width_for(parse_columns(Some("80"))), 78);
+        assert_eq!(box_width_for(parse_columns(Some("50"))), 48);
+        // Capped like a detected width
+        assert_eq!(box_width_for(parse_columns(Some("200"))), MAX_BOX_WIDTH);
+        // Unset or unusable COLUMNS falls through to the fallback here (no terminal)
+        assert_eq!(box_width_for(parse_columns(None)), FALLBACK_BOX_WIDTH);
+        assert_eq!(box_width_for(parse_columns(Some("wide"))), FALLBACK_BOX_WIDTH);
+        assert_eq!(box_width_for(parse_columns(Some("0"))), FALLBACK_BOX_WIDTH);
+    }
+
+    #[test]
+    fn test_author_color_mapping_is_stable() {
+        // Pinned values: changing the hash would recolor everyone's authors
+        assert_eq!(author_color_index("alice@example.com", 5), 3);
+        assert_eq!(author_color_index("bob@example.com", 5), 0);
+        assert_eq!(author_color_index("carol@example.com", 5), 4);
+        assert_eq!(author_color_index("dave@example.com", 5), 1);
+        // Same author however the email is written
+        assert_eq!(author_color_index(" Alice@Example.com ", 5), 3);
+
+        let renderer = test_renderer().with_author_colors(true);
+        assert_eq!(renderer.author_color("alice@example.com"), renderer.theme.green);
+        assert_eq!(renderer.author_color("bob@example.com"), renderer.theme.blue);
+    }
+
+    #[test]
+    fn test_quiet_drops_info_and_success() {
+        let renderer = test_renderer();
+        assert!(renderer.format_info("Fetching").unwrap().contains("Fetching"));
+        assert!(renderer.format_success("Done!").unwrap().contains("Done!"));
+
+        let quiet = test_renderer().with_quiet(true);
+        assert_eq!(quiet.format_info("Fetching"), None);
+        assert_eq!(quiet.format_success("Done!"), None);
+    }
+
+    #[test]
+    fn test_quiet_from_config() {
+        let config = Config {
+            quiet: true,
+            ..Config::default()
+        };
+        assert_eq!(Renderer::from_config(&config).format_info("Fetching"), None);
     }
 }
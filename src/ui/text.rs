@@ -0,0 +1,138 @@
+/// Truncate text to fit within `max_width` terminal columns, adding a trailing `…`
+/// when anything was cut. Width is measured with `console::measure_text_width`, so
+/// double-width characters (CJK, emoji) count as two columns.
+pub fn truncate_to_width(text: &str, max_width: usize) -> String {
+    if console::measure_text_width(text) <= max_width {
+        return text.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+
+    // Reserve one column for the ellipsis
+    let budget = max_width - 1;
+    let mut result = String::new();
+    let mut width = 0;
+    for c in text.chars() {
+        let char_width = console::measure_text_width(c.encode_utf8(&mut [0; 4]));
+        if width + char_width > budget {
+            break;
+        }
+        width += char_width;
+        result.push(c);
+    }
+    result.push('…');
+    result
+}
+
+/// Word-wrap text into lines of at most `max_width` terminal columns. Words wider
+/// than a line are split between characters; width is measured like `truncate_to_width`.
+pub fn wrap_to_width(text: &str, max_width: usize) -> Vec<String> {
+    let max_width = max_width.max(1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut width = 0;
+    for word in text.split_whitespace() {
+        let word_width = console::measure_text_width(word);
+        if width > 0 && width + 1 + word_width <= max_width {
+            line.push(' ');
+            line.push_str(word);
+            width += 1 + word_width;
+            continue;
+        }
+        if width > 0 {
+            lines.push(std::mem::take(&mut line));
+            width = 0;
+        }
+        for c in word.chars() {
+            let char_width = console::measure_text_width(c.encode_utf8(&mut [0; 4]));
+            if width > 0 && width + char_width > max_width {
+                lines.push(std::mem::take(&mut line));
+                width = 0;
+            }
+            line.push(c);
+            width += char_width;
+        }
+    }
+    if width > 0 {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_breaks_between_words() {
+        assert_eq!(
+            wrap_to_width("Explain why the stack needs this change", 16),
+            ["Explain why the", "stack needs this", "change"]
+        );
+        assert!(wrap_to_width("", 10).is_empty());
+    }
+
+    #[test]
+    fn test_wrap_splits_long_words_and_cjk() {
+        assert_eq!(wrap_to_width("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+        // Each CJK character is two columns wide
+        for line in wrap_to_width("添加功能以支持堆叠更改的工作流程", 5) {
+            assert!(console::measure_text_width(&line) <= 5);
+        }
+    }
+
+    #[test]
+    fn test_truncate_short_text_unchanged() {
+        assert_eq!(truncate_to_width("Add feature", 20), "Add feature");
+    }
+
+    #[test]
+    fn test_truncate_exact_width_unchanged() {
+        assert_eq!(truncate_to_width("abcde", 5), "abcde");
+    }
+
+    #[test]
+    fn test_truncate_long_text() {
+        assert_eq!(truncate_to_width("abcdefghij", 5), "abcd…");
+    }
+
+    #[test]
+    fn test_truncate_ascii_within_budget() {
+        let text = "Refactor the bookmark sync detection to handle diverged remotes";
+        for budget in 1..text.len() {
+            let truncated = truncate_to_width(text, budget);
+            assert!(console::measure_text_width(&truncated) <= budget);
+            assert!(truncated.ends_with('…'));
+        }
+    }
+
+    #[test]
+    fn test_truncate_cjk_within_budget() {
+        // Each CJK character is two columns wide
+        let text = "添加功能以支持堆叠更改的工作流程";
+        for budget in 1..console::measure_text_width(text) {
+            let truncated = truncate_to_width(text, budget);
+            assert!(console::measure_text_width(&truncated) <= budget);
+        }
+        assert_eq!(truncate_to_width(text, 6), "添加…");
+        // A double-width char that would overflow is dropped rather than split
+        assert_eq!(truncate_to_width(text, 5), "添加…");
+    }
+
+    #[test]
+    fn test_truncate_emoji_within_budget() {
+        let text = "🎉 Ship it 🚀🚀🚀 with confetti 🎊";
+        for budget in 1..console::measure_text_width(text) {
+            let truncated = truncate_to_width(text, budget);
+            assert!(console::measure_text_width(&truncated) <= budget);
+            // Result is always valid UTF-8 made of whole characters from the input
+            assert!(truncated.trim_end_matches('…').chars().all(|c| text.contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_truncate_zero_width() {
+        assert_eq!(truncate_to_width("abc", 0), "");
+    }
+}
@@ -0,0 +1,134 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Parse an ISO 8601 UTC timestamp (e.g., "2024-12-20T14:03:09Z") into Unix seconds
+pub fn parse_timestamp(timestamp: &str) -> Option<i64> {
+    let timestamp = timestamp.trim().trim_end_matches('Z');
+    let (date, time) = timestamp.split_once('T')?;
+
+    let mut date_parts = date.splitn(3, '-');
+    let year: i64 = date_parts.next()?.parse().ok()?;
+    let month: i64 = date_parts.next()?.parse().ok()?;
+    let day: i64 = date_parts.next()?.parse().ok()?;
+
+    let mut time_parts = time.splitn(3, ':');
+    let hour: i64 = time_parts.next()?.parse().ok()?;
+    let minute: i64 = time_parts.next()?.parse().ok()?;
+    // Ignore fractional seconds if present
+    let second: i64 = time_parts.next()?.split('.').next()?.parse().ok()?;
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    Some(days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Current time in Unix seconds
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Format the time elapsed between `then` and `now` (Unix seconds) as e.g. "2h ago"
+pub fn format_relative(then: i64, now: i64) -> String {
+    let elapsed = now.saturating_sub(then).max(0);
+
+    if elapsed < 60 {
+        format!("{}s ago", elapsed)
+    } else if elapsed < 3600 {
+        format!("{}m ago", elapsed / 60)
+    } else if elapsed < 86400 {
+        format!("{}h ago", elapsed / 3600)
+    } else if elapsed < 86400 * 30 {
+        format!("{}d ago", elapsed / 86400)
+    } else if elapsed < 86400 * 365 {
+        format!("{}mo ago", elapsed / (86400 * 30))
+    } else {
+        format!("{}y ago", elapsed / (86400 * 365))
+    }
+}
+
+/// Format an ISO timestamp relative to now (None if the timestamp can't be parsed)
+pub fn format_timestamp_relative(timestamp: &str) -> Option<String> {
+    parse_timestamp(timestamp).map(|then| format_relative(then, now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp_epoch() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+    }
+
+    #[test]
+    fn test_parse_timestamp_known_date() {
+        // 2024-12-20T14:03:09Z
+        assert_eq!(parse_timestamp("2024-12-20T14:03:09Z"), Some(1734703389));
+    }
+
+    #[test]
+    fn test_parse_timestamp_leap_day() {
+        assert_eq!(parse_timestamp("2024-02-29T00:00:00Z"), Some(1709164800));
+    }
+
+    #[test]
+    fn test_parse_timestamp_fractional_seconds() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:01.500Z"), Some(1));
+    }
+
+    #[test]
+    fn test_parse_timestamp_invalid() {
+        assert_eq!(parse_timestamp(""), None);
+        assert_eq!(parse_timestamp("not a timestamp"), None);
+        assert_eq!(parse_timestamp("2024-13-01T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn test_format_relative_seconds() {
+        assert_eq!(format_relative(1000, 1000), "0s ago");
+        assert_eq!(format_relative(1000, 1059), "59s ago");
+    }
+
+    #[test]
+    fn test_format_relative_minutes_boundary() {
+        assert_eq!(format_relative(0, 60), "1m ago");
+        assert_eq!(format_relative(0, 3599), "59m ago");
+    }
+
+    #[test]
+    fn test_format_relative_hours_boundary() {
+        assert_eq!(format_relative(0, 3600), "1h ago");
+        assert_eq!(format_relative(0, 86399), "23h ago");
+    }
+
+    #[test]
+    fn test_format_relative_days_boundary() {
+        assert_eq!(format_relative(0, 86400), "1d ago");
+        assert_eq!(format_relative(0, 86400 * 29), "29d ago");
+    }
+
+    #[test]
+    fn test_format_relative_months_and_years() {
+        assert_eq!(format_relative(0, 86400 * 30), "1mo ago");
+        assert_eq!(format_relative(0, 86400 * 365), "1y ago");
+    }
+
+    #[test]
+    fn test_format_relative_future_clamps_to_zero() {
+        assert_eq!(format_relative(2000, 1000), "0s ago");
+    }
+}
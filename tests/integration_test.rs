@@ -241,6 +241,133 @@ fn test_jf_push_dry_run_with_remote() {
         .success();
 }
 
+#[test]
+fn test_jf_push_revision_warns_about_unpushed_ancestor() {
+    let (repo_dir, _remote_dir) = create_jj_repo_with_remote();
+    create_jflow_config(repo_dir.path());
+
+    // Two-change stack: base -> top
+    std::process::Command::new("jj")
+        .args(["describe", "-m", "Base change"])
+        .current_dir(repo_dir.path())
+        .output()
+        .expect("Failed to describe change");
+
+    std::process::Command::new("jj")
+        .args(["new", "-m", "Top change"])
+        .current_dir(repo_dir.path())
+        .output()
+        .expect("Failed to create change");
+
+    // Pushing only the top warns that its base isn't on the remote
+    let mut cmd = Command::cargo_bin("jf").unwrap();
+    cmd.args(["push", "-r", "@", "--dry-run"])
+        .current_dir(repo_dir.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("have not been pushed"))
+        .stdout(predicate::str::contains("Base change"))
+        .stdout(predicate::str::contains("--with-ancestors"));
+
+    // --with-ancestors includes the base in the plan
+    let mut cmd = Command::cargo_bin("jf").unwrap();
+    cmd.args(["push", "-r", "@", "--with-ancestors", "--dry-run"])
+        .current_dir(repo_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Found 2 change(s)"));
+}
+
+#[test]
+fn test_jf_bookmark_track_shows_sync_state() {
+    let (repo_dir, _remote_dir) = create_jj_repo_with_remote();
+    create_jflow_config(repo_dir.path());
+
+    // Push a bookmarked change, then stop tracking it
+    std::process::Command::new("jj")
+        .args(["describe", "-m", "Feature change"])
+        .current_dir(repo_dir.path())
+        .output()
+        .expect("Failed to describe change");
+    std::process::Command::new("jj")
+        .args(["bookmark", "create", "feature", "-r", "@"])
+        .current_dir(repo_dir.path())
+        .output()
+        .expect("Failed to create bookmark");
+    std::process::Command::new("jj")
+        .args(["git", "push", "--bookmark", "feature", "--allow-new"])
+        .current_dir(repo_dir.path())
+        .output()
+        .expect("Failed to push bookmark");
+
+    let mut cmd = Command::cargo_bin("jf").unwrap();
+    cmd.args(["bookmark", "untrack", "feature"])
+        .current_dir(repo_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Stopped tracking feature@origin"));
+
+    // Tracking again shows the bookmark as synced with the remote
+    let mut cmd = Command::cargo_bin("jf").unwrap();
+    cmd.args(["bookmark", "track", "feature"])
+        .current_dir(repo_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Tracking feature@origin"))
+        .stdout(predicate::str::contains("✓"))
+        .stdout(predicate::str::contains("(local only)").not());
+}
+
+#[test]
+fn test_jf_pull_no_rebase_only_fetches() {
+    let (repo_dir, remote_dir) = create_jj_repo_with_remote();
+    create_jflow_config(repo_dir.path());
+
+    let jj = |dir: &std::path::Path, args: &[&str]| {
+        let output = std::process::Command::new("jj")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .expect("Failed to run jj");
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+
+    // Push a bookmarked change
+    jj(repo_dir.path(), &["describe", "-m", "Feature change"]);
+    jj(repo_dir.path(), &["bookmark", "create", "feature", "-r", "@"]);
+    jj(repo_dir.path(), &["git", "push", "--bookmark", "feature", "--allow-new"]);
+    let commit_before = jj(repo_dir.path(), &["log", "-r", "@", "--no-graph", "-T", "commit_id"]);
+
+    // From another clone, add a commit on top of feature and advance main
+    let other_dir = tempdir().unwrap();
+    let remote_path = remote_dir.path().to_str().unwrap();
+    std::process::Command::new("jj")
+        .args(["git", "clone", remote_path, "."])
+        .current_dir(other_dir.path())
+        .output()
+        .expect("Failed to clone");
+    jj(other_dir.path(), &["config", "set", "--repo", "user.name", "Other User"]);
+    jj(other_dir.path(), &["config", "set", "--repo", "user.email", "other@test.com"]);
+    jj(other_dir.path(), &["bookmark", "track", "feature@origin"]);
+    jj(other_dir.path(), &["new", "feature", "-m", "Follow-up"]);
+    jj(other_dir.path(), &["bookmark", "set", "feature", "-r", "@"]);
+    jj(other_dir.path(), &["new", "main", "-m", "Upstream change"]);
+    jj(other_dir.path(), &["bookmark", "set", "main", "-r", "@"]);
+    jj(other_dir.path(), &["git", "push", "--bookmark", "feature", "--bookmark", "main"]);
+
+    let mut cmd = Command::cargo_bin("jf").unwrap();
+    cmd.args(["pull", "--no-rebase"])
+        .current_dir(repo_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("behind"))
+        .stdout(predicate::str::contains("Rebasing").not());
+
+    // The local change stayed where it was
+    let commit_after = jj(repo_dir.path(), &["log", "-r", "@", "--no-graph", "-T", "commit_id"]);
+    assert_eq!(commit_before, commit_after);
+}
+
 #[test]
 fn test_jf_pull_with_remote() {
     let (repo_dir, _remote_dir) = create_jj_repo_with_remote();
@@ -254,6 +381,104 @@ fn test_jf_pull_with_remote() {
         .stdout(predicate::str::contains("Fetching"));
 }
 
+#[test]
+fn test_jf_push_refuses_stack_over_max_stack() {
+    let (repo_dir, remote_dir) = create_jj_repo_with_remote();
+    create_jflow_config(repo_dir.path());
+
+    for (i, name) in ["one", "two", "three"].iter().enumerate() {
+        if i > 0 {
+            std::process::Command::new("jj")
+                .args(["new"])
+                .current_dir(repo_dir.path())
+                .output()
+                .expect("Failed to create change");
+        }
+        std::process::Command::new("jj")
+            .args(["describe", "-m", &format!("Change {}", name)])
+            .current_dir(repo_dir.path())
+            .output()
+            .expect("Failed to describe change");
+        std::process::Command::new("jj")
+            .args(["bookmark", "create", name, "-r", "@"])
+            .current_dir(repo_dir.path())
+            .output()
+            .expect("Failed to create bookmark");
+    }
+
+    let mut cmd = Command::cargo_bin("jf").unwrap();
+    cmd.args(["push", "--max-stack", "2"])
+        .current_dir(repo_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--max-stack 3"));
+
+    // Nothing but main reached the remote
+    let output = std::process::Command::new("git")
+        .args(["branch", "--list"])
+        .current_dir(remote_dir.path())
+        .output()
+        .expect("Failed to list remote branches");
+    let branches = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(branches.trim().trim_start_matches("* "), "main");
+}
+
+#[test]
+fn test_jf_commands_on_trunk_report_empty_stack() {
+    let (repo_dir, _remote_dir) = create_jj_repo_with_remote();
+    create_jflow_config(repo_dir.path());
+
+    // Put the working copy on main itself
+    std::process::Command::new("jj")
+        .args(["edit", "main"])
+        .current_dir(repo_dir.path())
+        .output()
+        .expect("Failed to edit main");
+
+    for args in [&["push"][..], &["pull"], &["reorder", "--invert"]] {
+        let mut cmd = Command::cargo_bin("jf").unwrap();
+        cmd.args(args)
+            .current_dir(repo_dir.path())
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("You're on main, nothing to do"));
+    }
+}
+
+#[test]
+fn test_jf_sync_dry_run_with_remote() {
+    let (repo_dir, _remote_dir) = create_jj_repo_with_remote();
+    create_jflow_config(repo_dir.path());
+
+    // One bookmarked change and one without a bookmark
+    std::process::Command::new("jj")
+        .args(["describe", "-m", "Bookmarked change"])
+        .current_dir(repo_dir.path())
+        .output()
+        .expect("Failed to describe change");
+
+    std::process::Command::new("jj")
+        .args(["bookmark", "create", "feature-sync", "-r", "@"])
+        .current_dir(repo_dir.path())
+        .output()
+        .expect("Failed to create bookmark");
+
+    std::process::Command::new("jj")
+        .args(["new", "-m", "Unbookmarked change"])
+        .current_dir(repo_dir.path())
+        .output()
+        .expect("Failed to create change");
+
+    let mut cmd = Command::cargo_bin("jf").unwrap();
+    cmd.args(["sync", "--dry-run"])
+        .current_dir(repo_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Fetch from origin"))
+        .stdout(predicate::str::contains("Push feature-sync"))
+        .stdout(predicate::str::contains("Skip"));
+}
+
 #[test]
 fn test_jf_status_with_changes() {
     let dir = create_jj_repo();
@@ -508,3 +733,270 @@ fn test_jf_with_bookmark_special_chars() {
         .success()
         .stdout(predicate::str::contains("feature/add-login"));
 }
+
+#[test]
+fn test_jf_edit_moves_working_copy() {
+    let dir = create_jj_repo();
+    create_jflow_config(dir.path());
+
+    // Two changes: First -> Second (@)
+    std::process::Command::new("jj")
+        .args(["describe", "-m", "First change"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to describe change");
+
+    std::process::Command::new("jj")
+        .args(["new", "-m", "Second change"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to create change");
+
+    let first_id = std::process::Command::new("jj")
+        .args(["log", "-r", "@-", "--no-graph", "-T", "change_id.short()"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to get change id");
+    let first_id = String::from_utf8_lossy(&first_id.stdout).trim().to_string();
+
+    let mut cmd = Command::cargo_bin("jf").unwrap();
+    cmd.args(["edit", &first_id])
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Your Stack"));
+
+    let working = std::process::Command::new("jj")
+        .args(["log", "-r", "@", "--no-graph", "-T", "description"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to get working copy");
+    assert_eq!(String::from_utf8_lossy(&working.stdout).trim(), "First change");
+}
+
+#[test]
+fn test_jf_edit_unknown_revision() {
+    let dir = create_jj_repo();
+    create_jflow_config(dir.path());
+
+    let mut cmd = Command::cargo_bin("jf").unwrap();
+    cmd.args(["edit", "nonexistent-revision"])
+        .current_dir(dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Could not edit"));
+}
+
+#[test]
+fn test_jf_diff_shows_modified_file() {
+    let dir = create_jj_repo();
+    create_jflow_config(dir.path());
+
+    fs::write(dir.path().join("hello.txt"), "hello\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("jf").unwrap();
+    cmd.args(["diff"])
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("hello.txt"));
+
+    let mut cmd = Command::cargo_bin("jf").unwrap();
+    cmd.args(["diff", "--stat"])
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 file changed"));
+}
+
+#[test]
+fn test_jf_diff_unknown_revision() {
+    let dir = create_jj_repo();
+    create_jflow_config(dir.path());
+
+    let mut cmd = Command::cargo_bin("jf").unwrap();
+    cmd.args(["diff", "nonexistent-revision"])
+        .current_dir(dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Could not diff"));
+}
+
+#[test]
+fn test_jf_new_grows_stack() {
+    let dir = create_jj_repo();
+    create_jflow_config(dir.path());
+
+    std::process::Command::new("jj")
+        .args(["describe", "-m", "First change"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to describe change");
+
+    let count_changes = || {
+        let output = std::process::Command::new("jj")
+            .args(["log", "-r", "::@ ~ root()", "--no-graph", "-T", "change_id ++ \"\\n\""])
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to list changes");
+        String::from_utf8_lossy(&output.stdout).lines().count()
+    };
+    let before = count_changes();
+
+    let mut cmd = Command::cargo_bin("jf").unwrap();
+    cmd.args(["new", "-m", "Second change"])
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Second change"));
+
+    assert_eq!(count_changes(), before + 1);
+}
+
+#[test]
+fn test_jf_rebase_onto_second_branch() {
+    let (repo_dir, _remote_dir) = create_jj_repo_with_remote();
+    create_jflow_config(repo_dir.path());
+    let jj = |args: &[&str]| {
+        std::process::Command::new("jj")
+            .args(args)
+            .current_dir(repo_dir.path())
+            .output()
+            .expect("Failed to run jj")
+    };
+
+    // A coworker's branch off main
+    jj(&["describe", "-m", "Coworker change"]);
+    jj(&["bookmark", "create", "coworker", "-r", "@"]);
+
+    // My stack, also off main
+    jj(&["new", "main", "-m", "My base"]);
+    jj(&["new", "-m", "My top"]);
+
+    let mut cmd = Command::cargo_bin("jf").unwrap();
+    cmd.args(["rebase", "coworker"])
+        .current_dir(repo_dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("My top"));
+
+    // The stack now sits on the coworker's change
+    let output = jj(&["log", "-r", "coworker::@", "--no-graph", "-T", "description.first_line() ++ \"\\n\""]);
+    let log = String::from_utf8_lossy(&output.stdout);
+    assert!(log.contains("My base"));
+    assert!(log.contains("Coworker change"));
+}
+
+#[test]
+fn test_jf_rebase_unknown_destination() {
+    let dir = create_jj_repo();
+    create_jflow_config(dir.path());
+
+    std::process::Command::new("jj")
+        .args(["describe", "-m", "A change"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to describe change");
+
+    let mut cmd = Command::cargo_bin("jf").unwrap();
+    cmd.args(["rebase", "no-such-branch"])
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("doesn't resolve"));
+}
+
+#[test]
+fn test_jf_status_marks_one_working_copy_among_shared_prefixes() {
+    let dir = create_jj_repo();
+    create_jflow_config(dir.path());
+
+    // Change IDs use 16 letters, so 17 changes guarantee two share a prefix
+    let mut ids = Vec::new();
+    for i in 1..=17 {
+        std::process::Command::new("jj")
+            .args(["new", "-m", &format!("Change {}", i)])
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to create change");
+        let id = std::process::Command::new("jj")
+            .args(["log", "-r", "@", "--no-graph", "-T", "change_id"])
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to get change id");
+        ids.push(String::from_utf8_lossy(&id.stdout).trim().to_string());
+    }
+
+    // The pair sharing the longest prefix; @ goes on the later one so both stay in the stack
+    let shared = |a: &str, b: &str| a.chars().zip(b.chars()).take_while(|(x, y)| x == y).count();
+    let (_, later) = (0..ids.len())
+        .flat_map(|i| (i + 1..ids.len()).map(move |j| (i, j)))
+        .max_by_key(|&(i, j)| shared(&ids[i], &ids[j]))
+        .unwrap();
+    std::process::Command::new("jj")
+        .args(["edit", &ids[later]])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to edit change");
+
+    let output = Command::cargo_bin("jf")
+        .unwrap()
+        .args(["status", "--icons", "unicode"])
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let working: Vec<&str> = stdout.lines().filter(|line| line.contains('●')).collect();
+    assert_eq!(working.len(), 1, "expected one working copy, got: {:?}", working);
+    assert!(working[0].contains(&ids[later][..8]));
+}
+
+#[test]
+fn test_jf_status_box_width_follows_columns() {
+    let dir = create_jj_repo();
+    create_jflow_config(dir.path());
+
+    std::process::Command::new("jj")
+        .args(["describe", "-m", "A change"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to describe change");
+
+    let output = Command::cargo_bin("jf")
+        .unwrap()
+        .args(["status", "--icons", "unicode"])
+        .env("COLUMNS", "50")
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // COLUMNS=50 gives a 48-column box, drawn between the corners
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let bottom = stdout.lines().find(|line| line.starts_with('╰')).expect("box bottom");
+    assert_eq!(bottom.chars().count(), 48 + 2);
+}
+
+#[test]
+fn test_jf_quiet_new_prints_nothing() {
+    let dir = create_jj_repo();
+    create_jflow_config(dir.path());
+
+    // The stack shown after `jf new` is suppressed along with the chatter
+    let mut cmd = Command::cargo_bin("jf").unwrap();
+    cmd.args(["--quiet", "new", "-m", "Quiet change"])
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+
+    // jf status is for showing the stack, so it still does
+    let mut cmd = Command::cargo_bin("jf").unwrap();
+    cmd.args(["--quiet", "status"])
+        .current_dir(dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Quiet change"));
+}